@@ -0,0 +1,27 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+// The extension module leaves Python symbols to the interpreter which imports it, but the unit test
+// binary runs on its own, so it links libpython of the interpreter found as pyo3 does.
+fn main() {
+    let python = env::var("PYO3_PYTHON").unwrap_or_else(|_| "python3".to_string());
+    let script = "import sysconfig; print(sysconfig.get_config_var('LDVERSION')); print(sysconfig.get_config_var('LIBDIR'))";
+    let mut link_python = String::new();
+
+    if let Ok(output) = Command::new(python).args(["-c", script]).output() {
+        let output = String::from_utf8_lossy(&output.stdout).to_string();
+
+        if let [ld_version, lib_dir] = output.lines().collect::<Vec<_>>()[..] {
+            println!("cargo:rustc-link-search=native={}", lib_dir);
+            link_python = format!("#[link(name = \"python{}\")]\nextern \"C\" {{}}\n", ld_version);
+        }
+    }
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("link_python.rs"), link_python).unwrap();
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=PYO3_PYTHON");
+}
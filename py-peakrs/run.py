@@ -50,16 +50,17 @@ elif len(sys.argv) == 2: ## Input 1 para "file_name.csv" after Python Preview_fi
     file_path = sys.argv[1]    
 
 ## 1000 means validating first row of 1000 partitions as given by the file_path
-csv_vector, csv_meta = pr.get_csv_sample(file_path, 1000) 
+df = pr.get_csv_sample(file_path, 1000)
+csv_meta = df.meta
 
 if len(csv_meta.error_message) > 0:
     print(csv_meta.error_message)
 else: 
     ## Print first 20 sample rows to screen
-    pr.view_csv(csv_vector, csv_meta)
+    df.view()
 
     ## Print all validated rows to a disk file "%Sample.csv"
-    pr.write_csv(csv_vector, csv_meta)
+    df.write_csv()
 
     ## Print validation summary to screen
     print("File Size: " + format(csv_meta.file_size) + " bytes", end =" ")
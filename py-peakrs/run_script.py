@@ -0,0 +1,35 @@
+""" Run an ETL script of peakrs commands, one command per line, e.g.
+
+    df = read_csv("data.csv")
+    df = filter(df, "amount(Float > 100)")
+    summary = groupby(df, "region => count() sum(amount)")
+    write_csv(summary, "summary.csv")
+
+    How to use the app:-
+
+    Python run_script.py your_script.txt
+
+    Add --validate to check the script without running it.
+"""
+
+import sys
+import peakrs as pr
+
+script_file = sys.argv[1]
+
+if "--validate" in sys.argv:
+    error_message = pr.validate_script(script_file)
+
+    for message in error_message:
+        print(message)
+
+    if not error_message:
+        print("The script is valid.")
+
+    sys.exit(1 if error_message else 0)
+
+df = pr.run_script(script_file)
+
+if df.error_message:
+    print(df.error_message)
+    sys.exit(1)
@@ -0,0 +1,447 @@
+use crate::dataframe::Dataframe;
+use crate::{cell_address, cell_per_row, cell_range, parse_real_number, CsvMeta};
+use pyo3::prelude::*;
+
+#[derive(Clone, Debug)]
+pub(crate) enum Expression {
+    Number(f64),
+    Column(usize),
+    Negative(Box<Expression>),
+    Binary(u8, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum DivideByZero {
+    Null,
+    Zero,
+    Error,
+}
+
+#[derive(Clone)]
+pub(crate) struct AddColumnSetting {
+    pub(crate) expression: Expression,
+    pub(crate) column_name: String,
+    pub(crate) decimal_place: i32,
+    pub(crate) divide_by_zero: DivideByZero,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Operand(String),
+    Operator(u8),
+    OpenBracket,
+    CloseBracket,
+}
+
+// A number such as 1e or 2.5E, whose exponent sign follows
+fn is_exponent_start(operand: &str) -> bool {
+    let mantissa = match operand.strip_suffix('e').or_else(|| operand.strip_suffix('E')) {
+        Some(x) => x,
+        None => return false,
+    };
+
+    mantissa.bytes().any(|x| x.is_ascii_digit()) && mantissa.bytes().all(|x| x.is_ascii_digit() || x == b'.')
+}
+
+// Column names with space or operator characters can be quoted by `backtick`
+fn get_token(expression: &str) -> Result<Vec<Token>, String> {
+    let mut token = Vec::new();
+    let mut current_operand = String::new();
+    let mut chars = expression.chars();
+
+    while let Some(x) = chars.next() {
+        match x {
+            '+' | '-' if is_exponent_start(current_operand.trim()) => current_operand.push(x),
+            '+' | '-' | '*' | '/' | '(' | ')' => {
+                if !current_operand.trim().is_empty() {
+                    token.push(Token::Operand(current_operand.trim().to_string()));
+                }
+                current_operand.clear();
+
+                token.push(match x {
+                    '(' => Token::OpenBracket,
+                    ')' => Token::CloseBracket,
+                    _ => Token::Operator(x as u8),
+                });
+            }
+            '`' => {
+                let mut quoted_name = String::new();
+                let mut is_closed = false;
+
+                for y in chars.by_ref() {
+                    if y == '`' {
+                        is_closed = true;
+                        break;
+                    }
+                    quoted_name.push(y);
+                }
+
+                if !is_closed {
+                    return Err(format!("** Missing closing ` in expression {} ** \n", expression));
+                }
+
+                token.push(Token::Operand(quoted_name));
+            }
+            _ => current_operand.push(x),
+        }
+    }
+
+    if !current_operand.trim().is_empty() {
+        token.push(Token::Operand(current_operand.trim().to_string()));
+    }
+
+    Ok(token)
+}
+
+// Recursive descent: expression = term {(+|-) term}, term = factor {(*|/) factor}
+struct ExpressionParser<'a> {
+    token: Vec<Token>,
+    position: usize,
+    csv_meta: &'a CsvMeta,
+}
+
+impl<'a> ExpressionParser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.token.get(self.position)
+    }
+
+    fn parse_expression(&mut self) -> Result<Expression, String> {
+        let mut left = self.parse_term()?;
+
+        while let Some(Token::Operator(op)) = self.peek().cloned() {
+            if op != b'+' && op != b'-' {
+                break;
+            }
+            self.position += 1;
+            let right = self.parse_term()?;
+            left = Expression::Binary(op, Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_term(&mut self) -> Result<Expression, String> {
+        let mut left = self.parse_factor()?;
+
+        while let Some(Token::Operator(op)) = self.peek().cloned() {
+            if op != b'*' && op != b'/' {
+                break;
+            }
+            self.position += 1;
+            let right = self.parse_factor()?;
+            left = Expression::Binary(op, Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_factor(&mut self) -> Result<Expression, String> {
+        let current_token = self.peek().cloned();
+        self.position += 1;
+
+        match current_token {
+            Some(Token::Operator(b'-')) => Ok(Expression::Negative(Box::new(self.parse_factor()?))),
+            Some(Token::Operator(b'+')) => self.parse_factor(),
+            Some(Token::OpenBracket) => {
+                let expression = self.parse_expression()?;
+
+                if self.peek() != Some(&Token::CloseBracket) {
+                    return Err("** Missing closing bracket in expression ** \n".to_string());
+                }
+
+                self.position += 1;
+                Ok(expression)
+            }
+            Some(Token::Operand(operand)) => get_operand(&operand, self.csv_meta),
+            _ => Err("** Incomplete expression ** \n".to_string()),
+        }
+    }
+}
+
+// Column name takes priority, so a column named 2023 is still a column. nan, inf and infinity
+// are not numbers, so they are looked up as columns only.
+fn get_operand(operand: &str, csv_meta: &CsvMeta) -> Result<Expression, String> {
+    if let Some(n) = csv_meta.column_name.iter().position(|x| x == operand) {
+        return Ok(Expression::Column(n));
+    }
+
+    match operand.parse::<f64>() {
+        Ok(x) if x.is_finite() => Ok(Expression::Number(x)),
+        _ => Err(format!("** Column {} is not found ** \n", operand)),
+    }
+}
+
+pub(crate) fn parse_expression(expression: &str, csv_meta: &CsvMeta) -> Result<Expression, String> {
+    let token = get_token(expression)?;
+
+    if token.is_empty() {
+        return Err("** Fail to find any expression ** \n".to_string());
+    }
+
+    let mut parser = ExpressionParser {
+        token,
+        position: 0,
+        csv_meta,
+    };
+
+    let expression = parser.parse_expression()?;
+
+    if parser.position < parser.token.len() {
+        return Err(format!("** Unexpected {:?} in expression ** \n", parser.token[parser.position]));
+    }
+
+    Ok(expression)
+}
+
+// e.g. "amount, qty => multiply(total)" or "amount * (1 + tax_rate) - discount => compute(net_amount)"
+// where add, subtract, multiply and divide apply the operator from left to right over the column list
+pub(crate) fn parse_add_column_setting(setting: &str, csv_meta: &CsvMeta, decimal_place: i32, divide_by_zero: &str) -> Result<AddColumnSetting, String> {
+    let (left, right) = match setting.find("=>") {
+        Some(n) => (setting[..n].trim(), setting[n + 2..].trim()),
+        None => return Err(format!("** Missing => in {} ** \n", setting)),
+    };
+
+    let (function_name, column_name) = match (right.find('('), right.rfind(')')) {
+        (Some(m), Some(n)) if m < n => (right[..m].trim(), right[m + 1..n].trim()),
+        _ => return Err(format!("** {} should be written as math(new_col_name) ** \n", right)),
+    };
+
+    if column_name.is_empty() {
+        return Err("** Fail to find new column name ** \n".to_string());
+    }
+
+    if csv_meta.column_name.iter().any(|x| x == column_name) {
+        return Err(format!("** Column {} already exists ** \n", column_name));
+    }
+
+    let op = match function_name.to_lowercase().as_str() {
+        "add" => b'+',
+        "subtract" => b'-',
+        "multiply" => b'*',
+        "divide" => b'/',
+        "compute" => 0,
+        _ => return Err(format!("** Math function {} is not supported ** \n", function_name)),
+    };
+
+    let expression = if op == 0 {
+        parse_expression(left, csv_meta)?
+    } else {
+        let mut operand = Vec::new();
+
+        for item in left.split(',') {
+            let item = item.trim().trim_matches('`');
+            operand.push(get_operand(item, csv_meta)?);
+        }
+
+        if operand.len() < 2 {
+            return Err(format!("** {} requires at least 2 columns or values ** \n", function_name));
+        }
+
+        let mut operand = operand.into_iter();
+        let mut expression = operand.next().unwrap();
+
+        for current_operand in operand {
+            expression = Expression::Binary(op, Box::new(expression), Box::new(current_operand));
+        }
+
+        expression
+    };
+
+    let divide_by_zero = match divide_by_zero.to_lowercase().as_str() {
+        "null" => DivideByZero::Null,
+        "zero" => DivideByZero::Zero,
+        "error" => DivideByZero::Error,
+        _ => return Err(format!("** Divide by zero policy {} is not supported, use null, zero or error ** \n", divide_by_zero)),
+    };
+
+    Ok(AddColumnSetting {
+        expression,
+        column_name: column_name.to_string(),
+        decimal_place,
+        divide_by_zero,
+    })
+}
+
+// Columns referenced by an expression
+pub(crate) fn expression_column(expression: &Expression, column: &mut Vec<usize>) {
+    match expression {
+        Expression::Number(_) => {}
+        Expression::Column(x) => column.push(*x),
+        Expression::Negative(x) => expression_column(x, column),
+        Expression::Binary(_, x, y) => {
+            expression_column(x, column);
+            expression_column(y, column);
+        }
+    }
+}
+
+// None represents null, which is output as an empty cell
+pub(crate) fn evaluate(expression: &Expression, byte_array: &[u8], cell_address: &[i64], first_cell: usize, divide_by_zero: DivideByZero) -> Result<Option<f64>, String> {
+    match expression {
+        Expression::Number(x) => Ok(Some(*x)),
+        Expression::Column(n) => {
+            let (start_byte, end_byte) = cell_range(byte_array, cell_address, first_cell + n);
+            Ok(parse_real_number(&byte_array[start_byte..end_byte]))
+        }
+        Expression::Negative(x) => Ok(evaluate(x, byte_array, cell_address, first_cell, divide_by_zero)?.map(|x| -x)),
+        Expression::Binary(op, left, right) => {
+            let left = evaluate(left, byte_array, cell_address, first_cell, divide_by_zero)?;
+            let right = evaluate(right, byte_array, cell_address, first_cell, divide_by_zero)?;
+
+            let (left, right) = match (left, right) {
+                (Some(x), Some(y)) => (x, y),
+                _ => return Ok(None),
+            };
+
+            match op {
+                b'+' => Ok(Some(left + right)),
+                b'-' => Ok(Some(left - right)),
+                b'*' => Ok(Some(left * right)),
+                _ => {
+                    if right == 0.0 {
+                        match divide_by_zero {
+                            DivideByZero::Null => Ok(None),
+                            DivideByZero::Zero => Ok(Some(0.0)),
+                            DivideByZero::Error => Err("** Divide by zero ** \n".to_string()),
+                        }
+                    } else {
+                        Ok(Some(left / right))
+                    }
+                }
+            }
+        }
+    }
+}
+
+// decimal_place < 0 outputs whole numbers without decimal point and others in shortest form
+pub(crate) fn format_number(value: f64, decimal_place: i32) -> String {
+    let value = if value == 0.0 { 0.0 } else { value };
+
+    if decimal_place >= 0 {
+        let result = format!("{:.*}", decimal_place as usize, value);
+
+        if result.starts_with('-') && result[1..].bytes().all(|x| x == b'0' || x == b'.') {
+            return result[1..].to_string();
+        }
+
+        result
+    } else if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+pub(crate) fn add_column_byte_array(byte_array: &[u8], csv_meta: &CsvMeta, add_column_setting: &AddColumnSetting) -> Result<Vec<u8>, String> {
+    let cell_address = cell_address(byte_array, csv_meta);
+    let cell_per_row = cell_per_row(csv_meta);
+    let total_row = (cell_address.len() - 1) / cell_per_row;
+    let total_column = csv_meta.total_column as usize;
+    let mut result_bytes = Vec::with_capacity(byte_array.len() + total_row * 8);
+
+    for current_row in 0..total_row {
+        let first_cell = current_row * cell_per_row;
+        let start_byte = cell_address[first_cell] as usize;
+        let end_byte = cell_address[first_cell + cell_per_row] as usize;
+
+        // Line break position after the last cell
+        let line_br_byte = cell_address[first_cell + total_column] as usize - 1;
+
+        let value = evaluate(
+            &add_column_setting.expression,
+            byte_array,
+            &cell_address,
+            first_cell,
+            add_column_setting.divide_by_zero,
+        )?;
+
+        result_bytes.extend_from_slice(&byte_array[start_byte..line_br_byte]);
+        result_bytes.push(csv_meta.delimiter);
+
+        if let Some(x) = value {
+            result_bytes.extend_from_slice(format_number(x, add_column_setting.decimal_place).as_bytes());
+        }
+
+        result_bytes.extend_from_slice(&byte_array[line_br_byte..end_byte]);
+    }
+
+    Ok(result_bytes)
+}
+
+#[pyfunction(decimal_place = "-1", divide_by_zero = "\"null\"")]
+pub fn add_column(df: &Dataframe, setting: &str, decimal_place: i32, divide_by_zero: &str) -> PyResult<Dataframe> {
+    let result = parse_add_column_setting(setting, &df.csv_meta, decimal_place, divide_by_zero).and_then(|add_column_setting| {
+        add_column_byte_array(&df.byte_array, &df.csv_meta, &add_column_setting).map(|x| (x, add_column_setting.column_name))
+    });
+
+    match result {
+        Ok((result_bytes, column_name)) => {
+            let mut csv_meta = df.csv_meta.clone();
+            csv_meta.column_name.push(column_name);
+            csv_meta.total_column += 1;
+            Ok(Dataframe::new(result_bytes, csv_meta))
+        }
+        Err(error_message) => Ok(df.with_error(&error_message)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::test_csv_meta;
+
+    fn compute(setting: &str, text: &[u8], decimal_place: i32, divide_by_zero: &str) -> Result<String, String> {
+        let csv_meta = test_csv_meta(&["a", "b", "unit price"]);
+        let add_column_setting = parse_add_column_setting(setting, &csv_meta, decimal_place, divide_by_zero)?;
+        let result_bytes = add_column_byte_array(text, &csv_meta, &add_column_setting)?;
+        Ok(String::from_utf8(result_bytes).unwrap())
+    }
+
+    fn value(expression: &str, text: &[u8]) -> String {
+        let row = compute(&format!("{} => compute(x)", expression), text, -1, "null").unwrap();
+        row.trim_end().rsplit(',').next().unwrap().to_string()
+    }
+
+    #[test]
+    fn operator_precedence_and_bracket() {
+        assert_eq!(value("a + b * 2", b"1,2,3\n"), "5");
+        assert_eq!(value("(a + b) * 2", b"1,2,3\n"), "6");
+        assert_eq!(value("-(a - b) / 4", b"1,2,3\n"), "0.25");
+        assert_eq!(value("a - -b", b"1,2,3\n"), "3");
+        assert_eq!(value("`unit price` * a", b"2,0,1.5\n"), "3");
+        assert_eq!(value("a * b", b",2,3\n"), "");
+        assert_eq!(compute("a, b, 10 => multiply(x)", b"1,2,3\n", -1, "null").unwrap(), "1,2,3,20\n");
+    }
+
+    #[test]
+    fn exponent_literal() {
+        assert_eq!(value("a * 1e-5", b"100000,0,0\n"), "1");
+        assert_eq!(value("a * 2.5E+2 - 1e2", b"2,0,0\n"), "400");
+        assert_eq!(value("1e3-a", b"1,0,0\n"), "999");
+    }
+
+    #[test]
+    fn non_finite_literal_is_a_column_name() {
+        for literal in ["nan", "inf", "infinity", "1e999"] {
+            let error = compute(&format!("a + {} => compute(x)", literal), b"1,2,3\n", -1, "null").err().unwrap();
+            assert_eq!(error, format!("** Column {} is not found ** \n", literal));
+        }
+    }
+
+    #[test]
+    fn decimal_place() {
+        assert_eq!(compute("a / b => compute(x)", b"1,3,0\n", 2, "null").unwrap(), "1,3,0,0.33\n");
+        assert_eq!(compute("a / b => compute(x)", b"1,3,0\n", 0, "null").unwrap(), "1,3,0,0\n");
+        assert_eq!(compute("a / b => compute(x)", b"-1,1000,0\n", 2, "null").unwrap(), "-1,1000,0,0.00\n");
+        assert_eq!(format_number(1e15, -1), "1000000000000000");
+        assert_eq!(format_number(6.0, -1), "6");
+    }
+
+    #[test]
+    fn divide_by_zero_policy() {
+        assert_eq!(compute("a / b => compute(x)", b"1,0,0\n", -1, "null").unwrap(), "1,0,0,\n");
+        assert_eq!(compute("a / b => compute(x)", b"1,0,0\n", -1, "zero").unwrap(), "1,0,0,0\n");
+        assert_eq!(compute("a / b => compute(x)", b"1,0,0\n", -1, "error").err().unwrap(), "** Divide by zero ** \n");
+        assert!(compute("a / b => compute(x)", b"1,0,0\n", -1, "skip").is_err());
+    }
+}
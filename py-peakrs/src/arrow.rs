@@ -0,0 +1,676 @@
+use crate::dataframe::Dataframe;
+use crate::numeric::{get_numeric_array, get_numeric_type, ArrayData};
+use crate::{cell_per_row, cell_range, unquote_cell, write_csv_cell, CsvMeta};
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::ffi;
+use pyo3::prelude::*;
+use pyo3::AsPyPointer;
+use std::any::Any;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+use std::slice;
+
+// Structs of the Arrow C Data Interface, https://arrow.apache.org/docs/format/CDataInterface.html
+#[repr(C)]
+pub(crate) struct ArrowSchema {
+    format: *const c_char,
+    name: *const c_char,
+    metadata: *const c_char,
+    flags: i64,
+    n_children: i64,
+    children: *mut *mut ArrowSchema,
+    dictionary: *mut ArrowSchema,
+    release: Option<unsafe extern "C" fn(*mut ArrowSchema)>,
+    private_data: *mut c_void,
+}
+
+#[repr(C)]
+pub(crate) struct ArrowArray {
+    length: i64,
+    null_count: i64,
+    offset: i64,
+    n_buffers: i64,
+    n_children: i64,
+    buffers: *mut *const c_void,
+    children: *mut *mut ArrowArray,
+    dictionary: *mut ArrowArray,
+    release: Option<unsafe extern "C" fn(*mut ArrowArray)>,
+    private_data: *mut c_void,
+}
+
+#[repr(C)]
+pub(crate) struct ArrowArrayStream {
+    get_schema: Option<unsafe extern "C" fn(*mut ArrowArrayStream, *mut ArrowSchema) -> c_int>,
+    get_next: Option<unsafe extern "C" fn(*mut ArrowArrayStream, *mut ArrowArray) -> c_int>,
+    get_last_error: Option<unsafe extern "C" fn(*mut ArrowArrayStream) -> *const c_char>,
+    release: Option<unsafe extern "C" fn(*mut ArrowArrayStream)>,
+    private_data: *mut c_void,
+}
+
+const STREAM_CAPSULE_NAME: &[u8] = b"arrow_array_stream\0";
+const ARROW_FLAG_NULLABLE: i64 = 2;
+
+// Integer, floating point, boolean, string and date32 columns can be imported
+const SUPPORTED_FORMAT: [&str; 15] = ["c", "C", "s", "S", "i", "I", "l", "L", "f", "g", "b", "u", "U", "vu", "tdD"];
+
+impl ArrowSchema {
+    fn empty() -> ArrowSchema {
+        ArrowSchema {
+            format: ptr::null(),
+            name: ptr::null(),
+            metadata: ptr::null(),
+            flags: 0,
+            n_children: 0,
+            children: ptr::null_mut(),
+            dictionary: ptr::null_mut(),
+            release: None,
+            private_data: ptr::null_mut(),
+        }
+    }
+}
+
+impl ArrowArray {
+    fn empty() -> ArrowArray {
+        ArrowArray {
+            length: 0,
+            null_count: 0,
+            offset: 0,
+            n_buffers: 0,
+            n_children: 0,
+            buffers: ptr::null_mut(),
+            children: ptr::null_mut(),
+            dictionary: ptr::null_mut(),
+            release: None,
+            private_data: ptr::null_mut(),
+        }
+    }
+}
+
+struct SchemaPrivate {
+    format: CString,
+    name: CString,
+    children: Vec<*mut ArrowSchema>,
+}
+
+fn new_schema(format: &str, name: &str, flags: i64, children: Vec<ArrowSchema>) -> ArrowSchema {
+    let mut private_data = Box::new(SchemaPrivate {
+        format: CString::new(format).unwrap_or_default(),
+        name: CString::new(name).unwrap_or_default(),
+        children: children.into_iter().map(|x| Box::into_raw(Box::new(x))).collect(),
+    });
+
+    ArrowSchema {
+        format: private_data.format.as_ptr(),
+        name: private_data.name.as_ptr(),
+        metadata: ptr::null(),
+        flags,
+        n_children: private_data.children.len() as i64,
+        children: private_data.children.as_mut_ptr(),
+        dictionary: ptr::null_mut(),
+        release: Some(release_schema),
+        private_data: Box::into_raw(private_data) as *mut c_void,
+    }
+}
+
+unsafe extern "C" fn release_schema(schema: *mut ArrowSchema) {
+    if schema.is_null() || (*schema).release.is_none() {
+        return;
+    }
+
+    let private_data = Box::from_raw((*schema).private_data as *mut SchemaPrivate);
+
+    for &child in &private_data.children {
+        if let Some(release) = (*child).release {
+            release(child);
+        }
+        drop(Box::from_raw(child));
+    }
+
+    (*schema).release = None;
+}
+
+// The array owns its buffers, which stay in place until the consumer releases the array
+struct ArrayPrivate {
+    buffer: Vec<Box<dyn Any>>,
+    buffer_pointer: Vec<*const c_void>,
+    children: Vec<*mut ArrowArray>,
+}
+
+impl ArrayPrivate {
+    fn new() -> ArrayPrivate {
+        ArrayPrivate {
+            buffer: Vec::new(),
+            buffer_pointer: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    // None for an absent buffer, e.g. the validity bitmap of a column without null
+    fn push_buffer<T: 'static>(&mut self, buffer: Option<Vec<T>>) {
+        match buffer {
+            Some(buffer) => {
+                self.buffer_pointer.push(buffer.as_ptr() as *const c_void);
+                self.buffer.push(Box::new(buffer));
+            }
+            None => self.buffer_pointer.push(ptr::null()),
+        }
+    }
+}
+
+fn new_array(length: usize, null_count: usize, mut private_data: Box<ArrayPrivate>, children: Vec<ArrowArray>) -> ArrowArray {
+    private_data.children = children.into_iter().map(|x| Box::into_raw(Box::new(x))).collect();
+
+    ArrowArray {
+        length: length as i64,
+        null_count: null_count as i64,
+        offset: 0,
+        n_buffers: private_data.buffer_pointer.len() as i64,
+        n_children: private_data.children.len() as i64,
+        buffers: private_data.buffer_pointer.as_mut_ptr(),
+        children: private_data.children.as_mut_ptr(),
+        dictionary: ptr::null_mut(),
+        release: Some(release_array),
+        private_data: Box::into_raw(private_data) as *mut c_void,
+    }
+}
+
+unsafe extern "C" fn release_array(array: *mut ArrowArray) {
+    if array.is_null() || (*array).release.is_none() {
+        return;
+    }
+
+    let private_data = Box::from_raw((*array).private_data as *mut ArrayPrivate);
+
+    for &child in &private_data.children {
+        if let Some(release) = (*child).release {
+            release(child);
+        }
+        drop(Box::from_raw(child));
+    }
+
+    (*array).release = None;
+}
+
+// Bit n is set for a true value n
+fn bit_packed(value: &[bool]) -> Vec<u64> {
+    let mut bitmap = vec![0u64; value.len().div_ceil(64)];
+
+    for (n, &is_true) in value.iter().enumerate() {
+        if is_true {
+            bitmap[n / 64] |= 1 << (n % 64);
+        }
+    }
+
+    bitmap
+}
+
+// Bit n is set for a valid row n, None if every row is valid
+fn validity_bitmap(null_mask: &[bool]) -> Option<Vec<u64>> {
+    if !null_mask.contains(&true) {
+        return None;
+    }
+
+    Some(bit_packed(&null_mask.iter().map(|x| !x).collect::<Vec<bool>>()))
+}
+
+// A column is int64 or float64 by the same rule as to_numpy, otherwise utf8, an empty cell is null
+fn column_to_arrow(df: &Dataframe, current_column: usize) -> (&'static str, ArrowArray) {
+    let total_row = df.total_row();
+    let mut private_data = Box::new(ArrayPrivate::new());
+
+    if let Some(numeric_type) = get_numeric_type(df, current_column) {
+        let (data, null_mask) = get_numeric_array(df, current_column, numeric_type).into_parts();
+        let null_count = null_mask.iter().filter(|&&x| x).count();
+        private_data.push_buffer(validity_bitmap(&null_mask));
+
+        let format = match data {
+            ArrayData::Integer(x) => {
+                private_data.push_buffer(Some(x));
+                "l"
+            }
+            ArrayData::Float(x) => {
+                private_data.push_buffer(Some(x));
+                "g"
+            }
+            ArrayData::Bool(x) => {
+                private_data.push_buffer(Some(bit_packed(&x)));
+                "b"
+            }
+        };
+
+        return (format, new_array(total_row, null_count, private_data, Vec::new()));
+    }
+
+    let cell_address = df.cell_address();
+    let cell_per_row = cell_per_row(&df.csv_meta);
+    let mut null_mask = Vec::with_capacity(total_row);
+    let mut offset: Vec<i64> = Vec::with_capacity(total_row + 1);
+    let mut data = Vec::new();
+    offset.push(0);
+
+    for current_row in 0..total_row {
+        let (start_byte, end_byte) = cell_range(&df.byte_array, cell_address, current_row * cell_per_row + current_column);
+        let cell = String::from_utf8_lossy(&unquote_cell(&df.byte_array[start_byte..end_byte])).to_string();
+
+        null_mask.push(cell.is_empty());
+        data.extend_from_slice(cell.as_bytes());
+        offset.push(data.len() as i64);
+    }
+
+    let null_count = null_mask.iter().filter(|&&x| x).count();
+    private_data.push_buffer(validity_bitmap(&null_mask));
+
+    // Large utf8 with 64 bit offsets only if the text is over 2GB
+    let format = if data.len() <= i32::MAX as usize {
+        private_data.push_buffer(Some(offset.into_iter().map(|x| x as i32).collect::<Vec<i32>>()));
+        "u"
+    } else {
+        private_data.push_buffer(Some(offset));
+        "U"
+    };
+
+    private_data.push_buffer(Some(data));
+    (format, new_array(total_row, null_count, private_data, Vec::new()))
+}
+
+// The whole dataframe is one record batch, which is handed over by the first get_next
+struct StreamPrivate {
+    column: Vec<(String, &'static str)>,
+    batch: Option<ArrowArray>,
+}
+
+unsafe extern "C" fn stream_get_schema(stream: *mut ArrowArrayStream, out: *mut ArrowSchema) -> c_int {
+    let private_data = &*((*stream).private_data as *const StreamPrivate);
+
+    let children = private_data
+        .column
+        .iter()
+        .map(|(name, format)| new_schema(format, name, ARROW_FLAG_NULLABLE, Vec::new()))
+        .collect();
+
+    ptr::write(out, new_schema("+s", "", 0, children));
+    0
+}
+
+unsafe extern "C" fn stream_get_next(stream: *mut ArrowArrayStream, out: *mut ArrowArray) -> c_int {
+    let private_data = &mut *((*stream).private_data as *mut StreamPrivate);
+
+    match private_data.batch.take() {
+        Some(batch) => ptr::write(out, batch),
+        None => ptr::write(out, ArrowArray::empty()),
+    }
+
+    0
+}
+
+unsafe extern "C" fn stream_get_last_error(_stream: *mut ArrowArrayStream) -> *const c_char {
+    ptr::null()
+}
+
+unsafe extern "C" fn release_stream(stream: *mut ArrowArrayStream) {
+    if stream.is_null() || (*stream).release.is_none() {
+        return;
+    }
+
+    let mut private_data = Box::from_raw((*stream).private_data as *mut StreamPrivate);
+
+    if let Some(batch) = private_data.batch.as_mut() {
+        release_array(batch);
+    }
+
+    (*stream).release = None;
+}
+
+// The capsule frees the stream unless a consumer has moved it out
+unsafe extern "C" fn release_stream_capsule(capsule: *mut ffi::PyObject) {
+    let stream = ffi::PyCapsule_GetPointer(capsule, STREAM_CAPSULE_NAME.as_ptr() as *const c_char) as *mut ArrowArrayStream;
+
+    if stream.is_null() {
+        ffi::PyErr_Clear();
+        return;
+    }
+
+    if let Some(release) = (*stream).release {
+        release(stream);
+    }
+
+    drop(Box::from_raw(stream));
+}
+
+// PyCapsule of an ArrowArrayStream, for pyarrow.table(df), polars.from_arrow(df) or duckdb
+pub(crate) fn export_arrow_stream(py: Python, df: &Dataframe) -> PyResult<PyObject> {
+    let mut column = Vec::new();
+    let mut children = Vec::new();
+
+    for current_column in 0..df.csv_meta.total_column as usize {
+        let (format, array) = column_to_arrow(df, current_column);
+        column.push((df.csv_meta.column_name[current_column].clone(), format));
+        children.push(array);
+    }
+
+    let mut private_data = Box::new(ArrayPrivate::new());
+    private_data.push_buffer::<u64>(None);
+    let batch = new_array(df.total_row(), 0, private_data, children);
+
+    let stream = Box::new(ArrowArrayStream {
+        get_schema: Some(stream_get_schema),
+        get_next: Some(stream_get_next),
+        get_last_error: Some(stream_get_last_error),
+        release: Some(release_stream),
+        private_data: Box::into_raw(Box::new(StreamPrivate { column, batch: Some(batch) })) as *mut c_void,
+    });
+
+    unsafe {
+        let stream = Box::into_raw(stream);
+        let capsule = ffi::PyCapsule_New(
+            stream as *mut c_void,
+            STREAM_CAPSULE_NAME.as_ptr() as *const c_char,
+            Some(release_stream_capsule),
+        );
+
+        if capsule.is_null() {
+            release_stream(stream);
+            drop(Box::from_raw(stream));
+        }
+
+        PyObject::from_owned_ptr_or_err(py, capsule)
+    }
+}
+
+// A null pointer of an empty list is allowed by the interface
+unsafe fn raw_slice<'a, T>(pointer: *const T, length: usize) -> &'a [T] {
+    if pointer.is_null() || length == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(pointer, length)
+    }
+}
+
+unsafe fn is_valid(array: &ArrowArray, buffer: &[*const c_void], index: usize) -> bool {
+    array.null_count == 0 || buffer[0].is_null() || get_bit(buffer[0], index)
+}
+
+unsafe fn get_bit(buffer: *const c_void, index: usize) -> bool {
+    (*(buffer as *const u8).add(index / 8) >> (index % 8)) & 1 == 1
+}
+
+unsafe fn get_value<T: Copy>(buffer: *const c_void, index: usize) -> T {
+    ptr::read_unaligned((buffer as *const T).add(index))
+}
+
+// Days since 1970-01-01 to yyyy-mm-dd
+fn date_text(days: i64) -> String {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_position = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_position + 2) / 5 + 1;
+    let month = if month_position < 10 { month_position + 3 } else { month_position - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// A null cell is written as an empty cell
+unsafe fn write_cell(array: &ArrowArray, format: &str, row: usize, result_bytes: &mut Vec<u8>) {
+    let buffer = raw_slice(array.buffers as *const *const c_void, array.n_buffers as usize);
+    let index = array.offset as usize + row;
+
+    if !is_valid(array, buffer, index) {
+        return;
+    }
+
+    let text = match format {
+        "c" => get_value::<i8>(buffer[1], index).to_string(),
+        "C" => get_value::<u8>(buffer[1], index).to_string(),
+        "s" => get_value::<i16>(buffer[1], index).to_string(),
+        "S" => get_value::<u16>(buffer[1], index).to_string(),
+        "i" => get_value::<i32>(buffer[1], index).to_string(),
+        "I" => get_value::<u32>(buffer[1], index).to_string(),
+        "l" => get_value::<i64>(buffer[1], index).to_string(),
+        "L" => get_value::<u64>(buffer[1], index).to_string(),
+        "f" | "g" => {
+            let value = if format == "f" { get_value::<f32>(buffer[1], index) as f64 } else { get_value::<f64>(buffer[1], index) };
+            if value.is_nan() {
+                return;
+            }
+            value.to_string()
+        }
+        "b" => get_bit(buffer[1], index).to_string(),
+        "tdD" => date_text(get_value::<i32>(buffer[1], index) as i64),
+        "u" | "U" => {
+            let (start_byte, end_byte) = if format == "u" {
+                (get_value::<i32>(buffer[1], index) as usize, get_value::<i32>(buffer[1], index + 1) as usize)
+            } else {
+                (get_value::<i64>(buffer[1], index) as usize, get_value::<i64>(buffer[1], index + 1) as usize)
+            };
+            let text = slice::from_raw_parts((buffer[2] as *const u8).add(start_byte), end_byte - start_byte);
+            write_csv_cell(text, b',', result_bytes);
+            return;
+        }
+        "vu" => {
+            // A view is 16 bytes: length, then the text if at most 12 bytes,
+            // otherwise a prefix, the data buffer id and the offset in that buffer
+            let view = (buffer[1] as *const u8).add(index * 16);
+            let length = ptr::read_unaligned(view as *const i32) as usize;
+            let text = if length <= 12 {
+                slice::from_raw_parts(view.add(4), length)
+            } else {
+                let buffer_id = ptr::read_unaligned(view.add(8) as *const i32) as usize;
+                let offset = ptr::read_unaligned(view.add(12) as *const i32) as usize;
+                slice::from_raw_parts((buffer[2 + buffer_id] as *const u8).add(offset), length)
+            };
+            write_csv_cell(text, b',', result_bytes);
+            return;
+        }
+        _ => return,
+    };
+
+    result_bytes.extend_from_slice(text.as_bytes());
+}
+
+// (column name, format) of each child of the struct schema
+unsafe fn read_schema(schema: &ArrowSchema) -> Result<Vec<(String, String)>, String> {
+    let format = CStr::from_ptr(schema.format).to_string_lossy();
+
+    if format != "+s" {
+        return Err(format!("** Arrow stream of format {} is not a record batch ** \n", format));
+    }
+
+    let mut column = Vec::new();
+    let mut error_message = String::new();
+
+    for (n, &child) in raw_slice(schema.children as *const *mut ArrowSchema, schema.n_children as usize).iter().enumerate() {
+        let child = &*child;
+        let name = if child.name.is_null() { String::new() } else { CStr::from_ptr(child.name).to_string_lossy().to_string() };
+        let name = if name.is_empty() { format!("column{}", n + 1) } else { name };
+        let format = CStr::from_ptr(child.format).to_string_lossy().to_string();
+
+        if !child.dictionary.is_null() || !SUPPORTED_FORMAT.contains(&format.as_str()) {
+            error_message.push_str(&format!("** Arrow format {} of column {} is not supported ** \n", format, name));
+        }
+
+        column.push((name, format));
+    }
+
+    if error_message.is_empty() {
+        Ok(column)
+    } else {
+        Err(error_message)
+    }
+}
+
+unsafe fn stream_error(stream: &mut ArrowArrayStream, code: c_int) -> String {
+    let error_message = match stream.get_last_error {
+        Some(get_last_error) => get_last_error(stream),
+        None => ptr::null(),
+    };
+
+    if error_message.is_null() {
+        format!("** Arrow stream error {} ** \n", code)
+    } else {
+        format!("** {} ** \n", CStr::from_ptr(error_message).to_string_lossy())
+    }
+}
+
+// Every batch of the stream to csv rows
+unsafe fn read_arrow_stream(stream: &mut ArrowArrayStream) -> Result<(Vec<u8>, Vec<String>, i64), String> {
+    if stream.release.is_none() {
+        return Err("** Arrow stream is released ** \n".to_string());
+    }
+
+    let (get_schema, get_next) = match (stream.get_schema, stream.get_next) {
+        (Some(get_schema), Some(get_next)) => (get_schema, get_next),
+        _ => return Err("** Arrow stream has no get_schema or get_next ** \n".to_string()),
+    };
+
+    let mut schema = ArrowSchema::empty();
+    let code = get_schema(stream, &mut schema);
+
+    if code != 0 {
+        return Err(stream_error(stream, code));
+    }
+
+    let column = read_schema(&schema);
+
+    if let Some(release) = schema.release {
+        release(&mut schema);
+    }
+
+    let column = column?;
+    let mut byte_array = Vec::new();
+    let mut total_row = 0;
+
+    loop {
+        let mut array = ArrowArray::empty();
+        let code = get_next(stream, &mut array);
+
+        if code != 0 {
+            return Err(stream_error(stream, code));
+        }
+
+        let release = match array.release {
+            Some(release) => release,
+            None => break,
+        };
+
+        if array.n_children as usize != column.len() {
+            let column_count = array.n_children;
+            release(&mut array);
+            return Err(format!("** Arrow batch has {} columns but the schema has {} ** \n", column_count, column.len()));
+        }
+
+        let children = raw_slice(array.children as *const *mut ArrowArray, column.len());
+
+        for row in 0..array.length as usize {
+            for (current_column, (_, format)) in column.iter().enumerate() {
+                if current_column > 0 {
+                    byte_array.push(b',');
+                }
+                write_cell(&*children[current_column], format, array.offset as usize + row, &mut byte_array);
+            }
+            byte_array.extend_from_slice(b"\r\n");
+        }
+
+        total_row += array.length;
+        release(&mut array);
+    }
+
+    Ok((byte_array, column.into_iter().map(|x| x.0).collect(), total_row))
+}
+
+// Read an Arrow stream, e.g. a pyarrow table, a polars or pandas dataframe or a duckdb relation,
+// or a capsule returned by __arrow_c_stream__
+#[pyfunction]
+pub fn from_arrow(py: Python, data: &PyAny) -> PyResult<Dataframe> {
+    let capsule = if data.hasattr("__arrow_c_stream__")? { data.call_method0("__arrow_c_stream__")? } else { data };
+
+    let pointer = unsafe {
+        ffi::PyCapsule_GetPointer(capsule.as_ptr(), STREAM_CAPSULE_NAME.as_ptr() as *const c_char) as *mut ArrowArrayStream
+    };
+
+    if pointer.is_null() {
+        PyErr::fetch(py);
+        return Err(PyTypeError::new_err("from_arrow needs an object with __arrow_c_stream__ or an arrow_array_stream capsule"));
+    }
+
+    // A released stream was moved out by an earlier consumer, and its callbacks must not be called
+    if unsafe { (*pointer).release.is_none() } {
+        return Err(PyValueError::new_err("stream already consumed"));
+    }
+
+    // Move the stream out of the capsule, which then has nothing to release
+    let mut stream = unsafe { ptr::read(pointer) };
+    unsafe { (*pointer).release = None };
+
+    let result = unsafe { read_arrow_stream(&mut stream) };
+
+    if let Some(release) = stream.release {
+        unsafe { release(&mut stream) };
+    }
+
+    match result {
+        Ok((byte_array, column_name, total_row)) => {
+            let csv_meta = CsvMeta {
+                total_column: column_name.len() as i32,
+                validate_row: total_row,
+                estimate_row: total_row,
+                is_line_br_13_exist: true,
+                is_line_br_10_exist: true,
+                column_name,
+                file_size: byte_array.len() as i64,
+                delimiter: 44,
+                ..Default::default()
+            };
+
+            Ok(Dataframe::new(byte_array, csv_meta))
+        }
+        Err(error_message) => Ok(Dataframe::default().with_error(&error_message)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::test_dataframe;
+
+    #[test]
+    fn arrow_stream_round_trip() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let df = test_dataframe(b"1,1.5,true,\"a,b\"\n,-2.25,false,\n3,,true,c\n", &["qty", "price", "flag", "name"]);
+            let capsule = export_arrow_stream(py, &df).unwrap();
+            let result = from_arrow(py, capsule.as_ref(py)).unwrap();
+
+            assert_eq!(result.csv_meta.column_name, vec!["qty", "price", "flag", "name"]);
+            assert_eq!(result.csv_meta.validate_row, 3);
+            assert_eq!(result.byte_array, b"1,1.5,true,\"a,b\"\r\n,-2.25,false,\r\n3,,true,c\r\n");
+        });
+    }
+
+    #[test]
+    fn consumed_stream_is_not_read_again() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let df = test_dataframe(b"1\n2\n", &["qty"]);
+            let capsule = export_arrow_stream(py, &df).unwrap();
+
+            assert_eq!(from_arrow(py, capsule.as_ref(py)).unwrap().csv_meta.validate_row, 2);
+
+            let error = from_arrow(py, capsule.as_ref(py)).err().unwrap();
+            assert!(error.is_instance::<PyValueError>(py));
+            assert_eq!(error.pvalue(py).to_string(), "stream already consumed");
+        });
+    }
+
+    #[test]
+    fn date_text_of_days() {
+        assert_eq!(date_text(0), "1970-01-01");
+        assert_eq!(date_text(19_723), "2024-01-01");
+        assert_eq!(date_text(-1), "1969-12-31");
+    }
+}
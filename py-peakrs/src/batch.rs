@@ -0,0 +1,383 @@
+use crate::dataframe::Dataframe;
+use crate::numeric::{get_numeric_array, get_numeric_type, get_type_mismatch, NumericArray, NumericType};
+use crate::partition::{get_header_byte_count, read_partition};
+use crate::sample::SplitMix64;
+use crate::{read_csv_sample, CsvMeta};
+use pyo3::class::iter::PyIterProtocol;
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::thread;
+
+// Partition n holds the rows from partition_start[n] to partition_start[n + 1]
+struct PartitionReader {
+    file: File,
+    data_start: i64,
+    partition_start: Vec<i64>,
+    is_line_br_13_exist: bool,
+}
+
+impl PartitionReader {
+    fn new(filepath: &str, partition_size: i64, csv_meta: &CsvMeta) -> io::Result<PartitionReader> {
+        let mut file = File::open(filepath)?;
+        let data_start = get_header_byte_count(&mut file);
+        let partition_start = get_partition_start(&mut file, data_start, partition_size, csv_meta.file_size)?;
+
+        Ok(PartitionReader {
+            file,
+            data_start,
+            partition_start,
+            is_line_br_13_exist: csv_meta.is_line_br_13_exist,
+        })
+    }
+
+    fn partition_count(&self) -> usize {
+        self.partition_start.len() - 1
+    }
+
+    fn read(&mut self, current_partition: usize) -> io::Result<Vec<u8>> {
+        self.read_row(self.partition_start[current_partition], self.partition_start[current_partition + 1])
+    }
+
+    // Rows from start_byte to end_byte, both at the start of a row
+    fn read_row(&mut self, start_byte: i64, end_byte: i64) -> io::Result<Vec<u8>> {
+        // A row longer than a partition leaves the next partition empty
+        if start_byte >= end_byte {
+            return Ok(Vec::new());
+        }
+
+        let mut byte_array = vec![0; (end_byte - start_byte) as usize];
+        self.file.seek(SeekFrom::Start(start_byte as u64))?;
+        self.file.read_exact(&mut byte_array)?;
+
+        // Last row of a file may have no line break
+        if byte_array.last() != Some(&10) {
+            if self.is_line_br_13_exist {
+                byte_array.push(13);
+            }
+            byte_array.push(10);
+        }
+
+        Ok(byte_array)
+    }
+}
+
+// Partition n starts at the first row starting from data_start + n * partition_size. A line break
+// inside double quotes does not start a row, which is only known by reading from the header on,
+// so the whole file is scanned once, the same way as read_partition.
+fn get_partition_start(file: &mut File, data_start: i64, partition_size: i64, file_size: i64) -> io::Result<Vec<i64>> {
+    let partition_count = ((file_size - data_start).max(0) as usize).div_ceil(partition_size as usize);
+    let mut partition_start = vec![data_start];
+    let mut byte_array = vec![0; 65536];
+    let mut current_byte = data_start;
+    let mut double_quote_count = 0;
+
+    file.seek(SeekFrom::Start(data_start as u64))?;
+
+    while partition_start.len() < partition_count {
+        let read_count = file.read(&mut byte_array)?;
+
+        if read_count == 0 {
+            break;
+        }
+
+        for (n, &byte) in byte_array[..read_count].iter().enumerate() {
+            if byte == 34 {
+                double_quote_count += 1;
+            } else if byte == 10 && double_quote_count % 2 == 0 {
+                let row_start = current_byte + n as i64 + 1;
+
+                while partition_start.len() < partition_count
+                    && data_start + partition_start.len() as i64 * partition_size <= row_start
+                {
+                    partition_start.push(row_start);
+                }
+            }
+        }
+
+        current_byte += read_count as i64;
+    }
+
+    partition_start.resize(partition_count, file_size);
+    partition_start.push(file_size);
+    Ok(partition_start)
+}
+
+// Line breaks outside double quotes, i.e. the rows of a byte array of complete rows
+fn get_row_end_byte(byte_array: &[u8]) -> impl Iterator<Item = usize> + '_ {
+    let mut double_quote_count = 0;
+
+    byte_array.iter().enumerate().filter_map(move |(n, &byte)| {
+        if byte == 34 {
+            double_quote_count += 1;
+        }
+
+        match byte == 10 && double_quote_count % 2 == 0 {
+            true => Some(n + 1),
+            false => None,
+        }
+    })
+}
+
+enum PartitionSource {
+    Direct(PartitionReader, Vec<usize>),
+    Prefetch(Receiver<io::Result<Vec<u8>>>),
+}
+
+// Yields {column_name: NumericArray} of batch_rows rows, the last batch may be smaller
+#[pyclass]
+pub struct BatchIterator {
+    source: PartitionSource,
+    csv_meta: CsvMeta,
+    column: Vec<(usize, NumericType)>,
+    batch_rows: usize,
+    pending_bytes: Vec<u8>,
+    pending_row: usize,
+    is_end: bool,
+}
+
+impl BatchIterator {
+    fn next_partition(&mut self) -> PyResult<Option<Vec<u8>>> {
+        let partition = match &mut self.source {
+            PartitionSource::Direct(reader, partition) => partition.pop().map(|x| reader.read(x)),
+            // The worker thread reads without the GIL, so waiting here does not block it
+            PartitionSource::Prefetch(receiver) => receiver.recv().ok(),
+        };
+
+        match partition {
+            Some(byte_array) => Ok(Some(byte_array?)),
+            None => Ok(None),
+        }
+    }
+
+    fn next_batch(&mut self) -> PyResult<Option<Vec<u8>>> {
+        while self.pending_row < self.batch_rows && !self.is_end {
+            match self.next_partition()? {
+                Some(byte_array) => {
+                    self.pending_row += get_row_end_byte(&byte_array).count();
+                    self.pending_bytes.extend_from_slice(&byte_array);
+                }
+                None => self.is_end = true,
+            }
+        }
+
+        if self.pending_row == 0 {
+            return Ok(None);
+        }
+
+        if self.pending_row <= self.batch_rows {
+            self.pending_row = 0;
+            return Ok(Some(std::mem::take(&mut self.pending_bytes)));
+        }
+
+        let end_byte = get_row_end_byte(&self.pending_bytes)
+            .nth(self.batch_rows - 1)
+            .unwrap_or(self.pending_bytes.len());
+
+        let remaining_bytes = self.pending_bytes.split_off(end_byte);
+        self.pending_row -= self.batch_rows;
+        Ok(Some(std::mem::replace(&mut self.pending_bytes, remaining_bytes)))
+    }
+}
+
+#[pyproto]
+impl PyIterProtocol for BatchIterator {
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<Self>) -> PyResult<Option<HashMap<String, NumericArray>>> {
+        let byte_array = match slf.next_batch()? {
+            Some(byte_array) => byte_array,
+            None => return Ok(None),
+        };
+
+        let df = Dataframe::new(byte_array, slf.csv_meta.clone());
+        let mut numeric_array = HashMap::new();
+
+        for &(current_column, numeric_type) in &slf.column {
+            if let Some(cell) = get_type_mismatch(&df, current_column, numeric_type) {
+                return Err(PyValueError::new_err(format!(
+                    "Column {} has {}, which is not {}, the type inferred from the first 1MB of rows",
+                    slf.csv_meta.column_name[current_column],
+                    cell,
+                    match numeric_type {
+                        NumericType::Integer => "integer",
+                        NumericType::Float => "float",
+                    }
+                )));
+            }
+
+            numeric_array.insert(
+                slf.csv_meta.column_name[current_column].clone(),
+                get_numeric_array(&df, current_column, numeric_type),
+            );
+        }
+
+        Ok(Some(numeric_array))
+    }
+}
+
+// Iterate a csv file of any size in batches of numeric columns, e.g. for a training loop.
+// Partitions are read in a shuffled order if shuffle_partitions, and prefetch partitions
+// are read ahead by a worker thread.
+#[pyfunction(
+    columns = "None",
+    shuffle_partitions = "true",
+    seed = "None",
+    partition_size = "16_777_216",
+    prefetch = "0"
+)]
+#[allow(clippy::too_many_arguments)]
+pub fn iter_batches(
+    filepath: &str,
+    batch_rows: usize,
+    columns: Option<Vec<String>>,
+    shuffle_partitions: bool,
+    seed: Option<u64>,
+    partition_size: i64,
+    prefetch: usize,
+) -> PyResult<BatchIterator> {
+    if batch_rows == 0 {
+        return Err(PyValueError::new_err("batch_rows should be larger than 0"));
+    }
+
+    let (_, csv_meta) = read_csv_sample(filepath, 10)?;
+
+    if !csv_meta.error_message.is_empty() {
+        return Err(PyValueError::new_err(csv_meta.error_message));
+    }
+
+    let partition_size = if partition_size <= 0 { 16_777_216 } else { partition_size };
+    let mut reader = PartitionReader::new(filepath, partition_size, &csv_meta)?;
+
+    // Column types are inferred from the first 1MB of rows and kept for every batch
+    let (sample, _) = read_partition(&mut reader.file, reader.data_start, 1_048_576, csv_meta.file_size, &csv_meta);
+    let sample = Dataframe::new(sample, csv_meta.clone());
+    let mut column = Vec::new();
+
+    let column_id: Vec<usize> = match &columns {
+        Some(columns) => {
+            let mut column_id = Vec::new();
+
+            for column_name in columns {
+                match csv_meta.column_name.iter().position(|x| x == column_name) {
+                    Some(n) => column_id.push(n),
+                    None => return Err(PyKeyError::new_err(format!("Column {} is not found", column_name))),
+                }
+            }
+
+            column_id
+        }
+        None => (0..csv_meta.total_column as usize).collect(),
+    };
+
+    for current_column in column_id {
+        match get_numeric_type(&sample, current_column) {
+            Some(numeric_type) => column.push((current_column, numeric_type)),
+            None if columns.is_some() => {
+                return Err(PyValueError::new_err(format!("Column {} is not numeric", csv_meta.column_name[current_column])));
+            }
+            None => {}
+        }
+    }
+
+    // Partitions are taken from the end of the list
+    let mut partition: Vec<usize> = (0..reader.partition_count()).rev().collect();
+
+    if shuffle_partitions {
+        SplitMix64::new(seed).shuffle(&mut partition);
+    }
+
+    let source = if prefetch > 0 {
+        let (sender, receiver) = sync_channel(prefetch);
+
+        thread::spawn(move || {
+            while let Some(current_partition) = partition.pop() {
+                if sender.send(reader.read(current_partition)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        PartitionSource::Prefetch(receiver)
+    } else {
+        PartitionSource::Direct(reader, partition)
+    };
+
+    Ok(BatchIterator {
+        source,
+        csv_meta,
+        column,
+        batch_rows,
+        pending_bytes: Vec::new(),
+        pending_row: 0,
+        is_end: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_file(name: &str, text: &[u8]) -> String {
+        let filepath = std::env::temp_dir().join(format!("peakrs_batch_{}_{}.csv", std::process::id(), name));
+        fs::write(&filepath, text).unwrap();
+        filepath.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn partition_does_not_start_inside_double_quotes() {
+        let filepath = temp_file("quote", b"id,note\n1,\"a\n2,b\n3,c\"\n4,d\n5,e\n");
+        let mut file = File::open(&filepath).unwrap();
+        let partition_start = get_partition_start(&mut file, 8, 6, 30).unwrap();
+        fs::remove_file(&filepath).unwrap();
+
+        // Rows start at 8, 22 and 26, the line breaks at 12 and 16 are inside double quotes
+        assert_eq!(partition_start, vec![8, 22, 22, 26, 30]);
+    }
+
+    #[test]
+    fn batch_keeps_quoted_row_whole() {
+        let filepath = temp_file("batch", b"id,note\n1,\"a\n2,b\n3,c\"\n4,d\n5,e\n6,f\n");
+        let mut batch = iter_batches(&filepath, 2, Some(vec!["id".to_string()]), false, None, 6, 0).unwrap();
+
+        let mut batch_bytes = Vec::new();
+        while let Some(byte_array) = batch.next_batch().unwrap() {
+            batch_bytes.push(byte_array);
+        }
+        fs::remove_file(&filepath).unwrap();
+
+        assert_eq!(batch_bytes, vec![b"1,\"a\n2,b\n3,c\"\n4,d\n".to_vec(), b"5,e\n6,f\n".to_vec()]);
+    }
+
+    #[test]
+    fn later_cell_of_another_type_is_a_mismatch() {
+        // The text is after the first 1MB of rows, which decides the column types
+        let mut text = b"id,amount\n".to_vec();
+        for n in 0..100_000 {
+            text.extend_from_slice(format!("{},{}\n", n, n * 10).as_bytes());
+        }
+        text.extend_from_slice(b"100000,abc\n");
+
+        let filepath = temp_file("mismatch", &text);
+        let mut batch = iter_batches(&filepath, 60_000, None, false, None, 1_048_576, 0).unwrap();
+
+        let mut batch_bytes = Vec::new();
+        while let Some(byte_array) = batch.next_batch().unwrap() {
+            batch_bytes.push(byte_array);
+        }
+        fs::remove_file(&filepath).unwrap();
+
+        let mismatch: Vec<Option<String>> = batch_bytes
+            .into_iter()
+            .map(|x| get_type_mismatch(&Dataframe::new(x, batch.csv_meta.clone()), 1, NumericType::Integer))
+            .collect();
+        assert_eq!(mismatch, vec![None, Some("abc".to_string())]);
+        assert_eq!(batch.column.len(), 2);
+    }
+}
@@ -0,0 +1,133 @@
+use flate2::read::MultiGzDecoder;
+use std::cell::Cell;
+use crate::CsvMeta;
+use std::fs::{metadata, File};
+use std::io::{self, Cursor, Read, Seek};
+use std::rc::Rc;
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+}
+
+// By the magic number of the file, whatever its extension
+pub(crate) fn get_compression(filepath: &str) -> io::Result<Compression> {
+    let mut magic_number = Vec::with_capacity(4);
+    File::open(filepath)?.take(4).read_to_end(&mut magic_number)?;
+
+    let compression = match magic_number.as_slice() {
+        [0x1f, 0x8b, ..] => Compression::Gzip,
+        [0x28, 0xb5, 0x2f, 0xfd] => Compression::Zstd,
+        _ => Compression::None,
+    };
+
+    Ok(compression)
+}
+
+// Counts the compressed bytes read by a decoder
+struct CountReader {
+    file: File,
+    byte_count: Rc<Cell<u64>>,
+}
+
+impl Read for CountReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read_count = self.file.read(buf)?;
+        self.byte_count.set(self.byte_count.get() + read_count as u64);
+        Ok(read_count)
+    }
+}
+
+fn open_count_decoder(filepath: &str, byte_count: Rc<Cell<u64>>) -> io::Result<Box<dyn Read>> {
+    let file = CountReader {
+        file: File::open(filepath)?,
+        byte_count,
+    };
+
+    let decoder: Box<dyn Read> = match get_compression(filepath)? {
+        Compression::None => Box::new(file),
+        Compression::Gzip => Box::new(MultiGzDecoder::new(file)),
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(file)?),
+    };
+
+    Ok(decoder)
+}
+
+// A plain file, or a gzip or zstd file decompressed while it is read
+pub(crate) fn open_decoder(filepath: &str) -> io::Result<Box<dyn Read>> {
+    open_count_decoder(filepath, Rc::new(Cell::new(0)))
+}
+
+// Decompress about prefix_size bytes up to a line break. Returns the prefix and the uncompressed
+// size, exact if the prefix is the whole file, otherwise estimated by the compressed bytes read.
+pub(crate) fn read_compressed_prefix(filepath: &str, prefix_size: usize) -> io::Result<(Vec<u8>, i64)> {
+    let byte_count = Rc::new(Cell::new(0));
+    let mut decoder = open_count_decoder(filepath, byte_count.clone())?;
+    let mut prefix = Vec::with_capacity(prefix_size);
+
+    Read::by_ref(&mut decoder).take(prefix_size as u64).read_to_end(&mut prefix)?;
+
+    let mut extra_byte = [0u8; 1];
+
+    if decoder.read(&mut extra_byte)? == 0 {
+        let uncompressed_size = prefix.len() as i64;
+        return Ok((prefix, uncompressed_size));
+    }
+
+    prefix.push(extra_byte[0]);
+
+    let compressed_size = File::open(filepath)?.metadata()?.len() as f64;
+    let uncompressed_size = (prefix.len() as f64 * compressed_size / byte_count.get().max(1) as f64) as i64;
+
+    if let Some(n) = prefix.iter().rposition(|&x| x == 10) {
+        prefix.truncate(n + 1);
+    }
+
+    Ok((prefix, uncompressed_size))
+}
+
+// A file or a decompressed prefix in memory, for a sample reader
+pub(crate) trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+// Sample a file by read_sample(reader, size, sample_row). A compressed file is sampled from a
+// decompressed prefix of it, then estimate_row is scaled to the estimated uncompressed size,
+// which becomes file_size.
+pub(crate) fn sample_compressed<T, F>(filepath: &str, sample_row: i32, read_sample: F) -> io::Result<(T, CsvMeta)>
+where
+    F: FnOnce(&mut dyn ReadSeek, i64, i32) -> io::Result<(T, CsvMeta)>,
+{
+    let compression = get_compression(filepath)?;
+    let compressed_size = metadata(filepath)?.len() as i64;
+
+    if compression == Compression::None {
+        return read_sample(&mut File::open(filepath)?, compressed_size, sample_row);
+    }
+
+    let (prefix, uncompressed_size) = read_compressed_prefix(filepath, 8_388_608)?;
+    let prefix_size = prefix.len() as i64;
+    let (sample, mut csv_meta) = read_sample(&mut Cursor::new(prefix), prefix_size, sample_row)?;
+
+    if prefix_size > 0 {
+        csv_meta.estimate_row = (csv_meta.estimate_row as f64 * uncompressed_size as f64 / prefix_size as f64) as i64;
+    }
+
+    csv_meta.file_size = uncompressed_size;
+    csv_meta.compression = compression.name().to_string();
+    csv_meta.compressed_size = compressed_size;
+
+    Ok((sample, csv_meta))
+}
@@ -0,0 +1,191 @@
+use crate::arrow::export_arrow_stream;
+use crate::numeric::{to_numeric_array, NumericArray};
+use crate::parquet_file::write_parquet;
+use crate::jsonl::write_jsonl_file;
+use crate::{cell_address, cell_per_row, cell_range, view_csv, write_csv, CsvMeta};
+use pyo3::class::basic::PyObjectProtocol;
+use pyo3::class::mapping::PyMappingProtocol;
+use pyo3::exceptions::{PyIndexError, PyKeyError, PyTypeError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::collections::HashMap;
+use std::cell::OnceCell;
+
+// Dataframe = Vector + Meta Information. The rows stay in Rust, without a column name row, and the
+// cell addresses are located on first use by shape, column or indexing.
+#[pyclass]
+#[derive(Clone, Default)]
+pub struct Dataframe {
+    pub(crate) byte_array: Vec<u8>,
+    pub(crate) csv_meta: CsvMeta,
+    cell_address: OnceCell<Vec<i64>>,
+}
+
+impl Dataframe {
+    pub(crate) fn new(byte_array: Vec<u8>, csv_meta: CsvMeta) -> Dataframe {
+        Dataframe {
+            byte_array,
+            csv_meta,
+            cell_address: OnceCell::new(),
+        }
+    }
+
+    // A copy of the dataframe with error_message appended, as returned by a command which fails
+    pub(crate) fn with_error(&self, error_message: &str) -> Dataframe {
+        let mut dataframe = self.clone();
+        dataframe.csv_meta.error_message.push_str(error_message);
+        dataframe
+    }
+
+    pub(crate) fn cell_address(&self) -> &[i64] {
+        self.cell_address.get_or_init(|| cell_address(&self.byte_array, &self.csv_meta))
+    }
+
+    // A dataframe of a failed command may have no column
+    pub(crate) fn total_row(&self) -> usize {
+        match cell_per_row(&self.csv_meta) {
+            0 => 0,
+            cell_per_row => (self.cell_address().len() - 1) / cell_per_row,
+        }
+    }
+
+    fn cell_text(&self, current_row: usize, current_column: usize) -> String {
+        let first_cell = current_row * cell_per_row(&self.csv_meta);
+        let (start_byte, end_byte) = cell_range(&self.byte_array, self.cell_address(), first_cell + current_column);
+        String::from_utf8_lossy(&self.byte_array[start_byte..end_byte]).to_string()
+    }
+
+    fn row_text(&self, current_row: usize) -> Vec<String> {
+        (0..self.csv_meta.total_column as usize).map(|x| self.cell_text(current_row, x)).collect()
+    }
+}
+
+// Meta information of rows with a comma delimiter and LF line breaks, for unit tests
+#[cfg(test)]
+pub(crate) fn test_csv_meta(column_name: &[&str]) -> CsvMeta {
+    CsvMeta {
+        total_column: column_name.len() as i32,
+        is_line_br_10_exist: true,
+        column_name: column_name.iter().map(|x| x.to_string()).collect(),
+        delimiter: b',',
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn test_dataframe(text: &[u8], column_name: &[&str]) -> Dataframe {
+    Dataframe::new(text.to_vec(), test_csv_meta(column_name))
+}
+
+#[pymethods]
+impl Dataframe {
+    #[getter]
+    fn get_meta(&self) -> CsvMeta {
+        self.csv_meta.clone()
+    }
+
+    #[getter]
+    fn get_error_message(&self) -> String {
+        self.csv_meta.error_message.clone()
+    }
+
+    // (row count, column count)
+    #[getter]
+    fn get_shape(&self) -> (usize, usize) {
+        (self.total_row(), self.csv_meta.total_column as usize)
+    }
+
+    fn view(&self) {
+        view_csv(self);
+    }
+
+    #[args(file_name = "\"%Sample.csv\"")]
+    fn write_csv(&self, file_name: &str) {
+        write_csv(self, file_name);
+    }
+
+    #[args(file_name = "\"%Sample.jsonl\"")]
+    fn write_jsonl(&self, file_name: &str) -> PyResult<()> {
+        Ok(write_jsonl_file(self, file_name)?)
+    }
+
+    #[args(file_name = "\"%Sample.parquet\"", compression = "\"snappy\"", row_group_size = "1_000_000")]
+    fn write_parquet(&self, file_name: &str, compression: &str, row_group_size: usize) -> PyResult<()> {
+        write_parquet(self, file_name, compression, row_group_size)
+    }
+
+    // Trimmed cells of a column, from the first row to the last row
+    fn column(&self, column_name: &str) -> PyResult<Vec<String>> {
+        let current_column = match self.csv_meta.column_name.iter().position(|x| x == column_name) {
+            Some(n) => n,
+            None => return Err(PyKeyError::new_err(format!("Column {} is not found", column_name))),
+        };
+
+        Ok((0..self.total_row()).map(|x| self.cell_text(x, current_column)).collect())
+    }
+
+    // Numeric columns as {column_name: NumericArray} for numpy.asarray, all numeric columns if columns
+    // is not given. A listed column with a cell which is not a real number raises ValueError.
+    #[args(columns = "None")]
+    fn to_numpy(&self, columns: Option<Vec<String>>) -> PyResult<HashMap<String, NumericArray>> {
+        to_numeric_array(self, &columns)
+    }
+
+    // Arrow PyCapsule interface, the requested schema is ignored as the column types are inferred
+    #[args(requested_schema = "None")]
+    fn __arrow_c_stream__(&self, py: Python, requested_schema: Option<PyObject>) -> PyResult<PyObject> {
+        let _ = requested_schema;
+        export_arrow_stream(py, self)
+    }
+
+    // The rows as csv bytes, without the column name row
+    fn to_bytes<'p>(&self, py: Python<'p>) -> &'p PyBytes {
+        PyBytes::new(py, &self.byte_array)
+    }
+}
+
+#[pyproto]
+impl PyMappingProtocol for Dataframe {
+    fn __len__(&self) -> usize {
+        self.total_row()
+    }
+
+    // df[n] is the trimmed cells of row n, a negative n counts from the last row; df["column"] is a column
+    fn __getitem__(&self, key: &PyAny) -> PyResult<Vec<String>> {
+        if let Ok(column_name) = key.extract::<&str>() {
+            return self.column(column_name);
+        }
+
+        let current_row = match key.extract::<i64>() {
+            Ok(n) => n,
+            Err(_) => return Err(PyTypeError::new_err("Dataframe index should be a row number or a column name")),
+        };
+
+        let total_row = self.total_row() as i64;
+        let current_row = if current_row < 0 { current_row + total_row } else { current_row };
+
+        if current_row < 0 || current_row >= total_row {
+            return Err(PyIndexError::new_err(format!("Row {} is out of range of {} rows", current_row, total_row)));
+        }
+
+        Ok(self.row_text(current_row as usize))
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for Dataframe {
+    // Meta information can be read from the dataframe, e.g. df.file_size or df.column_name
+    fn __getattr__(&self, name: &str) -> PyResult<PyObject> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        Py::new(py, self.csv_meta.clone())?.getattr(py, name)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Dataframe(row={}, column=[{}])",
+            self.total_row(),
+            self.csv_meta.column_name.join(", ")
+        )
+    }
+}
@@ -0,0 +1,192 @@
+use crate::dataframe::Dataframe;
+use crate::{cell_address, cell_per_row, cell_range, count_row, parse_real_number, CsvMeta};
+use pyo3::prelude::*;
+
+#[derive(Clone, Debug)]
+pub(crate) enum Compare {
+    Equal(Vec<Vec<u8>>),
+    NotEqual(Vec<Vec<u8>>),
+    Greater(Vec<u8>),
+    GreaterEqual(Vec<u8>),
+    Less(Vec<u8>),
+    LessEqual(Vec<u8>),
+    Range(Vec<u8>, Vec<u8>),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct FilterCondition {
+    pub(crate) column: usize,
+    pub(crate) compare: Compare,
+    pub(crate) is_float: bool,
+}
+
+fn get_compare(condition: &str) -> Compare {
+    let value_list = |x: &str| x.split(',').map(|y| y.trim().as_bytes().to_vec()).collect();
+
+    for operator in [">=", "<=", "!=", ">", "<", "="] {
+        if let Some(value) = condition.strip_prefix(operator) {
+            let value = value.trim();
+
+            return match operator {
+                ">=" => Compare::GreaterEqual(value.as_bytes().to_vec()),
+                "<=" => Compare::LessEqual(value.as_bytes().to_vec()),
+                "!=" => Compare::NotEqual(value_list(value)),
+                ">" => Compare::Greater(value.as_bytes().to_vec()),
+                "<" => Compare::Less(value.as_bytes().to_vec()),
+                _ => Compare::Equal(value_list(value)),
+            };
+        }
+    }
+
+    match condition.find("..") {
+        Some(n) => Compare::Range(
+            condition[..n].trim().as_bytes().to_vec(),
+            condition[n + 2..].trim().as_bytes().to_vec(),
+        ),
+        None => Compare::Equal(value_list(condition)),
+    }
+}
+
+// e.g. "region(=EU,US) amount(Float > 100) ledger(L10..L20) year(float2020..2023)"
+// where compare_operator includes >,<,>=,<=,=,!= and Range, prefix Float to compare as real number.
+// A comma list after = or != matches any of the values.
+pub(crate) fn parse_filter_setting(setting: &str, column_name: &[String]) -> Result<Vec<FilterCondition>, String> {
+    let mut filter_condition = Vec::new();
+    let mut error_message = String::new();
+
+    for item in setting.split(')') {
+        let item = item.trim_matches(|x: char| x == ',' || x.is_whitespace());
+
+        if item.is_empty() {
+            continue;
+        }
+
+        let (column, condition) = match item.find('(') {
+            Some(n) => (item[..n].trim(), item[n + 1..].trim()),
+            None => {
+                error_message.push_str(&format!("** {} should be written as column(compare_operator value) ** \n", item));
+                continue;
+            }
+        };
+
+        let column_id = match column_name.iter().position(|x| x == column) {
+            Some(n) => n,
+            None => {
+                error_message.push_str(&format!("** Column {} is not found ** \n", column));
+                continue;
+            }
+        };
+
+        let is_float = condition.len() >= 5 && condition[..5].eq_ignore_ascii_case("float");
+        let condition = if is_float { condition[5..].trim() } else { condition };
+        let compare = get_compare(condition);
+
+        if is_float {
+            let mut value = Vec::new();
+
+            match &compare {
+                Compare::Equal(x) | Compare::NotEqual(x) => value.extend(x.iter()),
+                Compare::Greater(x) | Compare::GreaterEqual(x) | Compare::Less(x) | Compare::LessEqual(x) => value.push(x),
+                Compare::Range(x, y) => {
+                    value.push(x);
+                    value.push(y);
+                }
+            }
+
+            if value.iter().any(|x| parse_real_number(x).is_none()) {
+                error_message.push_str(&format!("** {} of column {} is not a real number ** \n", condition, column));
+                continue;
+            }
+        }
+
+        filter_condition.push(FilterCondition {
+            column: column_id,
+            compare,
+            is_float,
+        });
+    }
+
+    if error_message.is_empty() {
+        Ok(filter_condition)
+    } else {
+        Err(error_message)
+    }
+}
+
+fn compare_value(cell: &[u8], value: &[u8], is_float: bool) -> Option<std::cmp::Ordering> {
+    if is_float {
+        parse_real_number(cell)?.partial_cmp(&parse_real_number(value)?)
+    } else {
+        Some(cell.cmp(value))
+    }
+}
+
+// A cell which is not a real number never matches a Float condition
+pub(crate) fn is_condition_match(cell: &[u8], filter_condition: &FilterCondition) -> bool {
+    use std::cmp::Ordering::*;
+
+    let is_float = filter_condition.is_float;
+
+    match &filter_condition.compare {
+        Compare::Equal(value) => value.iter().any(|x| compare_value(cell, x, is_float) == Some(Equal)),
+        Compare::NotEqual(value) => {
+            (!is_float || parse_real_number(cell).is_some())
+                && value.iter().all(|x| compare_value(cell, x, is_float) != Some(Equal))
+        }
+        Compare::Greater(x) => compare_value(cell, x, is_float) == Some(Greater),
+        Compare::GreaterEqual(x) => matches!(compare_value(cell, x, is_float), Some(Greater | Equal)),
+        Compare::Less(x) => compare_value(cell, x, is_float) == Some(Less),
+        Compare::LessEqual(x) => matches!(compare_value(cell, x, is_float), Some(Less | Equal)),
+        Compare::Range(x, y) => {
+            matches!(compare_value(cell, x, is_float), Some(Greater | Equal))
+                && matches!(compare_value(cell, y, is_float), Some(Less | Equal))
+        }
+    }
+}
+
+// Rows matching all conditions, or the other rows if is_unmatch
+pub(crate) fn filter_byte_array(byte_array: &[u8], csv_meta: &CsvMeta, filter_condition: &[FilterCondition], is_unmatch: bool) -> Vec<u8> {
+    let cell_address = cell_address(byte_array, csv_meta);
+    let cell_per_row = cell_per_row(csv_meta);
+    let total_row = (cell_address.len() - 1) / cell_per_row;
+    let mut result_bytes = Vec::with_capacity(byte_array.len());
+
+    for current_row in 0..total_row {
+        let first_cell = current_row * cell_per_row;
+
+        let is_match = filter_condition.iter().all(|x| {
+            let (start_byte, end_byte) = cell_range(byte_array, &cell_address, first_cell + x.column);
+            is_condition_match(&byte_array[start_byte..end_byte], x)
+        });
+
+        if is_match != is_unmatch {
+            let start_byte = cell_address[first_cell] as usize;
+            let end_byte = cell_address[first_cell + cell_per_row] as usize;
+            result_bytes.extend_from_slice(&byte_array[start_byte..end_byte]);
+        }
+    }
+
+    result_bytes
+}
+
+fn filter_dataframe(df: &Dataframe, setting: &str, is_unmatch: bool) -> Dataframe {
+    match parse_filter_setting(setting, &df.csv_meta.column_name) {
+        Ok(filter_condition) => {
+            let result_bytes = filter_byte_array(&df.byte_array, &df.csv_meta, &filter_condition, is_unmatch);
+            let mut csv_meta = df.csv_meta.clone();
+            csv_meta.validate_row = count_row(&result_bytes);
+            Dataframe::new(result_bytes, csv_meta)
+        }
+        Err(error_message) => df.with_error(&error_message),
+    }
+}
+
+#[pyfunction]
+pub fn filter(df: &Dataframe, setting: &str) -> PyResult<Dataframe> {
+    Ok(filter_dataframe(df, setting, false))
+}
+
+#[pyfunction]
+pub fn filter_unmatch(df: &Dataframe, setting: &str) -> PyResult<Dataframe> {
+    Ok(filter_dataframe(df, setting, true))
+}
@@ -0,0 +1,485 @@
+use crate::dataframe::Dataframe;
+use crate::profile::{format_table, is_null_text, text_to_dataframe};
+use crate::source::FilePath;
+use crate::numeric::{get_cell_numeric_type, NumericType};
+use crate::{cell_per_row, cell_range, get_csv_sample, unquote_cell, CsvMeta};
+use pyo3::class::basic::PyObjectProtocol;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// Rows sampled from a file to infer column types
+const FINGERPRINT_SAMPLE_ROW: i32 = 1000;
+
+// The layout of a csv which should stay the same from one run to the next. column_type is empty
+// when only the meta information is known.
+#[pyclass]
+#[derive(Clone, Default)]
+pub struct Fingerprint {
+    delimiter: u8,
+    column_name: Vec<String>,
+    column_type: Vec<String>,
+    line_break: String,
+    encoding: String,
+    compression: String,
+}
+
+// Same rule as to_numpy, where a null text such as NULL is skipped like an empty cell
+fn column_type(df: &Dataframe, current_column: usize) -> &'static str {
+    let cell_address = df.cell_address();
+    let cell_per_row = cell_per_row(&df.csv_meta);
+
+    let cell = (0..df.total_row())
+        .map(|current_row| {
+            let (start_byte, end_byte) = cell_range(&df.byte_array, cell_address, current_row * cell_per_row + current_column);
+            unquote_cell(&df.byte_array[start_byte..end_byte])
+        })
+        .filter(|x| !is_null_text(x));
+
+    match get_cell_numeric_type(cell) {
+        Some(NumericType::Integer) => "integer",
+        Some(NumericType::Float) => "float",
+        None => "text",
+    }
+}
+
+impl Fingerprint {
+    fn from_meta(csv_meta: &CsvMeta) -> Fingerprint {
+        let line_break = match (csv_meta.is_line_br_13_exist, csv_meta.is_line_br_10_exist) {
+            (true, true) => "crlf",
+            (false, true) => "lf",
+            (true, false) => "cr",
+            (false, false) => "",
+        };
+
+        Fingerprint {
+            delimiter: csv_meta.delimiter,
+            column_name: csv_meta.column_name.clone(),
+            column_type: Vec::new(),
+            line_break: line_break.to_string(),
+            encoding: csv_meta.encoding.clone(),
+            compression: csv_meta.compression.clone(),
+        }
+    }
+
+    fn from_dataframe(df: &Dataframe) -> Fingerprint {
+        let mut fingerprint = Fingerprint::from_meta(&df.csv_meta);
+        fingerprint.column_type = (0..df.csv_meta.column_name.len()).map(|x| column_type(df, x).to_string()).collect();
+        fingerprint
+    }
+
+    // A key which is missing is unknown, so that it is not compared
+    fn from_json(text: &str) -> Result<Fingerprint, String> {
+        let value: Value = serde_json::from_str(text).map_err(|e| format!("Fingerprint is not valid JSON: {}", e))?;
+        let text = |key: &str| value.get(key).and_then(|x| x.as_str()).unwrap_or_default().to_string();
+        let text_list = |key: &str| -> Vec<String> {
+            value
+                .get(key)
+                .and_then(|x| x.as_array())
+                .map(|x| x.iter().filter_map(|x| x.as_str()).map(|x| x.to_string()).collect())
+                .unwrap_or_default()
+        };
+
+        Ok(Fingerprint {
+            delimiter: value.get("delimiter").and_then(|x| x.as_u64()).unwrap_or(0) as u8,
+            column_name: text_list("column_name"),
+            column_type: text_list("column_type"),
+            line_break: text("line_break"),
+            encoding: text("encoding"),
+            compression: text("compression"),
+        })
+    }
+
+    // A fingerprint, a CsvMeta, a dataframe, fingerprint JSON text, or a source of get_csv_sample
+    // from which 1,000 rows are sampled
+    fn extract(py: Python, ob: &PyAny) -> PyResult<Fingerprint> {
+        if let Ok(x) = ob.extract::<PyRef<Fingerprint>>() {
+            return Ok(x.clone());
+        }
+
+        if let Ok(x) = ob.extract::<PyRef<CsvMeta>>() {
+            return Ok(Fingerprint::from_meta(&x));
+        }
+
+        if let Ok(x) = ob.extract::<PyRef<Dataframe>>() {
+            return Ok(Fingerprint::from_dataframe(&x));
+        }
+
+        if let Ok(x) = ob.extract::<String>() {
+            if x.trim_start().starts_with('{') {
+                return Fingerprint::from_json(&x).map_err(PyValueError::new_err);
+            }
+        }
+
+        let df = get_csv_sample(py, ob.extract::<FilePath>()?, FINGERPRINT_SAMPLE_ROW, "even", None, "")?;
+
+        if !df.csv_meta.error_message.is_empty() {
+            return Err(PyValueError::new_err(df.csv_meta.error_message));
+        }
+
+        Ok(Fingerprint::from_dataframe(&df))
+    }
+}
+
+#[pymethods]
+impl Fingerprint {
+    #[getter]
+    fn get_delimiter(&self) -> u8 {
+        self.delimiter
+    }
+
+    #[getter]
+    fn get_column_name(&self) -> Vec<String> {
+        self.column_name.clone()
+    }
+
+    #[getter]
+    fn get_column_type(&self) -> Vec<String> {
+        self.column_type.clone()
+    }
+
+    #[getter]
+    fn get_line_break(&self) -> String {
+        self.line_break.clone()
+    }
+
+    #[getter]
+    fn get_encoding(&self) -> String {
+        self.encoding.clone()
+    }
+
+    #[getter]
+    fn get_compression(&self) -> String {
+        self.compression.clone()
+    }
+
+    fn to_json(&self) -> String {
+        let fingerprint = json!({
+            "delimiter": self.delimiter,
+            "column_name": self.column_name,
+            "column_type": self.column_type,
+            "line_break": self.line_break,
+            "encoding": self.encoding,
+            "compression": self.compression,
+        });
+
+        format!("{}\n", serde_json::to_string_pretty(&fingerprint).unwrap_or_default())
+    }
+
+    fn write_json(&self, file_name: &str) -> PyResult<()> {
+        let mut f = BufWriter::new(File::create(file_name)?);
+        f.write_all(self.to_json().as_bytes())?;
+        f.flush()?;
+        Ok(())
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for Fingerprint {
+    fn __repr__(&self) -> String {
+        format!(
+            "Fingerprint(delimiter={}, column=[{}], line_break={}, encoding={})",
+            delimiter_text(self.delimiter),
+            self.column_name.join(", "),
+            self.line_break,
+            self.encoding
+        )
+    }
+}
+
+fn delimiter_text(delimiter: u8) -> String {
+    match delimiter {
+        9 => "ASCII9 (tab)".to_string(),
+        x => format!("ASCII{} ({})", x, x as char),
+    }
+}
+
+// (change, column, before, after) of each difference
+#[derive(Clone)]
+struct Change {
+    change: &'static str,
+    column: String,
+    before: String,
+    after: String,
+}
+
+fn change(change: &'static str, column: &str, before: &str, after: &str) -> Change {
+    Change {
+        change,
+        column: column.to_string(),
+        before: before.to_string(),
+        after: after.to_string(),
+    }
+}
+
+// Position of the same column on the other side. A repeated column name is matched by its
+// occurrence, e.g. the second a of one side is the second a of the other side.
+fn matched_position(column_name: &[String], n: usize, other: &[String]) -> Option<usize> {
+    let name = &column_name[n];
+    let occurrence = column_name[..n].iter().filter(|x| *x == name).count();
+    other.iter().enumerate().filter(|(_, x)| *x == name).nth(occurrence).map(|(m, _)| m)
+}
+
+// A column missing from after and a new column at the same position is a rename, other missing
+// and new columns are removed and added. Types are compared only if both sides know them.
+fn compare_column(before: &Fingerprint, after: &Fingerprint, change_list: &mut Vec<Change>) {
+    let before_position: Vec<Option<usize>> = (0..before.column_name.len()).map(|n| matched_position(&before.column_name, n, &after.column_name)).collect();
+    let after_position: Vec<Option<usize>> = (0..after.column_name.len()).map(|n| matched_position(&after.column_name, n, &before.column_name)).collect();
+
+    let removed: Vec<usize> = (0..before.column_name.len()).filter(|&n| before_position[n].is_none()).collect();
+    let added: Vec<usize> = (0..after.column_name.len()).filter(|&n| after_position[n].is_none()).collect();
+
+    for &n in &removed {
+        let name = &before.column_name[n];
+
+        match added.contains(&n) {
+            true => change_list.push(change("renamed", name, name, &after.column_name[n])),
+            false => change_list.push(change("removed", name, &format!("position {}", n + 1), "")),
+        }
+    }
+
+    for &n in &added {
+        if !removed.contains(&n) {
+            change_list.push(change("added", &after.column_name[n], "", &format!("position {}", n + 1)));
+        }
+    }
+
+    // Columns in both, in the order of each side
+    let before_order: Vec<usize> = (0..before.column_name.len()).filter(|&n| before_position[n].is_some()).collect();
+    let after_order: Vec<usize> = (0..after.column_name.len()).filter(|&n| after_position[n].is_some()).collect();
+
+    for (&n, &m) in before_order.iter().zip(after_order.iter()) {
+        if before_position[n] != Some(m) {
+            change_list.push(change(
+                "moved",
+                &before.column_name[n],
+                &format!("position {}", n + 1),
+                &format!("position {}", before_position[n].unwrap_or(0) + 1),
+            ));
+        }
+    }
+
+    for (n, name) in before.column_name.iter().enumerate() {
+        let after_type = before_position[n].and_then(|x| after.column_type.get(x));
+
+        if let (Some(before_type), Some(after_type)) = (before.column_type.get(n), after_type) {
+            if before_type != after_type {
+                change_list.push(change("type", name, before_type, after_type));
+            }
+        }
+    }
+}
+
+// Differences from before to after, where an empty field of either side is unknown and not compared
+fn compare_fingerprint(before: &Fingerprint, after: &Fingerprint) -> Vec<Change> {
+    let mut change_list = Vec::new();
+
+    // A delimiter of 0 is unknown, e.g. in a fingerprint written by hand
+    if before.delimiter != 0 && after.delimiter != 0 && before.delimiter != after.delimiter {
+        change_list.push(change("delimiter", "", &delimiter_text(before.delimiter), &delimiter_text(after.delimiter)));
+    }
+
+    // No column name is unknown as well
+    if !before.column_name.is_empty() && !after.column_name.is_empty() {
+        compare_column(before, after, &mut change_list);
+    }
+
+    let setting = [
+        ("line_break", &before.line_break, &after.line_break),
+        ("encoding", &before.encoding, &after.encoding),
+        ("compression", &before.compression, &after.compression),
+    ];
+
+    for (name, before, after) in setting {
+        if !before.is_empty() && !after.is_empty() && before != after {
+            change_list.push(change(name, "", before, after));
+        }
+    }
+
+    change_list
+}
+
+// Differences between two fingerprints, none if the layout is the same
+#[pyclass]
+#[derive(Clone)]
+pub struct MetaDiff {
+    change: Vec<Change>,
+}
+
+const CHANGE_COLUMN: [&str; 4] = ["change", "column", "before", "after"];
+
+impl MetaDiff {
+    fn change_row(&self) -> Vec<Vec<String>> {
+        self.change
+            .iter()
+            .map(|x| vec![x.change.to_string(), x.column.clone(), x.before.clone(), x.after.clone()])
+            .collect()
+    }
+
+    fn summary(&self) -> String {
+        match self.change.len() {
+            0 => "No change".to_string(),
+            n => format!("{} changes", n),
+        }
+    }
+}
+
+#[pymethods]
+impl MetaDiff {
+    #[getter]
+    fn get_is_changed(&self) -> bool {
+        !self.change.is_empty()
+    }
+
+    #[getter]
+    fn get_change_count(&self) -> usize {
+        self.change.len()
+    }
+
+    fn view(&self) {
+        println!();
+        println!("  {}", self.summary());
+        println!();
+
+        if !self.change.is_empty() {
+            println!("{}", format_table(&CHANGE_COLUMN, &self.change_row()));
+        }
+    }
+
+    // The changes as a dataframe, one row per change
+    fn to_dataframe(&self) -> Dataframe {
+        text_to_dataframe(&CHANGE_COLUMN, &self.change_row())
+    }
+
+    fn to_json(&self) -> String {
+        let change: Vec<Value> = self
+            .change
+            .iter()
+            .map(|x| json!({"change": x.change, "column": x.column, "before": x.before, "after": x.after}))
+            .collect();
+
+        format!("{}\n", serde_json::to_string_pretty(&json!({"is_changed": self.get_is_changed(), "change": change})).unwrap_or_default())
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for MetaDiff {
+    fn __str__(&self) -> String {
+        format!("  {}\n\n{}", self.summary(), format_table(&CHANGE_COLUMN, &self.change_row()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("MetaDiff(change={})", self.change.len())
+    }
+}
+
+// Fingerprint of a dataframe, a CsvMeta or a source of get_csv_sample, to be written by write_json
+#[pyfunction]
+pub fn fingerprint(py: Python, df_or_path: &PyAny) -> PyResult<Fingerprint> {
+    Fingerprint::extract(py, df_or_path)
+}
+
+#[pyfunction]
+pub fn read_fingerprint(file_name: &str) -> PyResult<Fingerprint> {
+    Fingerprint::from_json(&fs::read_to_string(file_name)?).map_err(PyValueError::new_err)
+}
+
+// Each of meta_a and meta_b is a CsvMeta, a dataframe, a fingerprint or a source of get_csv_sample
+#[pyfunction]
+pub fn compare_meta(py: Python, meta_a: &PyAny, meta_b: &PyAny) -> PyResult<MetaDiff> {
+    let before = Fingerprint::extract(py, meta_a)?;
+    let after = Fingerprint::extract(py, meta_b)?;
+
+    Ok(MetaDiff {
+        change: compare_fingerprint(&before, &after),
+    })
+}
+
+// For a scheduled job: the differences from the fingerprint saved in file_name by the last run, then
+// the fingerprint of this run is saved if update. The first run only saves the fingerprint.
+#[pyfunction(update = "true")]
+pub fn check_fingerprint(py: Python, df_or_path: &PyAny, file_name: &str, update: bool) -> PyResult<MetaDiff> {
+    let current = Fingerprint::extract(py, df_or_path)?;
+
+    let change = if Path::new(file_name).exists() {
+        compare_fingerprint(&read_fingerprint(file_name)?, &current)
+    } else {
+        Vec::new()
+    };
+
+    if update || !Path::new(file_name).exists() {
+        current.write_json(file_name)?;
+    }
+
+    Ok(MetaDiff { change })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::test_dataframe;
+
+    fn fingerprint(column_name: &[&str]) -> Fingerprint {
+        Fingerprint {
+            delimiter: b',',
+            column_name: column_name.iter().map(|x| x.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn change_text(before: &[&str], after: &[&str]) -> Vec<String> {
+        compare_fingerprint(&fingerprint(before), &fingerprint(after))
+            .iter()
+            .map(|x| format!("{} {} {} {}", x.change, x.column, x.before, x.after).trim().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn repeated_column_name_is_matched_by_occurrence() {
+        assert_eq!(change_text(&["a", "b", "a"], &["a", "b"]), vec!["removed a position 3"]);
+        assert_eq!(change_text(&["a", "b"], &["a", "b", "a"]), vec!["added a  position 3"]);
+        assert!(change_text(&["a", "b", "a"], &["a", "b", "a"]).is_empty());
+    }
+
+    #[test]
+    fn rename_and_move() {
+        assert_eq!(change_text(&["a", "b", "c"], &["a", "x", "c"]), vec!["renamed b b x"]);
+        assert_eq!(
+            change_text(&["a", "b"], &["b", "a"]),
+            vec!["moved a position 1 position 2", "moved b position 2 position 1"]
+        );
+    }
+
+    #[test]
+    fn unknown_delimiter_and_column_name_are_not_compared() {
+        let known = fingerprint(&["a", "b"]);
+        let unknown = Fingerprint::from_json("{\"line_break\": \"lf\"}").unwrap();
+
+        assert!(compare_fingerprint(&known, &unknown).is_empty());
+        assert!(compare_fingerprint(&unknown, &known).is_empty());
+    }
+
+    #[test]
+    fn column_type_skips_null_text() {
+        let df = test_dataframe(b"1,NULL,00123,\"2.5\"\nnull,2,00124,x\n", &["a", "b", "c", "d"]);
+        let column_type: Vec<&str> = (0..4).map(|x| column_type(&df, x)).collect();
+
+        assert_eq!(column_type, vec!["integer", "integer", "text", "text"]);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut x = fingerprint(&["a", "b"]);
+        x.column_type = vec!["integer".to_string(), "text".to_string()];
+        x.line_break = "crlf".to_string();
+        x.encoding = "utf-8".to_string();
+        x.compression = "none".to_string();
+
+        let y = Fingerprint::from_json(&x.to_json()).unwrap();
+        assert!(compare_fingerprint(&x, &y).is_empty());
+        assert_eq!(y.column_type, x.column_type);
+    }
+}
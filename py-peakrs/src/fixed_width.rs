@@ -0,0 +1,387 @@
+use crate::compression::{open_decoder, sample_compressed};
+use crate::dataframe::Dataframe;
+use crate::{parse_real_number, write_csv_cell, CsvMeta};
+use pyo3::prelude::*;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+
+// (column name, start byte, byte length, type) of each column, type is text, integer or float
+type Layout = Vec<(String, usize, usize, String)>;
+
+fn trim_line_break(mut line: &[u8]) -> &[u8] {
+    while let [x @ .., b'\r' | b'\n'] = line {
+        line = x;
+    }
+    line
+}
+
+fn trim_space(mut cell: &[u8]) -> &[u8] {
+    while let [b' ' | b'\t', x @ ..] = cell {
+        cell = x;
+    }
+    while let [x @ .., b' ' | b'\t'] = cell {
+        cell = x;
+    }
+    cell
+}
+
+// Mainframe numbers may be zero padded or signed with +, e.g. +000123.40 is 123.40
+fn normalize_number(cell: &[u8]) -> Vec<u8> {
+    let (sign, digit) = match cell {
+        [b'+', x @ ..] => (&b""[..], x),
+        [b'-', x @ ..] => (&b"-"[..], x),
+        x => (&b""[..], x),
+    };
+
+    // A lone sign is not a number and stays as it is
+    if digit.is_empty() {
+        return cell.to_vec();
+    }
+
+    let leading_zero = digit.iter().take_while(|&&x| x == b'0').count();
+    let mut digit = &digit[leading_zero..];
+
+    if digit.is_empty() || digit[0] == b'.' {
+        digit = &cell[cell.len() - digit.len() - 1..];
+    }
+
+    [sign, digit].concat()
+}
+
+fn check_layout(layout: &Layout) -> Result<(), String> {
+    let mut error_message = String::new();
+
+    if layout.is_empty() {
+        error_message.push_str("** Layout has no column ** \n");
+    }
+
+    for (column_name, _, length, column_type) in layout {
+        if column_name.is_empty() {
+            error_message.push_str("** Layout has a column without name ** \n");
+        }
+        if *length == 0 {
+            error_message.push_str(&format!("** Length of column {} should be larger than 0 ** \n", column_name));
+        }
+        if !matches!(column_type.as_str(), "text" | "integer" | "float") {
+            error_message.push_str(&format!(
+                "** Type {} of column {} is not supported, use text, integer or float ** \n",
+                column_type, column_name
+            ));
+        }
+    }
+
+    if error_message.is_empty() {
+        Ok(())
+    } else {
+        Err(error_message)
+    }
+}
+
+// Lines of a file with the header line first if header, blank lines are skipped
+struct LineSample {
+    header: Vec<u8>,
+    line: Vec<Vec<u8>>,
+    sample_byte_count: i64,
+}
+
+// Same as get_csv_sample, the first line after each of sample_row evenly spaced byte offsets
+fn read_sample_line<R: Read + Seek>(file: &mut R, file_size: i64, sample_row: i32, header: bool) -> std::io::Result<LineSample> {
+    let mut reader = BufReader::new(file);
+    let mut sample = LineSample {
+        header: Vec::new(),
+        line: Vec::new(),
+        sample_byte_count: 0,
+    };
+
+    let data_start = if header { reader.read_until(10, &mut sample.header)? as i64 } else { 0 };
+    let mut next_start_byte = data_start;
+    let mut current_line = Vec::new();
+
+    for n in 0..sample_row as i64 {
+        let mut start_byte = data_start + (file_size - data_start) * n / sample_row as i64;
+
+        // A line read for the previous offset covers this offset too
+        if start_byte <= next_start_byte {
+            start_byte = next_start_byte;
+            reader.seek(SeekFrom::Start(start_byte as u64))?;
+        } else {
+            reader.seek(SeekFrom::Start(start_byte as u64 - 1))?;
+            current_line.clear();
+            start_byte += reader.read_until(10, &mut current_line)? as i64 - 1;
+        }
+
+        loop {
+            current_line.clear();
+            let read_count = reader.read_until(10, &mut current_line)? as i64;
+            next_start_byte = start_byte + read_count;
+            start_byte = next_start_byte;
+
+            if read_count == 0 {
+                break;
+            }
+
+            if !trim_line_break(&current_line).iter().all(|x| x.is_ascii_whitespace()) {
+                sample.line.push(trim_line_break(&current_line).to_vec());
+                sample.sample_byte_count += read_count;
+                break;
+            }
+        }
+
+        if next_start_byte >= file_size {
+            break;
+        }
+    }
+
+    Ok(sample)
+}
+
+// A compressed file is sampled from a decompressed prefix of it, the size is then estimated
+fn read_file_sample(filepath: &str, sample_row: i32, header: bool) -> PyResult<(LineSample, CsvMeta)> {
+    Ok(sample_compressed(filepath, sample_row, |mut reader, file_size, sample_row| {
+        let sample = read_sample_line(&mut reader, file_size, sample_row, header)?;
+
+        let mut csv_meta = CsvMeta {
+            validate_row: sample.line.len() as i64,
+            is_line_br_13_exist: true,
+            is_line_br_10_exist: true,
+            file_size,
+            delimiter: 44,
+            compressed_size: file_size,
+            ..Default::default()
+        };
+
+        if sample.sample_byte_count > 0 {
+            csv_meta.estimate_row = (file_size - sample.header.len() as i64) * sample.line.len() as i64 / sample.sample_byte_count;
+        }
+
+        Ok((sample, csv_meta))
+    })?)
+}
+
+// Byte positions which are a space in every sampled line are gaps between columns. A column
+// starts at the first byte after a gap and ends where the next column starts, so right aligned
+// numbers of any width stay in their column.
+fn infer_layout(sample: &LineSample, header: bool) -> Layout {
+    let line_length = sample.line.iter().map(|x| x.len()).max().unwrap_or(0);
+    let mut is_gap = vec![true; line_length];
+
+    for line in &sample.line {
+        for (n, &x) in line.iter().enumerate() {
+            if x != b' ' && x != b'\t' {
+                is_gap[n] = false;
+            }
+        }
+    }
+
+    let column_start: Vec<usize> = (0..line_length).filter(|&n| !is_gap[n] && (n == 0 || is_gap[n - 1])).collect();
+    let mut layout = Layout::new();
+
+    for (n, &start_byte) in column_start.iter().enumerate() {
+        let start_byte = if n == 0 { 0 } else { start_byte };
+        let end_byte = column_start.get(n + 1).copied().unwrap_or(line_length);
+        let header_line = trim_line_break(&sample.header);
+
+        let column_name = match header_line.get(start_byte..end_byte.min(header_line.len())) {
+            Some(x) if header && !trim_space(x).is_empty() => String::from_utf8_lossy(trim_space(x)).to_string(),
+            _ => format!("column_{}", n + 1),
+        };
+
+        layout.push((column_name, start_byte, end_byte - start_byte, "text".to_string()));
+    }
+
+    layout
+}
+
+// One csv row with a comma delimiter, a cell is trimmed and a number is normalized. Returns
+// the column of the first cell which does not match its type.
+fn write_line(line: &[u8], layout: &Layout, byte_array: &mut Vec<u8>) -> Option<usize> {
+    let mut mismatch_column = None;
+
+    for (n, (_, start_byte, length, column_type)) in layout.iter().enumerate() {
+        if n > 0 {
+            byte_array.push(b',');
+        }
+
+        let start_byte = (*start_byte).min(line.len());
+        let cell = trim_space(&line[start_byte..(start_byte + length).min(line.len())]);
+
+        if column_type == "text" || cell.is_empty() {
+            write_csv_cell(cell, b',', byte_array);
+            continue;
+        }
+
+        let number = normalize_number(cell);
+        let is_match = parse_real_number(&number).is_some() && (column_type == "float" || !number.contains(&b'.'));
+
+        if !is_match && mismatch_column.is_none() {
+            mismatch_column = Some(n);
+        }
+
+        write_csv_cell(&number, b',', byte_array);
+    }
+
+    byte_array.extend_from_slice(b"\r\n");
+    mismatch_column
+}
+
+fn layout_meta(csv_meta: &mut CsvMeta, layout: &Layout) {
+    csv_meta.total_column = layout.len() as i32;
+    csv_meta.column_name = layout.iter().map(|x| x.0.clone()).collect();
+}
+
+fn mismatch_message(layout: &Layout, current_column: usize, line_name: &str) -> String {
+    format!(
+        "** Column {} of {} is not {} ** \n",
+        layout[current_column].0, line_name, layout[current_column].3
+    )
+}
+
+fn read_fixed_width_sample(filepath: &str, sample_row: i32, layout: Option<Layout>, header: bool) -> PyResult<(Vec<u8>, CsvMeta)> {
+    let sample_row = if sample_row <= 0 { 10 } else { sample_row };
+    let (sample, mut csv_meta) = read_file_sample(filepath, sample_row, header)?;
+
+    // A few sampled lines may leave gaps which are not column boundaries
+    let layout = match layout {
+        Some(layout) => layout,
+        None => infer_fixed_width_layout(filepath, 1000.max(sample_row), header)?,
+    };
+
+    if let Err(error_message) = check_layout(&layout) {
+        csv_meta.error_message = error_message;
+        return Ok((Vec::new(), csv_meta));
+    }
+
+    layout_meta(&mut csv_meta, &layout);
+
+    let mut byte_array = Vec::new();
+
+    for (n, line) in sample.line.iter().enumerate() {
+        if let Some(current_column) = write_line(line, &layout, &mut byte_array) {
+            csv_meta.error_message = mismatch_message(&layout, current_column, &format!("sampled line {}", n + 1));
+            return Ok((Vec::new(), csv_meta));
+        }
+    }
+
+    Ok((byte_array, csv_meta))
+}
+
+// Without a layout, the layout is inferred from 1,000 sampled lines
+fn read_fixed_width_file(filepath: &str, layout: Option<Layout>, header: bool) -> PyResult<(Vec<u8>, CsvMeta)> {
+    let (sample, mut csv_meta) = read_file_sample(filepath, 1000, header)?;
+    let layout = layout.unwrap_or_else(|| infer_layout(&sample, header));
+
+    if let Err(error_message) = check_layout(&layout) {
+        csv_meta.error_message = error_message;
+        return Ok((Vec::new(), csv_meta));
+    }
+
+    layout_meta(&mut csv_meta, &layout);
+
+    let mut reader = BufReader::with_capacity(1 << 20, open_decoder(filepath)?);
+    let mut byte_array = Vec::new();
+    let mut line = Vec::new();
+    let mut file_size = 0;
+    let mut current_line = 0;
+    let mut total_row = 0;
+
+    loop {
+        line.clear();
+        let read_count = reader.read_until(10, &mut line)?;
+
+        if read_count == 0 {
+            break;
+        }
+
+        file_size += read_count as i64;
+        current_line += 1;
+
+        if (header && current_line == 1) || trim_line_break(&line).iter().all(|x| x.is_ascii_whitespace()) {
+            continue;
+        }
+
+        if let Some(current_column) = write_line(trim_line_break(&line), &layout, &mut byte_array) {
+            csv_meta.error_message = mismatch_message(&layout, current_column, &format!("line {}", current_line));
+            return Ok((Vec::new(), csv_meta));
+        }
+
+        total_row += 1;
+    }
+
+    csv_meta.file_size = file_size;
+    csv_meta.validate_row = total_row;
+    csv_meta.estimate_row = total_row;
+
+    Ok((byte_array, csv_meta))
+}
+
+// Column boundaries inferred from the whitespace alignment of sampled lines, to be checked or
+// edited and then given to read_fixed_width
+#[pyfunction(sample_row = "1000", header = "false")]
+pub fn infer_fixed_width_layout(filepath: &str, sample_row: i32, header: bool) -> PyResult<Layout> {
+    let sample_row = if sample_row <= 0 { 1000 } else { sample_row };
+    let (sample, _) = read_file_sample(filepath, sample_row, header)?;
+    Ok(infer_layout(&sample, header))
+}
+
+// layout is a list of (column name, start byte, byte length, type), inferred if not given.
+// header is true if the first line has the column names.
+#[pyfunction(layout = "None", header = "false")]
+pub fn get_fixed_width_sample(filepath: &str, sample_row: i32, layout: Option<Layout>, header: bool) -> PyResult<Dataframe> {
+    let (byte_array, csv_meta) = read_fixed_width_sample(filepath, sample_row, layout, header)?;
+    Ok(Dataframe::new(byte_array, csv_meta))
+}
+
+#[pyfunction(layout = "None", header = "false")]
+pub fn read_fixed_width(filepath: &str, layout: Option<Layout>, header: bool) -> PyResult<Dataframe> {
+    let (byte_array, csv_meta) = read_fixed_width_file(filepath, layout, header)?;
+    Ok(Dataframe::new(byte_array, csv_meta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn sample_line(text: &[u8], sample_row: i32, header: bool) -> Vec<String> {
+        let sample = read_sample_line(&mut Cursor::new(text), text.len() as i64, sample_row, header).unwrap();
+        sample.line.iter().map(|x| String::from_utf8_lossy(x).to_string()).collect()
+    }
+
+    #[test]
+    fn number_without_padding() {
+        assert_eq!(normalize_number(b"+000123.40"), b"123.40");
+        assert_eq!(normalize_number(b"+000.50"), b"0.50");
+        assert_eq!(normalize_number(b"-000.50"), b"-0.50");
+        assert_eq!(normalize_number(b"000"), b"0");
+        assert_eq!(normalize_number(b"-"), b"-");
+        assert_eq!(normalize_number(b"+"), b"+");
+        assert_eq!(normalize_number(b"12"), b"12");
+    }
+
+    #[test]
+    fn sample_line_takes_the_first_line_after_each_offset() {
+        // Every line once, a blank line is skipped
+        assert_eq!(sample_line(b"H\nA\n\nB\nC\nD\n", 10, true), vec!["A", "B", "C", "D"]);
+
+        // The offset 6 is inside B, so C is taken; the offset 4 is the start of C, which is taken
+        assert_eq!(sample_line(b"H\nA\n\nB\nC\nD\n", 2, true), vec!["A", "C"]);
+        assert_eq!(sample_line(b"A\nB\nC\nD\n", 2, false), vec!["A", "C"]);
+    }
+
+    #[test]
+    fn layout_from_gaps_and_header() {
+        let sample = LineSample {
+            header: b"ID  NAME     AMOUNT\r\n".to_vec(),
+            line: vec![b"01  Alice     12.50".to_vec(), b"02  Bob     1234.00".to_vec()],
+            sample_byte_count: 0,
+        };
+
+        let layout: Vec<(String, usize, usize)> = infer_layout(&sample, true).into_iter().map(|x| (x.0, x.1, x.2)).collect();
+        assert_eq!(
+            layout,
+            vec![("ID".to_string(), 0, 4), ("NAME".to_string(), 4, 8), ("AMOUNT".to_string(), 12, 7)]
+        );
+
+        let layout = infer_layout(&sample, false);
+        assert_eq!(layout[2].0, "column_3");
+    }
+}
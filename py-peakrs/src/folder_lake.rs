@@ -0,0 +1,454 @@
+use crate::dataframe::Dataframe;
+use crate::filter::{filter_byte_array, is_condition_match, parse_filter_setting, FilterCondition};
+use crate::keyvalue::split_setting;
+use crate::partition::{get_header_byte_count, read_partition};
+use crate::split_file::manifest_to_dataframe;
+use crate::{cell_address, cell_per_row, cell_range, get_column_id, read_csv_file, read_csv_sample, unquote_cell, write_csv_cell, CsvMeta};
+use pyo3::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+// Hive style escape of characters not allowed in a folder name
+fn escape_partition_value(value: &[u8]) -> String {
+    if value.is_empty() {
+        return DEFAULT_PARTITION.to_string();
+    }
+
+    let mut result = Vec::with_capacity(value.len());
+
+    for &byte in value {
+        if byte < 32 || byte == 127 || b"\"#%'*/:=?\\{[]^|<>".contains(&byte) {
+            result.extend_from_slice(format!("%{:02X}", byte).as_bytes());
+        } else {
+            result.push(byte);
+        }
+    }
+
+    String::from_utf8_lossy(&result).to_string()
+}
+
+fn unescape_partition_value(value: &str) -> String {
+    if value == DEFAULT_PARTITION {
+        return String::new();
+    }
+
+    let byte_array = value.as_bytes();
+    let mut result = Vec::with_capacity(byte_array.len());
+    let mut n = 0;
+
+    while n < byte_array.len() {
+        if byte_array[n] == b'%' && n + 2 < byte_array.len() {
+            let hex = std::str::from_utf8(&byte_array[n + 1..n + 3]).ok();
+
+            if let Some(byte) = hex.and_then(|x| u8::from_str_radix(x, 16).ok()) {
+                result.push(byte);
+                n += 3;
+                continue;
+            }
+        }
+        result.push(byte_array[n]);
+        n += 1;
+    }
+
+    String::from_utf8_lossy(&result).to_string()
+}
+
+// Writes rows into folder/column=value/.../part-0.csv, keeping at most max_open_file files open.
+// The least recently used file is closed and reopened in append mode when needed again.
+pub(crate) struct FolderLakeWriter {
+    folder: PathBuf,
+    partition_column: Vec<usize>,
+    partition_column_name: Vec<String>,
+    data_column: Vec<usize>,
+    header_bytes: Vec<u8>,
+    line_br: Vec<u8>,
+    max_open_file: usize,
+    open_file: HashMap<PathBuf, (BufWriter<File>, u64)>,
+    partition_stat: BTreeMap<PathBuf, (i64, i64)>,
+    use_count: u64,
+}
+
+impl FolderLakeWriter {
+    pub(crate) fn new(folder: &str, csv_meta: &CsvMeta, partition_column: Vec<usize>, max_open_file: usize) -> FolderLakeWriter {
+        let data_column: Vec<usize> = (0..csv_meta.total_column as usize).filter(|x| !partition_column.contains(x)).collect();
+        let line_br = if csv_meta.is_line_br_13_exist { vec![13, 10] } else { vec![10] };
+
+        let mut header_bytes = data_column
+            .iter()
+            .map(|&x| csv_meta.column_name[x].clone())
+            .collect::<Vec<String>>()
+            .join(&(csv_meta.delimiter as char).to_string())
+            .into_bytes();
+        header_bytes.extend_from_slice(&line_br);
+
+        FolderLakeWriter {
+            folder: PathBuf::from(folder),
+            partition_column_name: partition_column.iter().map(|&x| csv_meta.column_name[x].clone()).collect(),
+            partition_column,
+            data_column,
+            header_bytes,
+            line_br,
+            max_open_file: max_open_file.max(1),
+            open_file: HashMap::new(),
+            partition_stat: BTreeMap::new(),
+            use_count: 0,
+        }
+    }
+
+    fn get_writer(&mut self, file_path: &Path) -> std::io::Result<&mut BufWriter<File>> {
+        self.use_count += 1;
+
+        if !self.open_file.contains_key(file_path) {
+            if self.open_file.len() >= self.max_open_file {
+                let least_used = self.open_file.iter().min_by_key(|(_, (_, x))| *x).map(|(x, _)| x.clone()).unwrap();
+                let (mut writer, _) = self.open_file.remove(&least_used).unwrap();
+                writer.flush()?;
+            }
+
+            // A file created in this run is appended, a file left by an earlier run is replaced
+            let writer = if self.partition_stat.contains_key(file_path) {
+                BufWriter::new(OpenOptions::new().append(true).open(file_path)?)
+            } else {
+                fs::create_dir_all(file_path.parent().unwrap())?;
+                let mut writer = BufWriter::new(File::create(file_path)?);
+                writer.write_all(&self.header_bytes)?;
+                self.partition_stat.insert(file_path.to_path_buf(), (0, self.header_bytes.len() as i64));
+                writer
+            };
+
+            self.open_file.insert(file_path.to_path_buf(), (writer, 0));
+        }
+
+        let (writer, last_use) = self.open_file.get_mut(file_path).unwrap();
+        *last_use = self.use_count;
+
+        Ok(writer)
+    }
+
+    pub(crate) fn write_byte_array(&mut self, byte_array: &[u8], csv_meta: &CsvMeta) -> std::io::Result<()> {
+        let cell_address = cell_address(byte_array, csv_meta);
+        let cell_per_row = cell_per_row(csv_meta);
+        let total_row = (cell_address.len() - 1) / cell_per_row;
+        let mut row_bytes = Vec::new();
+
+        for current_row in 0..total_row {
+            let first_cell = current_row * cell_per_row;
+            let mut file_path = self.folder.clone();
+
+            for (n, &current_column) in self.partition_column.iter().enumerate() {
+                let (start_byte, end_byte) = cell_range(byte_array, &cell_address, first_cell + current_column);
+                file_path.push(format!(
+                    "{}={}",
+                    self.partition_column_name[n],
+                    escape_partition_value(&unquote_cell(&byte_array[start_byte..end_byte]))
+                ));
+            }
+
+            file_path.push("part-0.csv");
+
+            row_bytes.clear();
+
+            for (n, &current_column) in self.data_column.iter().enumerate() {
+                if n > 0 {
+                    row_bytes.push(csv_meta.delimiter);
+                }
+
+                let start_byte = cell_address[first_cell + current_column] as usize;
+                let end_byte = cell_address[first_cell + current_column + 1] as usize - 1;
+                row_bytes.extend_from_slice(&byte_array[start_byte..end_byte]);
+            }
+
+            row_bytes.extend_from_slice(&self.line_br);
+
+            self.get_writer(&file_path)?.write_all(&row_bytes)?;
+
+            let stat = self.partition_stat.get_mut(&file_path).unwrap();
+            stat.0 += 1;
+            stat.1 += row_bytes.len() as i64;
+        }
+
+        Ok(())
+    }
+
+    // Returns (file_name, row_count, byte_count) of every partition file
+    pub(crate) fn finish(mut self) -> std::io::Result<Vec<(String, i64, i64)>> {
+        for (_, (writer, _)) in self.open_file.iter_mut() {
+            writer.flush()?;
+        }
+
+        Ok(self
+            .partition_stat
+            .iter()
+            .map(|(x, (row_count, byte_count))| (x.to_string_lossy().to_string(), *row_count, *byte_count))
+            .collect())
+    }
+}
+
+// "column, column => folder_name"
+pub(crate) fn parse_folder_lake_setting(setting: &str, csv_meta: &CsvMeta) -> Result<(Vec<usize>, String), String> {
+    let (left, _, folder) = split_setting(setting)?;
+
+    if folder.is_empty() {
+        return Err("** Fail to find folder name ** \n".to_string());
+    }
+
+    Ok((get_column_id(left, csv_meta)?, folder.to_string()))
+}
+
+pub(crate) fn write_folder_lake(byte_array: &[u8], csv_meta: &CsvMeta, partition_column: Vec<usize>, folder: &str, max_open_file: usize) -> std::io::Result<(Vec<u8>, CsvMeta)> {
+    let mut writer = FolderLakeWriter::new(folder, csv_meta, partition_column, max_open_file);
+    writer.write_byte_array(byte_array, csv_meta)?;
+    Ok(manifest_to_dataframe(&writer.finish()?))
+}
+
+// Returns the manifest of partition files as a dataframe
+#[pyfunction(max_open_file = "64")]
+pub fn create_folder_lake(df: &Dataframe, setting: &str, max_open_file: usize) -> PyResult<Dataframe> {
+    match parse_folder_lake_setting(setting, &df.csv_meta) {
+        Ok((partition_column, folder)) => {
+            let (byte_array, manifest_meta) = write_folder_lake(&df.byte_array, &df.csv_meta, partition_column, &folder, max_open_file)?;
+            Ok(Dataframe::new(byte_array, manifest_meta))
+        }
+        Err(error_message) => {
+            let (byte_array, mut manifest_meta) = manifest_to_dataframe(&[]);
+            manifest_meta.error_message.push_str(&error_message);
+            Ok(Dataframe::new(byte_array, manifest_meta))
+        }
+    }
+}
+
+// Stream a large file partition by partition into the folder lake
+#[pyfunction(partition_size = "67_108_864", max_open_file = "64")]
+pub fn create_folder_lake_file(filepath: &str, setting: &str, partition_size: i64, max_open_file: usize) -> PyResult<Dataframe> {
+    let (_, csv_meta) = read_csv_sample(filepath, 10)?;
+    let (byte_array, mut manifest_meta) = manifest_to_dataframe(&[]);
+
+    if !csv_meta.error_message.is_empty() {
+        manifest_meta.error_message.push_str(&csv_meta.error_message);
+        return Ok(Dataframe::new(byte_array, manifest_meta));
+    }
+
+    let (partition_column, folder) = match parse_folder_lake_setting(setting, &csv_meta) {
+        Ok(x) => x,
+        Err(error_message) => {
+            manifest_meta.error_message.push_str(&error_message);
+            return Ok(Dataframe::new(byte_array, manifest_meta));
+        }
+    };
+
+    let partition_size = if partition_size <= 0 { 67_108_864 } else { partition_size };
+    let mut writer = FolderLakeWriter::new(&folder, &csv_meta, partition_column, max_open_file);
+    let mut file = File::open(filepath)?;
+    let mut start_byte = get_header_byte_count(&mut file);
+
+    while start_byte < csv_meta.file_size {
+        let (byte_array, consumed_byte_count) =
+            read_partition(&mut file, start_byte, partition_size, csv_meta.file_size, &csv_meta);
+        start_byte += consumed_byte_count;
+        writer.write_byte_array(&byte_array, &csv_meta)?;
+    }
+
+    let (byte_array, manifest_meta) = manifest_to_dataframe(&writer.finish()?);
+    Ok(Dataframe::new(byte_array, manifest_meta))
+}
+
+// Every .csv file under folder with its column=value folder names
+fn get_lake_file(folder: &Path, partition_value: &mut Vec<(String, String)>, lake_file: &mut Vec<(PathBuf, Vec<(String, String)>)>) -> std::io::Result<()> {
+    let mut entry: Vec<PathBuf> = fs::read_dir(folder)?.filter_map(|x| x.ok()).map(|x| x.path()).collect();
+    entry.sort();
+
+    for path in entry {
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+
+        // A column=value folder is a partition even if the column starts with _ or .
+        let partition = name.find('=').filter(|_| path.is_dir());
+
+        if partition.is_none() && (name.starts_with('.') || name.starts_with('_')) {
+            continue;
+        }
+
+        if path.is_dir() {
+            if let Some(n) = partition {
+                partition_value.push((name[..n].to_string(), unescape_partition_value(&name[n + 1..])));
+                get_lake_file(&path, partition_value, lake_file)?;
+                partition_value.pop();
+            }
+        } else if name.to_lowercase().ends_with(".csv") {
+            lake_file.push((path, partition_value.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+// Read a folder lake as one dataframe, the partition columns are appended after the file columns.
+// Conditions on partition columns skip whole folders without opening their files.
+#[pyfunction(setting = "\"\"")]
+pub fn read_folder_lake(folder: &str, setting: &str) -> PyResult<Dataframe> {
+    let mut lake_file = Vec::new();
+    get_lake_file(Path::new(folder), &mut Vec::new(), &mut lake_file)?;
+
+    let mut csv_meta = CsvMeta::default();
+
+    if lake_file.is_empty() {
+        csv_meta.error_message.push_str(&format!("** Fail to find any csv file in folder {} ** \n", folder));
+        return Ok(Dataframe::new(Vec::new(), csv_meta));
+    }
+
+    let partition_column_name: Vec<String> = lake_file[0].1.iter().map(|(x, _)| x.clone()).collect();
+    let (_, first_meta) = read_csv_sample(&lake_file[0].0.to_string_lossy(), 10)?;
+
+    if !first_meta.error_message.is_empty() {
+        return Ok(Dataframe::new(Vec::new(), first_meta));
+    }
+
+    let mut column_name = first_meta.column_name.clone();
+    column_name.extend(partition_column_name.iter().cloned());
+
+    let filter_condition = match parse_filter_setting(setting, &column_name) {
+        Ok(x) => x,
+        Err(error_message) => {
+            csv_meta.error_message.push_str(&error_message);
+            return Ok(Dataframe::new(Vec::new(), csv_meta));
+        }
+    };
+
+    let file_column_count = first_meta.total_column as usize;
+    let (partition_condition, row_condition): (Vec<FilterCondition>, Vec<FilterCondition>) =
+        filter_condition.into_iter().partition(|x| x.column >= file_column_count);
+
+    csv_meta = first_meta.clone();
+    csv_meta.column_name = column_name;
+    csv_meta.total_column = csv_meta.column_name.len() as i32;
+    csv_meta.file_size = 0;
+
+    let mut result_bytes = Vec::new();
+    let mut total_row_count = 0;
+
+    for (file_path, partition_value) in &lake_file {
+        let current_name: Vec<String> = partition_value.iter().map(|(x, _)| x.clone()).collect();
+
+        if current_name != partition_column_name {
+            csv_meta.error_message.push_str(&format!(
+                "** Partition folders of {} are {:?}, but {:?} is expected ** \n",
+                file_path.to_string_lossy(),
+                current_name,
+                partition_column_name
+            ));
+            continue;
+        }
+
+        let is_match = partition_condition
+            .iter()
+            .all(|x| is_condition_match(partition_value[x.column - file_column_count].1.as_bytes(), x));
+
+        if !is_match {
+            continue;
+        }
+
+        let (byte_array, file_meta) = read_csv_file(&file_path.to_string_lossy())?;
+
+        if file_meta.column_name != first_meta.column_name {
+            csv_meta.error_message.push_str(&format!(
+                "** Column names of {} are different from {} ** \n",
+                file_path.to_string_lossy(),
+                lake_file[0].0.to_string_lossy()
+            ));
+            continue;
+        }
+
+        csv_meta.file_size += file_meta.file_size;
+
+        let byte_array = filter_byte_array(&byte_array, &file_meta, &row_condition, false);
+        let cell_address = cell_address(&byte_array, &file_meta);
+        let cell_per_row = cell_per_row(&file_meta);
+        let total_row = (cell_address.len() - 1) / cell_per_row;
+
+        for current_row in 0..total_row {
+            let first_cell = current_row * cell_per_row;
+            let start_byte = cell_address[first_cell] as usize;
+            let end_byte = cell_address[first_cell + cell_per_row] as usize;
+            let line_br_byte = cell_address[first_cell + file_column_count] as usize - 1;
+
+            result_bytes.extend_from_slice(&byte_array[start_byte..line_br_byte]);
+
+            for (_, value) in partition_value {
+                result_bytes.push(csv_meta.delimiter);
+                write_csv_cell(value.as_bytes(), csv_meta.delimiter, &mut result_bytes);
+            }
+
+            result_bytes.extend_from_slice(&byte_array[line_br_byte..end_byte]);
+            total_row_count += 1;
+        }
+    }
+
+    csv_meta.validate_row = total_row_count;
+    csv_meta.estimate_row = csv_meta.validate_row;
+
+    Ok(Dataframe::new(result_bytes, csv_meta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::test_dataframe;
+
+    fn temp_folder(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("peakrs_lake_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&folder);
+        folder
+    }
+
+    #[test]
+    fn partition_column_starting_with_underscore_reads_back() {
+        let folder = temp_folder("underscore");
+        let df = test_dataframe(b"1,x,a\n2,y,b\n1,z,c\n", &["_id", "v", "w"]);
+        write_folder_lake(&df.byte_array, &df.csv_meta, vec![0], folder.to_str().unwrap(), 64).unwrap();
+
+        // Marker files and hidden folders are still skipped
+        fs::write(folder.join("_SUCCESS"), b"").unwrap();
+        fs::create_dir_all(folder.join(".cache")).unwrap();
+        fs::write(folder.join(".cache").join("old.csv"), b"v,w\nq,d\n").unwrap();
+
+        let lake = read_folder_lake(folder.to_str().unwrap(), "").unwrap();
+        fs::remove_dir_all(&folder).unwrap();
+
+        assert_eq!(lake.csv_meta.error_message, "");
+        assert_eq!(lake.csv_meta.column_name, vec!["v", "w", "_id"]);
+        assert_eq!(lake.byte_array, b"x,a,1\nz,c,1\ny,b,2\n");
+        assert_eq!(lake.csv_meta.validate_row, 3);
+    }
+
+    #[test]
+    fn partition_value_is_written_as_csv_cell() {
+        let folder = temp_folder("cell");
+        let df = test_dataframe(b"\"a,b\",1,x\n\"say \"\"hi\"\"\",2,y\n", &["k", "v", "w"]);
+        write_folder_lake(&df.byte_array, &df.csv_meta, vec![0], folder.to_str().unwrap(), 64).unwrap();
+
+        let lake = read_folder_lake(folder.to_str().unwrap(), "").unwrap();
+        fs::remove_dir_all(&folder).unwrap();
+
+        assert_eq!(lake.csv_meta.total_column, 3);
+        assert_eq!(lake.byte_array, b"1,x,\"a,b\"\n2,y,\"say \"\"hi\"\"\"\n");
+        assert_eq!(lake.total_row(), 2);
+    }
+
+    #[test]
+    fn partition_value_is_quoted_for_the_delimiter() {
+        let folder = temp_folder("delimiter");
+        let mut df = test_dataframe(b"\"a;b\";1;x\nc;2;y\n", &["k", "v", "w"]);
+        df.csv_meta.delimiter = b';';
+        write_folder_lake(&df.byte_array, &df.csv_meta, vec![0], folder.to_str().unwrap(), 64).unwrap();
+
+        let lake = read_folder_lake(folder.to_str().unwrap(), "").unwrap();
+        fs::remove_dir_all(&folder).unwrap();
+
+        assert_eq!(lake.csv_meta.delimiter, b';');
+        assert_eq!(lake.byte_array, b"1;x;\"a;b\"\n2;y;c\n");
+        assert_eq!(lake.total_row(), 2);
+    }
+}
@@ -0,0 +1,187 @@
+use crate::add_column::format_number;
+use crate::dataframe::Dataframe;
+use crate::keyvalue::get_composite_key;
+use crate::{cell_per_row, cell_range, get_column_id, parse_real_number, CsvMeta};
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq)]
+enum AggregateType {
+    Count,
+    Sum,
+    Max,
+    Min,
+}
+
+struct Aggregate {
+    aggregate_type: AggregateType,
+    column: usize,
+}
+
+struct GroupbySetting {
+    group_column: Vec<usize>,
+    aggregate: Vec<Aggregate>,
+    column_name: Vec<String>,
+}
+
+// e.g. "region, year => count() sum(amount) max(amount) min(amount)"
+// Output column names are count, sum_amount, max_amount and min_amount
+fn parse_groupby_setting(setting: &str, csv_meta: &CsvMeta) -> Result<GroupbySetting, String> {
+    let (left, right) = match setting.find("=>") {
+        Some(n) => (&setting[..n], &setting[n + 2..]),
+        None => return Err(format!("** Missing => in {} ** \n", setting)),
+    };
+
+    let group_column = if left.trim().is_empty() { Vec::new() } else { get_column_id(left, csv_meta)? };
+    let mut aggregate = Vec::new();
+    let mut column_name: Vec<String> = group_column.iter().map(|&x| csv_meta.column_name[x].clone()).collect();
+    let mut error_message = String::new();
+
+    for item in right.split(')') {
+        let item = item.trim_matches(|x: char| x == ',' || x.is_whitespace());
+
+        if item.is_empty() {
+            continue;
+        }
+
+        let (function_name, column) = match item.find('(') {
+            Some(n) => (item[..n].trim().to_lowercase(), item[n + 1..].trim()),
+            None => (item.to_lowercase(), ""),
+        };
+
+        let aggregate_type = match function_name.as_str() {
+            "count" => AggregateType::Count,
+            "sum" => AggregateType::Sum,
+            "max" => AggregateType::Max,
+            "min" => AggregateType::Min,
+            _ => {
+                error_message.push_str(&format!("** Aggregate function {} is not supported ** \n", function_name));
+                continue;
+            }
+        };
+
+        if aggregate_type == AggregateType::Count {
+            aggregate.push(Aggregate { aggregate_type, column: 0 });
+            column_name.push("count".to_string());
+            continue;
+        }
+
+        match csv_meta.column_name.iter().position(|x| x == column) {
+            Some(n) => {
+                aggregate.push(Aggregate { aggregate_type, column: n });
+                column_name.push(format!("{}_{}", function_name, column));
+            }
+            None => error_message.push_str(&format!("** Column {} is not found ** \n", column)),
+        }
+    }
+
+    if aggregate.is_empty() && error_message.is_empty() {
+        error_message.push_str("** Fail to find any aggregate function ** \n");
+    }
+
+    if error_message.is_empty() {
+        Ok(GroupbySetting {
+            group_column,
+            aggregate,
+            column_name,
+        })
+    } else {
+        Err(error_message)
+    }
+}
+
+// Groups are output in order of first appearance. Cells which are not real numbers are skipped by sum, max and min.
+#[pyfunction]
+pub fn groupby(df: &Dataframe, setting: &str) -> PyResult<Dataframe> {
+    let GroupbySetting { group_column, aggregate, column_name } = match parse_groupby_setting(setting, &df.csv_meta) {
+        Ok(x) => x,
+        Err(error_message) => return Ok(df.with_error(&error_message)),
+    };
+
+    let byte_array = &df.byte_array;
+    let mut csv_meta = df.csv_meta.clone();
+    let cell_address = df.cell_address();
+    let cell_per_row = cell_per_row(&csv_meta);
+    let total_row = (cell_address.len() - 1) / cell_per_row;
+    let mut group_id: HashMap<Vec<u8>, usize> = HashMap::new();
+    let mut group_first_row = Vec::new();
+    let mut group_count: Vec<i64> = Vec::new();
+    let mut group_value: Vec<Vec<Option<f64>>> = Vec::new();
+    let mut composite_key = Vec::new();
+
+    for current_row in 0..total_row {
+        let first_cell = current_row * cell_per_row;
+
+        get_composite_key(byte_array, cell_address, first_cell, &group_column, &mut composite_key);
+
+        let current_group = match group_id.get(&composite_key) {
+            Some(&n) => n,
+            None => {
+                group_id.insert(composite_key.clone(), group_first_row.len());
+                group_first_row.push(current_row);
+                group_count.push(0);
+                group_value.push(vec![None; aggregate.len()]);
+                group_first_row.len() - 1
+            }
+        };
+
+        group_count[current_group] += 1;
+
+        for (n, current_aggregate) in aggregate.iter().enumerate() {
+            if current_aggregate.aggregate_type == AggregateType::Count {
+                continue;
+            }
+
+            let (start_byte, end_byte) = cell_range(byte_array, cell_address, first_cell + current_aggregate.column);
+
+            if let Some(x) = parse_real_number(&byte_array[start_byte..end_byte]) {
+                let value = &mut group_value[current_group][n];
+
+                *value = Some(match (current_aggregate.aggregate_type, *value) {
+                    (_, None) => x,
+                    (AggregateType::Sum, Some(y)) => x + y,
+                    (AggregateType::Max, Some(y)) => x.max(y),
+                    (_, Some(y)) => x.min(y),
+                });
+            }
+        }
+    }
+
+    let mut result_bytes = Vec::new();
+
+    for (current_group, &current_row) in group_first_row.iter().enumerate() {
+        let first_cell = current_row * cell_per_row;
+
+        for (n, &current_column) in group_column.iter().enumerate() {
+            if n > 0 {
+                result_bytes.push(csv_meta.delimiter);
+            }
+
+            let (start_byte, end_byte) = cell_range(byte_array, cell_address, first_cell + current_column);
+            result_bytes.extend_from_slice(&byte_array[start_byte..end_byte]);
+        }
+
+        for (n, current_aggregate) in aggregate.iter().enumerate() {
+            if n > 0 || !group_column.is_empty() {
+                result_bytes.push(csv_meta.delimiter);
+            }
+
+            if current_aggregate.aggregate_type == AggregateType::Count {
+                result_bytes.extend_from_slice(group_count[current_group].to_string().as_bytes());
+            } else if let Some(x) = group_value[current_group][n] {
+                result_bytes.extend_from_slice(format_number(x, -1).as_bytes());
+            }
+        }
+
+        if csv_meta.is_line_br_13_exist {
+            result_bytes.push(13);
+        }
+        result_bytes.push(10);
+    }
+
+    csv_meta.column_name = column_name;
+    csv_meta.total_column = csv_meta.column_name.len() as i32;
+    csv_meta.validate_row = group_first_row.len() as i64;
+
+    Ok(Dataframe::new(result_bytes, csv_meta))
+}
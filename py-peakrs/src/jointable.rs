@@ -0,0 +1,337 @@
+use crate::dataframe::Dataframe;
+use crate::keyvalue::{build_keyvalue_table, get_composite_key, split_setting, KeyValueTable};
+use crate::{cell_address, cell_per_row, cell_range, count_row, unquote_cell, write_csv_cell, CsvMeta};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq)]
+enum JoinType {
+    Inner,
+    Left,
+    Right,
+    Full,
+    Semi,
+    Anti,
+}
+
+struct JoinTableSetting {
+    join_type: JoinType,
+    left_key_column: Vec<usize>,
+    right_key_column: Vec<usize>,
+    table_name: String,
+}
+
+// e.g. "customer_id = id, region => left(customer)"
+// where a column without = has the same name in both tables
+fn parse_jointable_setting(setting: &str, csv_meta: &CsvMeta, right_csv_meta: &CsvMeta) -> Result<JoinTableSetting, String> {
+    let (left, join_type, table_name) = split_setting(setting)?;
+
+    let join_type = match join_type.to_lowercase().as_str() {
+        "inner" => JoinType::Inner,
+        "all_match" | "allmatch" | "left" => JoinType::Left,
+        "right" => JoinType::Right,
+        "full" | "full_outer" | "outer" => JoinType::Full,
+        "semi" => JoinType::Semi,
+        "anti" => JoinType::Anti,
+        _ => {
+            return Err(format!(
+                "** Join type {} is not supported, use all_match, inner, left, right, full_outer, semi or anti ** \n",
+                join_type
+            ))
+        }
+    };
+
+    let mut left_key_column = Vec::new();
+    let mut right_key_column = Vec::new();
+    let mut error_message = String::new();
+
+    for item in left.split(',') {
+        let item = item.trim();
+
+        if item.is_empty() {
+            continue;
+        }
+
+        let (left_column, right_column) = match item.find('=') {
+            Some(n) => (item[..n].trim(), item[n + 1..].trim()),
+            None => (item, item),
+        };
+
+        match csv_meta.column_name.iter().position(|x| x == left_column) {
+            Some(n) => left_key_column.push(n),
+            None => error_message.push_str(&format!("** Column {} is not found in left table ** \n", left_column)),
+        }
+
+        match right_csv_meta.column_name.iter().position(|x| x == right_column) {
+            Some(n) => right_key_column.push(n),
+            None => error_message.push_str(&format!("** Column {} is not found in right table ** \n", right_column)),
+        }
+    }
+
+    if left_key_column.is_empty() && error_message.is_empty() {
+        error_message.push_str("** Fail to find any join column ** \n");
+    }
+
+    if !error_message.is_empty() {
+        return Err(error_message);
+    }
+
+    Ok(JoinTableSetting {
+        join_type,
+        left_key_column,
+        right_key_column,
+        table_name: if table_name.is_empty() { "right".to_string() } else { table_name.to_string() },
+    })
+}
+
+// Right table column names colliding with left table names get the suffix
+fn get_jointable_column_name(csv_meta: &CsvMeta, table: &KeyValueTable, join_type: JoinType, suffix: &str) -> Vec<String> {
+    let mut column_name = csv_meta.column_name.clone();
+
+    if join_type == JoinType::Semi || join_type == JoinType::Anti {
+        return column_name;
+    }
+
+    for current_name in &table.value_column_name {
+        if csv_meta.column_name.contains(current_name) {
+            column_name.push(format!("{}{}", current_name, suffix));
+        } else {
+            column_name.push(current_name.clone());
+        }
+    }
+
+    column_name
+}
+
+// A right cell is quoted again for the delimiter of the left table, which may differ
+fn push_right_byte(result_bytes: &mut Vec<u8>, table: &KeyValueTable, cell_id: usize, delimiter: u8) {
+    let (start_byte, end_byte) = cell_range(&table.byte_array, &table.cell_address, cell_id);
+    write_csv_cell(&unquote_cell(&table.byte_array[start_byte..end_byte]), delimiter, result_bytes);
+}
+
+fn push_right_cell(result_bytes: &mut Vec<u8>, table: &KeyValueTable, right_row: Option<usize>, delimiter: u8) {
+    for &current_column in &table.value_column {
+        result_bytes.push(delimiter);
+
+        if let Some(current_row) = right_row {
+            push_right_byte(result_bytes, table, current_row * table.cell_per_row + current_column, delimiter);
+        }
+    }
+}
+
+fn jointable_byte_array(byte_array: &[u8], csv_meta: &CsvMeta, table: &KeyValueTable, join_setting: &JoinTableSetting) -> Vec<u8> {
+    let cell_address = cell_address(byte_array, csv_meta);
+    let cell_per_row = cell_per_row(csv_meta);
+    let total_row = (cell_address.len() - 1) / cell_per_row;
+    let total_column = csv_meta.total_column as usize;
+    let join_type = join_setting.join_type;
+    let right_total_row = (table.cell_address.len() - 1) / table.cell_per_row;
+    let mut is_right_row_matched = vec![false; right_total_row];
+    let mut composite_key = Vec::new();
+    let mut result_bytes = Vec::with_capacity(byte_array.len() * 2);
+
+    for current_row in 0..total_row {
+        let first_cell = current_row * cell_per_row;
+        let start_byte = cell_address[first_cell] as usize;
+        let end_byte = cell_address[first_cell + cell_per_row] as usize;
+        let line_br_byte = cell_address[first_cell + total_column] as usize - 1;
+
+        get_composite_key(byte_array, &cell_address, first_cell, &join_setting.left_key_column, &mut composite_key);
+
+        match (table.key_row.get(&composite_key), join_type) {
+            (Some(_), JoinType::Semi) | (None, JoinType::Anti) => {
+                result_bytes.extend_from_slice(&byte_array[start_byte..end_byte]);
+            }
+            (Some(right_row), JoinType::Inner | JoinType::Left | JoinType::Right | JoinType::Full) => {
+                for &current_right_row in right_row {
+                    is_right_row_matched[current_right_row] = true;
+                    result_bytes.extend_from_slice(&byte_array[start_byte..line_br_byte]);
+                    push_right_cell(&mut result_bytes, table, Some(current_right_row), csv_meta.delimiter);
+                    result_bytes.extend_from_slice(&byte_array[line_br_byte..end_byte]);
+                }
+            }
+            (None, JoinType::Left | JoinType::Full) => {
+                result_bytes.extend_from_slice(&byte_array[start_byte..line_br_byte]);
+                push_right_cell(&mut result_bytes, table, None, csv_meta.delimiter);
+                result_bytes.extend_from_slice(&byte_array[line_br_byte..end_byte]);
+            }
+            _ => {}
+        }
+    }
+
+    // Unmatched right rows, with their key values placed in the left key columns
+    if join_type == JoinType::Right || join_type == JoinType::Full {
+        for (current_right_row, &is_matched) in is_right_row_matched.iter().enumerate() {
+            if is_matched {
+                continue;
+            }
+
+            for current_column in 0..total_column {
+                if current_column > 0 {
+                    result_bytes.push(csv_meta.delimiter);
+                }
+
+                if let Some(n) = join_setting.left_key_column.iter().position(|&x| x == current_column) {
+                    let cell_id = current_right_row * table.cell_per_row + join_setting.right_key_column[n];
+                    push_right_byte(&mut result_bytes, table, cell_id, csv_meta.delimiter);
+                }
+            }
+
+            push_right_cell(&mut result_bytes, table, Some(current_right_row), csv_meta.delimiter);
+
+            if csv_meta.is_line_br_13_exist {
+                result_bytes.push(13);
+            }
+            result_bytes.push(10);
+        }
+    }
+
+    result_bytes
+}
+
+#[pyfunction(suffix = "\"\"")]
+pub fn jointable(df: &Dataframe, right_df: &Dataframe, setting: &str, suffix: &str) -> PyResult<Dataframe> {
+    let join_setting = match parse_jointable_setting(setting, &df.csv_meta, &right_df.csv_meta) {
+        Ok(join_setting) => join_setting,
+        Err(error_message) => return Ok(df.with_error(&error_message)),
+    };
+
+    let suffix = if suffix.is_empty() { format!("_{}", join_setting.table_name) } else { suffix.to_string() };
+    let table = build_keyvalue_table(right_df.byte_array.clone(), &right_df.csv_meta, join_setting.right_key_column.clone());
+    let result_bytes = jointable_byte_array(&df.byte_array, &df.csv_meta, &table, &join_setting);
+    let mut csv_meta = df.csv_meta.clone();
+
+    csv_meta.column_name = get_jointable_column_name(&csv_meta, &table, join_setting.join_type, &suffix);
+    csv_meta.total_column = csv_meta.column_name.len() as i32;
+    csv_meta.validate_row = count_row(&result_bytes);
+
+    Ok(Dataframe::new(result_bytes, csv_meta))
+}
+
+// Key cardinality of both tables, to check whether a join is one-to-one, one-to-many or many-to-many
+// before running it
+#[pyfunction]
+pub fn join_cardinality(df: &Dataframe, right_df: &Dataframe, setting: &str) -> PyResult<HashMap<String, i64>> {
+    let mut report = HashMap::new();
+
+    let join_setting = match parse_jointable_setting(setting, &df.csv_meta, &right_df.csv_meta) {
+        Ok(join_setting) => join_setting,
+        Err(error_message) => return Err(PyValueError::new_err(error_message)),
+    };
+
+    let left_table = build_keyvalue_table(df.byte_array.clone(), &df.csv_meta, join_setting.left_key_column.clone());
+    let right_table = build_keyvalue_table(right_df.byte_array.clone(), &right_df.csv_meta, join_setting.right_key_column.clone());
+
+    let mut matched_key = 0;
+    let mut inner_row = 0;
+    let mut semi_row = 0;
+    let mut left_only_row = 0;
+    let mut right_only_row = 0;
+
+    for (composite_key, left_row) in &left_table.key_row {
+        match right_table.key_row.get(composite_key) {
+            Some(right_row) => {
+                matched_key += 1;
+                inner_row += (left_row.len() * right_row.len()) as i64;
+                semi_row += left_row.len() as i64;
+            }
+            None => left_only_row += left_row.len() as i64,
+        }
+    }
+
+    for (composite_key, right_row) in &right_table.key_row {
+        if !left_table.key_row.contains_key(composite_key) {
+            right_only_row += right_row.len() as i64;
+        }
+    }
+
+    let left_distinct_key = left_table.key_row.len() as i64;
+    let right_distinct_key = right_table.key_row.len() as i64;
+
+    report.insert("left_row".to_string(), ((left_table.cell_address.len() - 1) / left_table.cell_per_row) as i64);
+    report.insert("right_row".to_string(), ((right_table.cell_address.len() - 1) / right_table.cell_per_row) as i64);
+    report.insert("left_distinct_key".to_string(), left_distinct_key);
+    report.insert("right_distinct_key".to_string(), right_distinct_key);
+    report.insert("matched_key".to_string(), matched_key);
+    report.insert("left_only_key".to_string(), left_distinct_key - matched_key);
+    report.insert("right_only_key".to_string(), right_distinct_key - matched_key);
+    report.insert("left_max_duplicate".to_string(), left_table.key_row.values().map(|x| x.len()).max().unwrap_or(0) as i64);
+    report.insert("right_max_duplicate".to_string(), right_table.key_row.values().map(|x| x.len()).max().unwrap_or(0) as i64);
+    report.insert("inner_join_row".to_string(), inner_row);
+    report.insert("left_join_row".to_string(), inner_row + left_only_row);
+    report.insert("right_join_row".to_string(), inner_row + right_only_row);
+    report.insert("full_outer_join_row".to_string(), inner_row + left_only_row + right_only_row);
+    report.insert("semi_join_row".to_string(), semi_row);
+    report.insert("anti_join_row".to_string(), left_only_row);
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::test_dataframe;
+
+    // The right table is ; delimited, so a, must be quoted and "c;d" unquoted in the joined rows
+    fn join(join_type: &str, suffix: &str) -> Dataframe {
+        let df = test_dataframe(b"1,x\n2,y\n3,z\n", &["id", "v"]);
+        let mut right_df = test_dataframe(b"1;a,b\n1;\"c;d\"\n4;r\n", &["id", "v"]);
+        right_df.csv_meta.delimiter = b';';
+
+        jointable(&df, &right_df, &format!("id => {}(r)", join_type), suffix).unwrap()
+    }
+
+    #[test]
+    fn every_join_type() {
+        let inner = b"1,x,\"a,b\"\n1,x,c;d\n".to_vec();
+        let left = [&inner[..], b"2,y,\n3,z,\n"].concat();
+
+        assert_eq!(join("inner", "").byte_array, inner);
+        assert_eq!(join("left", "").byte_array, left);
+        assert_eq!(join("right", "").byte_array, [&inner[..], b"4,,r\n"].concat());
+        assert_eq!(join("full_outer", "").byte_array, [&left[..], b"4,,r\n"].concat());
+        assert_eq!(join("semi", "").byte_array, b"1,x\n");
+        assert_eq!(join("anti", "").byte_array, b"2,y\n3,z\n");
+
+        let full = join("full_outer", "");
+        assert_eq!(full.csv_meta.validate_row, 5);
+        assert_eq!(full.total_row(), 5);
+    }
+
+    #[test]
+    fn colliding_column_gets_suffix() {
+        assert_eq!(join("left", "").csv_meta.column_name, vec!["id", "v", "v_r"]);
+        assert_eq!(join("left", "_new").csv_meta.column_name, vec!["id", "v", "v_new"]);
+        assert_eq!(join("semi", "").csv_meta.column_name, vec!["id", "v"]);
+    }
+
+    #[test]
+    fn cardinality_matches_join_rows() {
+        let df = test_dataframe(b"1,x\n2,y\n3,z\n", &["id", "v"]);
+        let right_df = test_dataframe(b"1,p\n1,q\n4,r\n", &["id", "v"]);
+        let report = join_cardinality(&df, &right_df, "id => inner(r)").unwrap();
+
+        let expected = [
+            ("left_row", 3),
+            ("right_row", 3),
+            ("left_distinct_key", 3),
+            ("right_distinct_key", 2),
+            ("matched_key", 1),
+            ("left_only_key", 2),
+            ("right_only_key", 1),
+            ("left_max_duplicate", 1),
+            ("right_max_duplicate", 2),
+            ("inner_join_row", 2),
+            ("left_join_row", 4),
+            ("right_join_row", 3),
+            ("full_outer_join_row", 5),
+            ("semi_join_row", 1),
+            ("anti_join_row", 2),
+        ];
+
+        for (key, value) in expected.iter() {
+            assert_eq!(report[*key], *value, "{}", key);
+        }
+    }
+}
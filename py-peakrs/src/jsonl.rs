@@ -0,0 +1,347 @@
+use crate::compression::{get_compression, open_decoder, sample_compressed, Compression};
+use crate::dataframe::Dataframe;
+use crate::{cell_address, cell_per_row, cell_range, unquote_cell, write_csv_cell, CsvMeta};
+use pyo3::prelude::*;
+use serde_json::{Map, Number, Value};
+use std::collections::HashMap;
+use std::fs::{metadata, File};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+
+// Column names in the order first seen, a nested object is flattened to parent.child columns
+#[derive(Default)]
+struct JsonlSchema {
+    column_name: Vec<String>,
+    column_id: HashMap<String, usize>,
+}
+
+impl JsonlSchema {
+    fn get_column_id(&mut self, column_name: &str) -> usize {
+        if let Some(&n) = self.column_id.get(column_name) {
+            return n;
+        }
+
+        self.column_name.push(column_name.to_string());
+        self.column_id.insert(column_name.to_string(), self.column_name.len() - 1);
+        self.column_name.len() - 1
+    }
+
+    // (column, cell) of an object, a null is None
+    fn flatten(&mut self, prefix: &str, object: &Map<String, Value>, row: &mut Vec<(usize, Option<String>)>) {
+        for (key, value) in object {
+            let column_name = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+
+            match value {
+                Value::Object(child) if !child.is_empty() => self.flatten(&column_name, child, row),
+                Value::Null => row.push((self.get_column_id(&column_name), None)),
+                Value::String(text) => row.push((self.get_column_id(&column_name), Some(text.clone()))),
+                // Numbers and booleans as written, arrays and empty objects as JSON text
+                value => row.push((self.get_column_id(&column_name), Some(value.to_string()))),
+            }
+        }
+    }
+}
+
+// A JSON object of one line, None for a blank line
+fn parse_line(line: &[u8]) -> Result<Option<Map<String, Value>>, ()> {
+    if line.iter().all(|x| x.is_ascii_whitespace()) {
+        return Ok(None);
+    }
+
+    match serde_json::from_slice(line) {
+        Ok(Value::Object(object)) => Ok(Some(object)),
+        _ => Err(()),
+    }
+}
+
+// One csv row of total_column cells with a comma delimiter, a null or missing key is an empty cell
+fn write_row(row: &[(usize, Option<String>)], total_column: usize, byte_array: &mut Vec<u8>) {
+    let mut cell = vec![None; total_column];
+
+    for (current_column, text) in row {
+        cell[*current_column] = text.as_deref();
+    }
+
+    for (n, text) in cell.into_iter().enumerate() {
+        if n > 0 {
+            byte_array.push(b',');
+        }
+        if let Some(text) = text {
+            write_csv_cell(text.as_bytes(), b',', byte_array);
+        }
+    }
+
+    byte_array.extend_from_slice(b"\r\n");
+}
+
+fn jsonl_meta(schema: JsonlSchema, total_row: i64, file_size: i64) -> CsvMeta {
+    let mut csv_meta = CsvMeta {
+        total_column: schema.column_name.len() as i32,
+        validate_row: total_row,
+        estimate_row: total_row,
+        is_line_br_13_exist: true,
+        is_line_br_10_exist: true,
+        column_name: schema.column_name,
+        file_size,
+        delimiter: 44,
+        compression: Compression::None.name().to_string(),
+        compressed_size: file_size,
+        ..Default::default()
+    };
+
+    if total_row == 0 {
+        csv_meta.error_message.push_str("** Fail to find any JSON object ** \n");
+    } else if csv_meta.total_column == 0 {
+        csv_meta.error_message.push_str("** Fail to find any column name ** \n");
+    }
+
+    if !csv_meta.error_message.is_empty() {
+        csv_meta.validate_row = 0;
+    }
+
+    csv_meta
+}
+
+// Same as get_csv_sample, the first line after each of sample_row evenly spaced byte offsets
+fn read_sample<R: Read + Seek>(file: &mut R, file_size: i64, mut sample_row: i32) -> std::io::Result<(Vec<u8>, CsvMeta)> {
+    if sample_row <= 0 {
+        sample_row = 10;
+    }
+
+    let mut schema = JsonlSchema::default();
+    let mut sample = Vec::new();
+    let mut sample_byte_count = 0;
+    let mut next_start_byte = 0;
+    let mut line = Vec::new();
+
+    for n in 0..sample_row as i64 {
+        let mut start_byte = file_size * n / sample_row as i64;
+
+        // A line read for the previous offset covers this offset too
+        if start_byte < next_start_byte {
+            start_byte = next_start_byte;
+        }
+
+        if start_byte >= file_size {
+            break;
+        }
+
+        let mut reader = BufReader::new(&mut *file);
+
+        // The line starts after the first line break from start_byte - 1
+        if start_byte > 0 && start_byte > next_start_byte {
+            reader.seek(SeekFrom::Start(start_byte as u64 - 1))?;
+            line.clear();
+            start_byte += reader.read_until(10, &mut line)? as i64 - 1;
+        } else {
+            reader.seek(SeekFrom::Start(start_byte as u64))?;
+        }
+
+        // Blank lines are skipped
+        loop {
+            line.clear();
+            let read_count = reader.read_until(10, &mut line)? as i64;
+            next_start_byte = start_byte + read_count;
+
+            if read_count == 0 {
+                break;
+            }
+
+            match parse_line(&line) {
+                Ok(Some(object)) => {
+                    let mut row = Vec::new();
+                    schema.flatten("", &object, &mut row);
+                    sample.push(row);
+                    sample_byte_count += read_count;
+                    break;
+                }
+                Ok(None) => start_byte = next_start_byte,
+                Err(()) => {
+                    let csv_meta = CsvMeta {
+                        file_size,
+                        error_message: format!("** Line at byte {} is not a JSON object ** \n", start_byte),
+                        ..Default::default()
+                    };
+                    return Ok((Vec::new(), csv_meta));
+                }
+            }
+        }
+    }
+
+    let mut byte_array = Vec::new();
+
+    for row in &sample {
+        write_row(row, schema.column_name.len(), &mut byte_array);
+    }
+
+    let mut csv_meta = jsonl_meta(schema, sample.len() as i64, file_size);
+
+    if sample_byte_count > 0 {
+        csv_meta.estimate_row = file_size * sample.len() as i64 / sample_byte_count;
+    }
+
+    Ok((byte_array, csv_meta))
+}
+
+// A compressed file is sampled from a decompressed prefix of it, as get_csv_sample
+fn read_jsonl_sample(filepath: &str, sample_row: i32) -> PyResult<(Vec<u8>, CsvMeta)> {
+    Ok(sample_compressed(filepath, sample_row, |mut reader, file_size, sample_row| {
+        read_sample(&mut reader, file_size, sample_row)
+    })?)
+}
+
+// Every line of the file, the columns are known only after the last line, so rows are
+// flattened first and then written with all columns
+fn read_jsonl_file(filepath: &str) -> PyResult<(Vec<u8>, CsvMeta)> {
+    let compression = get_compression(filepath)?;
+    let mut reader = BufReader::with_capacity(1 << 20, open_decoder(filepath)?);
+    let mut schema = JsonlSchema::default();
+    let mut all_row = Vec::new();
+    let mut line = Vec::new();
+    let mut file_size = 0;
+    let mut current_line = 0;
+
+    loop {
+        line.clear();
+        let read_count = reader.read_until(10, &mut line)?;
+
+        if read_count == 0 {
+            break;
+        }
+
+        file_size += read_count as i64;
+        current_line += 1;
+
+        match parse_line(&line) {
+            Ok(Some(object)) => {
+                let mut row = Vec::new();
+                schema.flatten("", &object, &mut row);
+                all_row.push(row);
+            }
+            Ok(None) => {}
+            Err(()) => {
+                let csv_meta = CsvMeta {
+                    file_size,
+                    error_message: format!("** Line {} is not a JSON object ** \n", current_line),
+                    ..Default::default()
+                };
+                return Ok((Vec::new(), csv_meta));
+            }
+        }
+    }
+
+    let mut byte_array = Vec::new();
+
+    for row in &all_row {
+        write_row(row, schema.column_name.len(), &mut byte_array);
+    }
+
+    let mut csv_meta = jsonl_meta(schema, all_row.len() as i64, file_size);
+    csv_meta.compression = compression.name().to_string();
+    csv_meta.compressed_size = metadata(filepath)?.len() as i64;
+
+    Ok((byte_array, csv_meta))
+}
+
+// A cell to a JSON value: an empty cell is null, true and false are booleans, a JSON number is a
+// number, a JSON array or object is kept as it is, other cells are strings. A csv cell has no type,
+// so a string "123" or "true" read from JSON Lines is written back as 123 or true.
+fn cell_to_value(cell: &[u8]) -> Value {
+    if cell.is_empty() {
+        return Value::Null;
+    }
+
+    match cell {
+        b"true" => return Value::Bool(true),
+        b"false" => return Value::Bool(false),
+        _ => {}
+    }
+
+    if let Ok(number) = serde_json::from_slice::<Number>(cell) {
+        return Value::Number(number);
+    }
+
+    if cell[0] == b'[' || cell[0] == b'{' {
+        if let Ok(value) = serde_json::from_slice::<Value>(cell) {
+            return value;
+        }
+    }
+
+    Value::String(String::from_utf8_lossy(cell).to_string())
+}
+
+pub(crate) fn write_jsonl_file(df: &Dataframe, file_name: &str) -> std::io::Result<()> {
+    let csv_meta = &df.csv_meta;
+    let address = cell_address(&df.byte_array, csv_meta);
+    let mut f = BufWriter::new(File::create(file_name)?);
+
+    if cell_per_row(csv_meta) > 0 {
+        for current_row in 0..df.total_row() {
+            let first_cell = current_row * cell_per_row(csv_meta);
+            let mut object = Map::new();
+
+            for (current_column, column_name) in csv_meta.column_name.iter().enumerate() {
+                let (start_byte, end_byte) = cell_range(&df.byte_array, &address, first_cell + current_column);
+                object.insert(column_name.clone(), cell_to_value(&unquote_cell(&df.byte_array[start_byte..end_byte])));
+            }
+
+            serde_json::to_writer(&mut f, &object)?;
+            f.write_all(b"\n")?;
+        }
+    }
+
+    f.flush()?;
+
+    println!("A file named {} is created.", file_name);
+    println!();
+
+    Ok(())
+}
+
+// Same as get_csv_sample for a JSON Lines file, the columns are flattened from the sampled objects
+#[pyfunction]
+pub fn get_jsonl_sample(filepath: &str, sample_row: i32) -> PyResult<Dataframe> {
+    let (byte_array, csv_meta) = read_jsonl_sample(filepath, sample_row)?;
+    Ok(Dataframe::new(byte_array, csv_meta))
+}
+
+#[pyfunction]
+pub fn read_jsonl(filepath: &str) -> PyResult<Dataframe> {
+    let (byte_array, csv_meta) = read_jsonl_file(filepath)?;
+    Ok(Dataframe::new(byte_array, csv_meta))
+}
+
+#[pyfunction(file_name = "\"%Sample.jsonl\"")]
+pub fn write_jsonl(df: &Dataframe, file_name: &str) -> PyResult<()> {
+    Ok(write_jsonl_file(df, file_name)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::test_dataframe;
+
+    #[test]
+    fn jsonl_round_trip() {
+        let df = test_dataframe(b"1,1.5,true,\"a,b\"\n2,,false,\"say \"\"hi\"\"\"\n", &["id", "price", "flag", "name"]);
+        let file_name = std::env::temp_dir().join(format!("peakrs_jsonl_{}.jsonl", std::process::id()));
+        let file_name = file_name.to_str().unwrap();
+
+        write_jsonl_file(&df, file_name).unwrap();
+        let text = std::fs::read_to_string(file_name).unwrap();
+        let (byte_array, csv_meta) = read_jsonl_file(file_name).unwrap();
+        std::fs::remove_file(file_name).unwrap();
+
+        assert_eq!(text.lines().next(), Some(r#"{"id":1,"price":1.5,"flag":true,"name":"a,b"}"#));
+        assert_eq!(csv_meta.column_name, vec!["id", "price", "flag", "name"]);
+        assert_eq!(csv_meta.validate_row, 2);
+        assert_eq!(byte_array, b"1,1.5,true,\"a,b\"\r\n2,,false,\"say \"\"hi\"\"\"\r\n");
+    }
+
+    #[test]
+    fn cell_to_json_value() {
+        assert_eq!(cell_to_value(b""), Value::Null);
+        assert_eq!(cell_to_value(b"007"), Value::String("007".to_string()));
+        assert_eq!(cell_to_value(b"[1, 2]"), serde_json::json!([1, 2]));
+        assert_eq!(cell_to_value(b"-3"), serde_json::json!(-3));
+    }
+}
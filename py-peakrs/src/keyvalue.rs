@@ -0,0 +1,295 @@
+use crate::dataframe::Dataframe;
+use crate::partition::{get_header_byte_count, read_partition};
+use crate::{cell_address, cell_per_row, cell_range, count_row, get_column_id, read_csv_sample, CsvMeta};
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::{Arc, Mutex, OnceLock};
+
+// Lookup table built from a small master file, probed row by row by join_keyvalue
+pub(crate) struct KeyValueTable {
+    pub(crate) byte_array: Vec<u8>,
+    pub(crate) cell_address: Vec<i64>,
+    pub(crate) cell_per_row: usize,
+    pub(crate) key_column: Vec<usize>,
+    pub(crate) value_column: Vec<usize>,
+    pub(crate) value_column_name: Vec<String>,
+    pub(crate) key_row: HashMap<Vec<u8>, Vec<usize>>,
+}
+
+fn keyvalue_registry() -> &'static Mutex<HashMap<String, Arc<KeyValueTable>>> {
+    static KEYVALUE_REGISTRY: OnceLock<Mutex<HashMap<String, Arc<KeyValueTable>>>> = OnceLock::new();
+    KEYVALUE_REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Tables registered while a script is validated are rolled back afterwards
+pub(crate) fn save_keyvalue_registry() -> HashMap<String, Arc<KeyValueTable>> {
+    keyvalue_registry().lock().unwrap().clone()
+}
+
+pub(crate) fn restore_keyvalue_registry(keyvalue_table: HashMap<String, Arc<KeyValueTable>>) {
+    *keyvalue_registry().lock().unwrap() = keyvalue_table;
+}
+
+// Composite key of trimmed cells, separated by byte 0 so that ("ab", "c") differs from ("a", "bc")
+pub(crate) fn get_composite_key(byte_array: &[u8], cell_address: &[i64], first_cell: usize, key_column: &[usize], composite_key: &mut Vec<u8>) {
+    composite_key.clear();
+
+    for (n, &current_column) in key_column.iter().enumerate() {
+        if n > 0 {
+            composite_key.push(0);
+        }
+
+        let (start_byte, end_byte) = cell_range(byte_array, cell_address, first_cell + current_column);
+        composite_key.extend_from_slice(&byte_array[start_byte..end_byte]);
+    }
+}
+
+// "column, column => name" or "column, column => function(name)"
+pub(crate) fn split_setting(setting: &str) -> Result<(&str, &str, &str), String> {
+    let (left, right) = match setting.find("=>") {
+        Some(n) => (setting[..n].trim(), setting[n + 2..].trim()),
+        None => return Err(format!("** Missing => in {} ** \n", setting)),
+    };
+
+    match (right.find('('), right.rfind(')')) {
+        (Some(m), Some(n)) if m < n => Ok((left, right[..m].trim(), right[m + 1..n].trim())),
+        (None, None) => Ok((left, "", right)),
+        _ => Err(format!("** Missing bracket in {} ** \n", right)),
+    }
+}
+
+pub(crate) fn build_keyvalue_table(byte_array: Vec<u8>, csv_meta: &CsvMeta, key_column: Vec<usize>) -> KeyValueTable {
+    let cell_address = cell_address(&byte_array, csv_meta);
+    let cell_per_row = cell_per_row(csv_meta);
+    let total_row = (cell_address.len() - 1) / cell_per_row;
+    let mut key_row: HashMap<Vec<u8>, Vec<usize>> = HashMap::new();
+    let mut composite_key = Vec::new();
+
+    for current_row in 0..total_row {
+        get_composite_key(&byte_array, &cell_address, current_row * cell_per_row, &key_column, &mut composite_key);
+
+        match key_row.get_mut(&composite_key) {
+            Some(row) => row.push(current_row),
+            None => {
+                key_row.insert(composite_key.clone(), vec![current_row]);
+            }
+        }
+    }
+
+    let value_column: Vec<usize> = (0..csv_meta.total_column as usize).filter(|x| !key_column.contains(x)).collect();
+    let value_column_name = value_column.iter().map(|&x| csv_meta.column_name[x].clone()).collect();
+
+    KeyValueTable {
+        byte_array,
+        cell_address,
+        cell_per_row,
+        key_column,
+        value_column,
+        value_column_name,
+        key_row,
+    }
+}
+
+#[pyfunction]
+pub fn build_keyvalue(df: &Dataframe, setting: &str) -> PyResult<Dataframe> {
+    let result = split_setting(setting).and_then(|(left, _, table_name)| {
+        if table_name.is_empty() {
+            return Err("** Fail to find key value table name ** \n".to_string());
+        }
+        get_column_id(left, &df.csv_meta).map(|x| (x, table_name.to_string()))
+    });
+
+    match result {
+        Ok((key_column, table_name)) => {
+            let keyvalue_table = build_keyvalue_table(df.byte_array.clone(), &df.csv_meta, key_column);
+            keyvalue_registry().lock().unwrap().insert(table_name, Arc::new(keyvalue_table));
+            Ok(df.clone())
+        }
+        Err(error_message) => Ok(df.with_error(&error_message)),
+    }
+}
+
+#[pyfunction]
+pub fn drop_keyvalue(table_name: &str) -> bool {
+    keyvalue_registry().lock().unwrap().remove(table_name).is_some()
+}
+
+pub(crate) struct JoinKeyValueSetting {
+    key_column: Vec<usize>,
+    is_inner: bool,
+    table: Arc<KeyValueTable>,
+}
+
+// e.g. "column, column => all_match(master)" or "column, column => inner(master)"
+fn parse_join_keyvalue_setting(setting: &str, csv_meta: &CsvMeta) -> Result<JoinKeyValueSetting, String> {
+    let (left, join_type, table_name) = split_setting(setting)?;
+
+    let is_inner = match join_type.to_lowercase().as_str() {
+        "all_match" | "allmatch" => false,
+        "inner" => true,
+        _ => return Err(format!("** Join type {} is not supported, use all_match or inner ** \n", join_type)),
+    };
+
+    let table = match keyvalue_registry().lock().unwrap().get(table_name) {
+        Some(table) => table.clone(),
+        None => return Err(format!("** Key value table {} is not found ** \n", table_name)),
+    };
+
+    let key_column = get_column_id(left, csv_meta)?;
+
+    if key_column.len() != table.key_column.len() {
+        return Err(format!(
+            "** Number of join column is {}, but key value table {} has {} key column ** \n",
+            key_column.len(),
+            table_name,
+            table.key_column.len()
+        ));
+    }
+
+    Ok(JoinKeyValueSetting {
+        key_column,
+        is_inner,
+        table,
+    })
+}
+
+// Column names of the master table, renamed to name_table_name when the fact table has the same name
+fn get_join_column_name(csv_meta: &CsvMeta, table: &KeyValueTable, table_name: &str) -> Vec<String> {
+    let mut column_name = csv_meta.column_name.clone();
+
+    for current_name in &table.value_column_name {
+        if column_name.contains(current_name) {
+            column_name.push(format!("{}_{}", current_name, table_name));
+        } else {
+            column_name.push(current_name.clone());
+        }
+    }
+
+    column_name
+}
+
+// all_match keeps every fact row with empty cells if no match, inner keeps only matched rows.
+// A key matching many master rows outputs one row per master row.
+fn join_keyvalue_byte_array(byte_array: &[u8], csv_meta: &CsvMeta, join_setting: &JoinKeyValueSetting) -> Vec<u8> {
+    let cell_address = cell_address(byte_array, csv_meta);
+    let cell_per_row = cell_per_row(csv_meta);
+    let total_row = (cell_address.len() - 1) / cell_per_row;
+    let total_column = csv_meta.total_column as usize;
+    let table = &join_setting.table;
+    let mut composite_key = Vec::new();
+    let mut result_bytes = Vec::with_capacity(byte_array.len() * 2);
+
+    for current_row in 0..total_row {
+        let first_cell = current_row * cell_per_row;
+        let start_byte = cell_address[first_cell] as usize;
+        let end_byte = cell_address[first_cell + cell_per_row] as usize;
+        let line_br_byte = cell_address[first_cell + total_column] as usize - 1;
+
+        get_composite_key(byte_array, &cell_address, first_cell, &join_setting.key_column, &mut composite_key);
+
+        match table.key_row.get(&composite_key) {
+            Some(master_row) => {
+                for &current_master_row in master_row {
+                    result_bytes.extend_from_slice(&byte_array[start_byte..line_br_byte]);
+
+                    for &current_column in &table.value_column {
+                        let (cell_start, cell_end) = cell_range(
+                            &table.byte_array,
+                            &table.cell_address,
+                            current_master_row * table.cell_per_row + current_column,
+                        );
+                        result_bytes.push(csv_meta.delimiter);
+                        result_bytes.extend_from_slice(&table.byte_array[cell_start..cell_end]);
+                    }
+
+                    result_bytes.extend_from_slice(&byte_array[line_br_byte..end_byte]);
+                }
+            }
+            None => {
+                if !join_setting.is_inner {
+                    result_bytes.extend_from_slice(&byte_array[start_byte..line_br_byte]);
+
+                    for _ in &table.value_column {
+                        result_bytes.push(csv_meta.delimiter);
+                    }
+
+                    result_bytes.extend_from_slice(&byte_array[line_br_byte..end_byte]);
+                }
+            }
+        }
+    }
+
+    result_bytes
+}
+
+#[pyfunction]
+pub fn join_keyvalue(df: &Dataframe, setting: &str) -> PyResult<Dataframe> {
+    match parse_join_keyvalue_setting(setting, &df.csv_meta) {
+        Ok(join_setting) => {
+            let result_bytes = join_keyvalue_byte_array(&df.byte_array, &df.csv_meta, &join_setting);
+            let (_, _, table_name) = split_setting(setting).unwrap();
+            let mut csv_meta = df.csv_meta.clone();
+
+            csv_meta.column_name = get_join_column_name(&csv_meta, &join_setting.table, table_name);
+            csv_meta.total_column = csv_meta.column_name.len() as i32;
+            csv_meta.validate_row = count_row(&result_bytes);
+
+            Ok(Dataframe::new(result_bytes, csv_meta))
+        }
+        Err(error_message) => Ok(df.with_error(&error_message)),
+    }
+}
+
+// Stream the fact file partition by partition, so only the master table and one partition are in memory
+#[pyfunction(partition_size = "67_108_864")]
+pub fn join_keyvalue_file(filepath: &str, setting: &str, output_file: &str, partition_size: i64) -> PyResult<CsvMeta> {
+    let (_, mut csv_meta) = read_csv_sample(filepath, 10)?;
+
+    if !csv_meta.error_message.is_empty() {
+        return Ok(csv_meta);
+    }
+
+    let join_setting = match parse_join_keyvalue_setting(setting, &csv_meta) {
+        Ok(join_setting) => join_setting,
+        Err(error_message) => {
+            csv_meta.error_message.push_str(&error_message);
+            return Ok(csv_meta);
+        }
+    };
+
+    let partition_size = if partition_size <= 0 { 67_108_864 } else { partition_size };
+    let (_, _, table_name) = split_setting(setting).unwrap();
+    let column_name = get_join_column_name(&csv_meta, &join_setting.table, table_name);
+
+    let mut header = column_name.join(&(csv_meta.delimiter as char).to_string());
+    header.push_str(if csv_meta.is_line_br_13_exist { "\r\n" } else { "\n" });
+
+    let f = File::create(output_file)?;
+    let mut f = BufWriter::new(f);
+    f.write_all(header.as_bytes())?;
+
+    let mut file = File::open(filepath)?;
+    let mut start_byte = get_header_byte_count(&mut file);
+    let mut total_row = 0;
+
+    while start_byte < csv_meta.file_size {
+        let (byte_array, consumed_byte_count) =
+            read_partition(&mut file, start_byte, partition_size, csv_meta.file_size, &csv_meta);
+        start_byte += consumed_byte_count;
+
+        let result_bytes = join_keyvalue_byte_array(&byte_array, &csv_meta, &join_setting);
+        total_row += count_row(&result_bytes);
+        f.write_all(&result_bytes)?;
+    }
+
+    f.flush()?;
+
+    csv_meta.column_name = column_name;
+    csv_meta.total_column = csv_meta.column_name.len() as i32;
+    csv_meta.validate_row = total_row;
+    csv_meta.estimate_row = total_row;
+
+    Ok(csv_meta)
+}
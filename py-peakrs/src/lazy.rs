@@ -0,0 +1,491 @@
+use crate::add_column::expression_column;
+use crate::dataframe::Dataframe;
+use crate::filter::{is_condition_match, parse_filter_setting, FilterCondition};
+use crate::partition::{get_header_byte_count, read_partition};
+use crate::stream::{StreamOperator, StreamPlan};
+use crate::{count_row, read_csv_sample, CsvMeta};
+use pyo3::prelude::*;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// A query on a csv file, built by chaining select, filter and add_column, run only by collect or write_csv
+#[pyclass]
+#[derive(Clone)]
+pub struct LazyFrame {
+    filepath: String,
+    operation: Vec<Vec<String>>,
+}
+
+struct LazyStep {
+    operation: Vec<String>,
+    referenced_column: Vec<String>,
+    added_column: String,
+    output_column: Vec<String>,
+}
+
+// Filters evaluated by the scanner on the file columns, and the file columns kept
+struct LazyPlan {
+    csv_meta: CsvMeta,
+    scan_column: Vec<usize>,
+    scan_filter: Vec<(Vec<FilterCondition>, bool)>,
+    stream_plan: StreamPlan,
+    explain: Vec<String>,
+}
+
+fn is_filter(step: &LazyStep) -> bool {
+    matches!(step.operation[0].as_str(), "filter" | "filter_unmatch")
+}
+
+// A filter moves before a select, or before an add_column whose new column it does not use
+fn is_filter_movable(previous_step: &LazyStep, step: &LazyStep) -> bool {
+    match previous_step.operation[0].as_str() {
+        "select" | "select_unmatch" => true,
+        "add_column" => !step.referenced_column.contains(&previous_step.added_column),
+        _ => false,
+    }
+}
+
+fn get_lazy_step(operation: &[Vec<String>], csv_meta: &CsvMeta) -> Result<(Vec<LazyStep>, CsvMeta), String> {
+    let mut stream_plan = StreamPlan::new(csv_meta);
+    let mut error_message = String::new();
+
+    for current_operation in operation {
+        if let Err(x) = stream_plan.push(current_operation) {
+            error_message.push_str(&x);
+        }
+    }
+
+    if !error_message.is_empty() {
+        return Err(error_message);
+    }
+
+    let mut lazy_step = Vec::new();
+
+    for (n, (stream_operator, input_meta)) in stream_plan.operator.iter().enumerate() {
+        let output_meta = match stream_plan.operator.get(n + 1) {
+            Some((_, x)) => x,
+            None => &stream_plan.csv_meta,
+        };
+
+        let mut column = Vec::new();
+        let mut added_column = String::new();
+
+        match stream_operator {
+            StreamOperator::Filter(filter_condition, _) => column.extend(filter_condition.iter().map(|x| x.column)),
+            StreamOperator::Select(_) => {}
+            StreamOperator::AddColumn(add_column_setting) => {
+                expression_column(&add_column_setting.expression, &mut column);
+                added_column = add_column_setting.column_name.clone();
+            }
+        }
+
+        lazy_step.push(LazyStep {
+            operation: operation[n].clone(),
+            referenced_column: column.iter().map(|&x| input_meta.column_name[x].clone()).collect(),
+            added_column,
+            output_column: output_meta.column_name.clone(),
+        });
+    }
+
+    Ok((lazy_step, stream_plan.csv_meta))
+}
+
+// Predicate pushdown moves filters ahead of select and add_column, and leading filters are run by the scanner.
+// Projection pushdown keeps only the file columns used by a later step or the output, and drops add_column
+// steps whose new column is never used. A file which cannot be opened is an IO error, a plan which
+// cannot be built is the meta information of the file with an error message.
+fn optimize(filepath: &str, operation: &[Vec<String>]) -> PyResult<Result<LazyPlan, Box<CsvMeta>>> {
+    let (_, mut csv_meta) = read_csv_sample(filepath, 10)?;
+
+    if csv_meta.error_message.is_empty() && csv_meta.column_name.is_empty() {
+        csv_meta.error_message.push_str(&format!("** Fail to find any column name in {} ** \n", filepath));
+    }
+
+    if !csv_meta.error_message.is_empty() {
+        return Ok(Err(Box::new(csv_meta)));
+    }
+
+    let (mut lazy_step, output_meta) = match get_lazy_step(operation, &csv_meta) {
+        Ok(x) => x,
+        Err(error_message) => {
+            csv_meta.error_message.push_str(&error_message);
+            return Ok(Err(Box::new(csv_meta)));
+        }
+    };
+
+    for n in 0..lazy_step.len() {
+        if is_filter(&lazy_step[n]) {
+            let mut m = n;
+
+            while m > 0 && is_filter_movable(&lazy_step[m - 1], &lazy_step[m]) {
+                lazy_step.swap(m - 1, m);
+                m -= 1;
+            }
+        }
+    }
+
+    let scan_filter_count = lazy_step.iter().take_while(|x| is_filter(x)).count();
+    let mut needed_column: HashSet<String> = output_meta.column_name.iter().cloned().collect();
+    let mut is_kept = vec![true; lazy_step.len()];
+
+    for n in (scan_filter_count..lazy_step.len()).rev() {
+        let step = &mut lazy_step[n];
+
+        match step.operation[0].as_str() {
+            "add_column" if !needed_column.contains(&step.added_column) => is_kept[n] = false,
+            "add_column" => {
+                needed_column.remove(&step.added_column);
+                needed_column.extend(step.referenced_column.iter().cloned());
+            }
+            "select" | "select_unmatch" => {
+                let mut column: Vec<String> = step.output_column.iter().filter(|x| needed_column.contains(*x)).cloned().collect();
+
+                // No column of this select is used later, e.g. only columns added after it are kept, but a
+                // select keeps at least one column, so it keeps its whole output
+                if column.is_empty() {
+                    column = step.output_column.clone();
+                    needed_column.extend(column.iter().cloned());
+                }
+
+                step.operation = vec!["select".to_string(), column.join(", ")];
+            }
+            _ => needed_column.extend(step.referenced_column.iter().cloned()),
+        }
+    }
+
+    let scan_column: Vec<usize> = (0..csv_meta.column_name.len()).filter(|&x| needed_column.contains(&csv_meta.column_name[x])).collect();
+    let mut scan_filter = Vec::new();
+    let mut explain = vec![format!(
+        "scan {} column({})",
+        filepath,
+        scan_column.iter().map(|&x| csv_meta.column_name[x].as_str()).collect::<Vec<_>>().join(", ")
+    )];
+
+    for step in &lazy_step[..scan_filter_count] {
+        let filter_condition = match parse_filter_setting(&step.operation[1], &csv_meta.column_name) {
+            Ok(x) => x,
+            Err(error_message) => {
+                csv_meta.error_message.push_str(&error_message);
+                return Ok(Err(Box::new(csv_meta)));
+            }
+        };
+        scan_filter.push((filter_condition, step.operation[0] == "filter_unmatch"));
+        explain.push(format!("    {}({})", step.operation[0], step.operation[1]));
+    }
+
+    let mut scan_meta = csv_meta.clone();
+    scan_meta.column_name = scan_column.iter().map(|&x| csv_meta.column_name[x].clone()).collect();
+    scan_meta.total_column = scan_column.len() as i32;
+
+    let mut stream_plan = StreamPlan::new(&scan_meta);
+
+    for (n, step) in lazy_step.iter().enumerate().skip(scan_filter_count) {
+        if is_kept[n] {
+            if let Err(error_message) = stream_plan.push(&step.operation) {
+                csv_meta.error_message.push_str(&error_message);
+                return Ok(Err(Box::new(csv_meta)));
+            }
+
+            explain.push(format!("{}({})", step.operation[0], step.operation[1..].join(", ")));
+        }
+    }
+
+    Ok(Ok(LazyPlan {
+        csv_meta,
+        scan_column,
+        scan_filter,
+        stream_plan,
+        explain,
+    }))
+}
+
+// Locate the cells of a row only up to the last column used, check the filters on them and copy the
+// scan columns. Bytes after the last column used are skipped to the line break.
+fn scan_byte_array(byte_array: &[u8], csv_meta: &CsvMeta, scan_column: &[usize], scan_filter: &[(Vec<FilterCondition>, bool)]) -> Vec<u8> {
+    let max_column = scan_filter
+        .iter()
+        .flat_map(|(x, _)| x.iter().map(|y| y.column))
+        .chain(scan_column.iter().copied())
+        .max()
+        .unwrap_or(0);
+
+    let mut cell = vec![(0, 0); max_column + 1];
+    let mut result_bytes = Vec::with_capacity(byte_array.len());
+    let mut start_byte = 0;
+
+    while start_byte < byte_array.len() {
+        let mut current_column = 0;
+        let mut cell_start_byte = start_byte;
+        let mut double_quote_count = 0;
+        let mut i = start_byte;
+
+        while i < byte_array.len() && current_column <= max_column {
+            let byte = byte_array[i];
+
+            if double_quote_count % 2 == 1 && (byte == 10 || byte == 13) {
+                // A line break inside double quotes belongs to the cell
+            } else if (byte == csv_meta.delimiter && double_quote_count % 2 == 0) || byte == 10 || byte == 13 {
+                cell[current_column] = (cell_start_byte, i);
+                current_column += 1;
+                cell_start_byte = i + 1;
+                double_quote_count = 0;
+
+                if byte != csv_meta.delimiter {
+                    break;
+                }
+            } else if byte == 34 {
+                double_quote_count += 1;
+            }
+
+            i += 1;
+        }
+
+        for x in cell.iter_mut().skip(current_column) {
+            *x = (i, i);
+        }
+
+        // Skip to the line break outside double quotes
+        let mut end_byte = i;
+        let mut double_quote_count = 0;
+
+        while end_byte < byte_array.len() && (byte_array[end_byte] != 10 || double_quote_count % 2 == 1) {
+            if byte_array[end_byte] == 34 {
+                double_quote_count += 1;
+            }
+            end_byte += 1;
+        }
+        let end_byte = (end_byte + 1).min(byte_array.len());
+
+        let is_match = scan_filter.iter().all(|(filter_condition, is_unmatch)| {
+            let is_match = filter_condition.iter().all(|x| {
+                let (mut cell_start, mut cell_end) = cell[x.column];
+
+                while cell_start < cell_end && byte_array[cell_start] == 32 {
+                    cell_start += 1;
+                }
+
+                while cell_end > cell_start && byte_array[cell_end - 1] == 32 {
+                    cell_end -= 1;
+                }
+
+                is_condition_match(&byte_array[cell_start..cell_end], x)
+            });
+
+            is_match != *is_unmatch
+        });
+
+        if is_match {
+            for (n, &current_column) in scan_column.iter().enumerate() {
+                if n > 0 {
+                    result_bytes.push(csv_meta.delimiter);
+                }
+
+                let (cell_start, cell_end) = cell[current_column];
+                result_bytes.extend_from_slice(&byte_array[cell_start..cell_end]);
+            }
+
+            if csv_meta.is_line_br_13_exist {
+                result_bytes.push(13);
+            }
+            result_bytes.push(10);
+        }
+
+        start_byte = end_byte;
+    }
+
+    result_bytes
+}
+
+// Run the plan partition by partition, passing each result to output
+fn run_lazy_plan(filepath: &str, lazy_plan: &LazyPlan, partition_size: i64, mut output: impl FnMut(&[u8]) -> std::io::Result<()>) -> PyResult<Result<i64, String>> {
+    let csv_meta = &lazy_plan.csv_meta;
+    let partition_size = if partition_size <= 0 { 67_108_864 } else { partition_size };
+    let mut file = File::open(filepath)?;
+    let mut start_byte = get_header_byte_count(&mut file);
+    let mut total_row = 0;
+
+    while start_byte < csv_meta.file_size {
+        let (byte_array, consumed_byte_count) = read_partition(&mut file, start_byte, partition_size, csv_meta.file_size, csv_meta);
+        start_byte += consumed_byte_count;
+
+        let byte_array = scan_byte_array(&byte_array, csv_meta, &lazy_plan.scan_column, &lazy_plan.scan_filter);
+
+        match lazy_plan.stream_plan.run(byte_array) {
+            Ok(result_bytes) => {
+                total_row += count_row(&result_bytes);
+                output(&result_bytes)?;
+            }
+            Err(error_message) => return Ok(Err(error_message)),
+        }
+    }
+
+    Ok(Ok(total_row))
+}
+
+impl LazyFrame {
+    fn push(&self, operation: Vec<String>) -> LazyFrame {
+        let mut lazy_frame = self.clone();
+        lazy_frame.operation.push(operation);
+        lazy_frame
+    }
+}
+
+#[pymethods]
+impl LazyFrame {
+    fn filter(&self, setting: &str) -> LazyFrame {
+        self.push(vec!["filter".to_string(), setting.to_string()])
+    }
+
+    fn filter_unmatch(&self, setting: &str) -> LazyFrame {
+        self.push(vec!["filter_unmatch".to_string(), setting.to_string()])
+    }
+
+    fn select(&self, setting: &str) -> LazyFrame {
+        self.push(vec!["select".to_string(), setting.to_string()])
+    }
+
+    fn select_unmatch(&self, setting: &str) -> LazyFrame {
+        self.push(vec!["select_unmatch".to_string(), setting.to_string()])
+    }
+
+    #[args(decimal_place = "-1", divide_by_zero = "\"null\"")]
+    fn add_column(&self, setting: &str, decimal_place: i32, divide_by_zero: &str) -> LazyFrame {
+        self.push(vec![
+            "add_column".to_string(),
+            setting.to_string(),
+            decimal_place.to_string(),
+            divide_by_zero.to_string(),
+        ])
+    }
+
+    // The optimized plan, one step per line
+    fn explain(&self) -> PyResult<String> {
+        match optimize(&self.filepath, &self.operation)? {
+            Ok(lazy_plan) => Ok(lazy_plan.explain.join("\n")),
+            Err(csv_meta) => Ok(csv_meta.error_message),
+        }
+    }
+
+    #[args(partition_size = "67_108_864")]
+    fn collect(&self, partition_size: i64) -> PyResult<Dataframe> {
+        let lazy_plan = match optimize(&self.filepath, &self.operation)? {
+            Ok(lazy_plan) => lazy_plan,
+            Err(csv_meta) => return Ok(Dataframe::new(Vec::new(), *csv_meta)),
+        };
+
+        let mut byte_array = Vec::new();
+        let result = run_lazy_plan(&self.filepath, &lazy_plan, partition_size, |x| {
+            byte_array.extend_from_slice(x);
+            Ok(())
+        })?;
+
+        let mut csv_meta = lazy_plan.stream_plan.csv_meta;
+
+        match result {
+            Ok(total_row) => {
+                csv_meta.validate_row = total_row;
+                csv_meta.estimate_row = total_row;
+            }
+            Err(error_message) => csv_meta.error_message.push_str(&error_message),
+        }
+
+        Ok(Dataframe::new(byte_array, csv_meta))
+    }
+
+    // Stream the result to output_file, so memory stays bounded whatever the file size
+    #[args(partition_size = "67_108_864")]
+    fn write_csv(&self, output_file: &str, partition_size: i64) -> PyResult<CsvMeta> {
+        let lazy_plan = match optimize(&self.filepath, &self.operation)? {
+            Ok(lazy_plan) => lazy_plan,
+            Err(csv_meta) => return Ok(*csv_meta),
+        };
+
+        let mut csv_meta = lazy_plan.stream_plan.csv_meta.clone();
+        let mut header = csv_meta.column_name.join(&(csv_meta.delimiter as char).to_string());
+        header.push_str(if csv_meta.is_line_br_13_exist { "\r\n" } else { "\n" });
+
+        let f = File::create(output_file)?;
+        let mut f = BufWriter::new(f);
+        f.write_all(header.as_bytes())?;
+
+        match run_lazy_plan(&self.filepath, &lazy_plan, partition_size, |x| f.write_all(x))? {
+            Ok(total_row) => {
+                csv_meta.validate_row = total_row;
+                csv_meta.estimate_row = total_row;
+            }
+            Err(error_message) => csv_meta.error_message.push_str(&error_message),
+        }
+
+        f.flush()?;
+
+        Ok(csv_meta)
+    }
+}
+
+// Start a lazy query on a csv file, e.g.
+//     pr.scan_csv("data.csv").filter("amount(Float > 100)").select("id, amount").collect()
+#[pyfunction]
+pub fn scan_csv(filepath: &str) -> LazyFrame {
+    LazyFrame {
+        filepath: filepath.to_string(),
+        operation: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::test_csv_meta;
+
+    fn run(filepath: &str, operation: &[Vec<String>]) -> (Vec<u8>, CsvMeta) {
+        let lazy_plan = match optimize(filepath, operation).unwrap() {
+            Ok(x) => x,
+            Err(csv_meta) => panic!("{}", csv_meta.error_message),
+        };
+
+        let mut byte_array = Vec::new();
+        run_lazy_plan(filepath, &lazy_plan, 0, |x| {
+            byte_array.extend_from_slice(x);
+            Ok(())
+        })
+        .unwrap()
+        .unwrap();
+
+        (byte_array, lazy_plan.stream_plan.csv_meta)
+    }
+
+    fn operation(x: &[&[&str]]) -> Vec<Vec<String>> {
+        x.iter().map(|x| x.iter().map(|y| y.to_string()).collect()).collect()
+    }
+
+    #[test]
+    fn scan_keeps_quoted_line_breaks_and_escaped_quotes() {
+        let text = b"1,\"x\ny\",z\n2,\"a\"\"b\",w\n3,q,\"p\nr\"\n4,s,t\n";
+        let byte_array = scan_byte_array(text, &test_csv_meta(&["a", "b", "c"]), &[0, 1], &[]);
+
+        assert_eq!(byte_array, b"1,\"x\ny\"\n2,\"a\"\"b\"\n3,q\n4,s\n".to_vec());
+    }
+
+    // A select whose columns are all replaced by a later add_column keeps its output
+    #[test]
+    fn select_before_added_column_is_not_emptied() {
+        let filepath = std::env::temp_dir().join(format!("peakrs_lazy_{}.csv", std::process::id()));
+        std::fs::write(&filepath, "a,b\n1,2\n3,4\n").unwrap();
+        let filepath = filepath.to_str().unwrap();
+
+        let (byte_array, csv_meta) = run(
+            filepath,
+            &operation(&[&["select", "a"], &["add_column", "1 + 1 => compute(two)"], &["select", "two"]]),
+        );
+
+        std::fs::remove_file(filepath).unwrap();
+        assert_eq!(csv_meta.column_name, vec!["two"]);
+        assert_eq!(byte_array, b"2\n2\n");
+    }
+
+    #[test]
+    fn missing_file_is_io_error() {
+        assert!(optimize("/nonexistent/peakrs.csv", &[]).is_err());
+    }
+}
@@ -1,11 +1,77 @@
+use pyo3::class::basic::PyObjectProtocol;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use serde_json::{json, Value};
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
 use std::fs::{File, metadata};
 use std::str;
 use std::io::{Write, BufWriter};
 
-#[pyclass]
+mod add_column;
+mod arrow;
+mod batch;
+mod compression;
+mod dataframe;
+mod filter;
+mod fingerprint;
+mod fixed_width;
+mod folder_lake;
+mod groupby;
+mod jsonl;
+mod jointable;
+mod keyvalue;
+mod lazy;
+mod multi_file;
+mod numeric;
+mod orderby;
+mod parquet_file;
+mod partition;
+mod profile;
+mod sample;
+mod script;
+mod select;
+mod source;
+mod split_file;
+mod stream;
+mod validate;
+mod xlsx;
+
+#[cfg(test)]
+include!(concat!(env!("OUT_DIR"), "/link_python.rs"));
+
+use add_column::*;
+use arrow::*;
+use batch::*;
+use compression::{get_compression, open_decoder, sample_compressed, Compression};
+use dataframe::*;
+use filter::*;
+use fingerprint::*;
+use fixed_width::*;
+use folder_lake::*;
+use groupby::*;
+use jsonl::*;
+use jointable::*;
+use keyvalue::*;
+use lazy::*;
+use multi_file::read_multi_file;
+use numeric::*;
+use orderby::*;
+use parquet_file::*;
+use partition::{get_header_byte_count, read_partition};
+use profile::*;
+use sample::read_csv_sample_by_mode;
+use script::*;
+use select::*;
+use source::{read_buffer_file, read_buffer_sample, read_seekable_sample, FilePath, STREAM_SAMPLE_SIZE};
+use split_file::*;
+use stream::*;
+use validate::*;
+use xlsx::*;
+
+// module lets pickle find the class
+#[pyclass(module = "peakrs")]
 #[derive(Clone)]
 struct CsvMeta {
     total_column: i32,
@@ -17,10 +83,161 @@ struct CsvMeta {
     file_size: i64,
     delimiter: u8,   
     error_message: String, 
+    compression: String,
+    compressed_size: i64,
+    encoding: String,
+}
+
+// Nothing detected yet, e.g. a dataframe which only carries an error. CsvMeta() and from_json
+// start from python_default instead.
+impl Default for CsvMeta {
+    fn default() -> CsvMeta {
+        CsvMeta {
+            total_column: 0,
+            validate_row: 0,
+            estimate_row: 0,
+            is_line_br_13_exist: false,
+            is_line_br_10_exist: false,
+            column_name: Vec::new(),
+            file_size: 0,
+            delimiter: 0,
+            error_message: String::new(),
+            compression: Compression::None.name().to_string(),
+            compressed_size: 0,
+            encoding: String::new(),
+        }
+    }
+}
+
+impl CsvMeta {
+    // CsvMeta() without arguments: a comma delimited file with LF line breaks
+    fn python_default() -> CsvMeta {
+        CsvMeta::new(0, 0, 0, false, true, Vec::new(), 0, 44, "", Compression::None.name(), 0, "")
+    }
+
+    // A key which is missing keeps its CsvMeta() default, so older JSON can still be read
+    fn from_value(value: &Value) -> Result<CsvMeta, String> {
+        let object = value.as_object().ok_or("CsvMeta JSON should be an object")?;
+        let mut csv_meta = CsvMeta::python_default();
+
+        for (key, x) in object {
+            let to_error = || format!("{} of CsvMeta JSON has a wrong type", key);
+            let number = || x.as_i64().ok_or_else(to_error);
+            let flag = || x.as_bool().ok_or_else(to_error);
+            let text = || x.as_str().map(|x| x.to_string()).ok_or_else(to_error);
+
+            match key.as_str() {
+                "total_column" => csv_meta.total_column = number()? as i32,
+                "validate_row" => csv_meta.validate_row = number()?,
+                "estimate_row" => csv_meta.estimate_row = number()?,
+                "is_line_br_13_exist" => csv_meta.is_line_br_13_exist = flag()?,
+                "is_line_br_10_exist" => csv_meta.is_line_br_10_exist = flag()?,
+                "column_name" => csv_meta.column_name = serde_json::from_value(x.clone()).map_err(|_| to_error())?,
+                "file_size" => csv_meta.file_size = number()?,
+                "delimiter" => csv_meta.delimiter = x.as_u64().filter(|&x| x <= 255).ok_or_else(to_error)? as u8,
+                "error_message" => csv_meta.error_message = text()?,
+                "compression" => csv_meta.compression = text()?,
+                "compressed_size" => csv_meta.compressed_size = number()?,
+                "encoding" => csv_meta.encoding = text()?,
+                _ => return Err(format!("{} is not a field of CsvMeta", key)),
+            }
+        }
+
+        if csv_meta.total_column == 0 {
+            csv_meta.total_column = csv_meta.column_name.len() as i32;
+        }
+
+        Ok(csv_meta)
+    }
+
+    fn to_value(&self) -> Value {
+        json!({
+            "total_column": self.total_column,
+            "validate_row": self.validate_row,
+            "estimate_row": self.estimate_row,
+            "is_line_br_13_exist": self.is_line_br_13_exist,
+            "is_line_br_10_exist": self.is_line_br_10_exist,
+            "column_name": self.column_name,
+            "file_size": self.file_size,
+            "delimiter": self.delimiter,
+            "error_message": self.error_message,
+            "compression": self.compression,
+            "compressed_size": self.compressed_size,
+            "encoding": self.encoding,
+        })
+    }
 }
 
 #[pymethods]
 impl CsvMeta {
+    // Meta information detected before, e.g. read back from a file, so that detection can be skipped.
+    // total_column is the number of column names if not given.
+    #[new]
+    #[args(
+        total_column = "0",
+        validate_row = "0",
+        estimate_row = "0",
+        is_line_br_13_exist = "false",
+        is_line_br_10_exist = "true",
+        column_name = "Vec::new()",
+        file_size = "0",
+        delimiter = "44",
+        error_message = "\"\"",
+        compression = "Compression::None.name()",
+        compressed_size = "0",
+        encoding = "\"\""
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        total_column: i32,
+        validate_row: i64,
+        estimate_row: i64,
+        is_line_br_13_exist: bool,
+        is_line_br_10_exist: bool,
+        column_name: Vec<String>,
+        file_size: i64,
+        delimiter: u8,
+        error_message: &str,
+        compression: &str,
+        compressed_size: i64,
+        encoding: &str,
+    ) -> CsvMeta {
+        CsvMeta {
+            total_column: if total_column == 0 { column_name.len() as i32 } else { total_column },
+            validate_row,
+            estimate_row,
+            is_line_br_13_exist,
+            is_line_br_10_exist,
+            column_name,
+            file_size,
+            delimiter,
+            error_message: error_message.to_string(),
+            compression: compression.to_string(),
+            compressed_size,
+            encoding: encoding.to_string(),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        self.to_value().to_string()
+    }
+
+    #[staticmethod]
+    fn from_json(text: &str) -> PyResult<CsvMeta> {
+        let value: Value = serde_json::from_str(text).map_err(|e| PyValueError::new_err(format!("CsvMeta JSON is not valid: {}", e)))?;
+        CsvMeta::from_value(&value).map_err(PyValueError::new_err)
+    }
+
+    // pickle keeps the JSON, e.g. to pass the meta information to multiprocessing workers
+    fn __getstate__(&self) -> String {
+        self.to_json()
+    }
+
+    fn __setstate__(&mut self, state: &str) -> PyResult<()> {
+        *self = CsvMeta::from_json(state)?;
+        Ok(())
+    }
+
     #[getter]
     fn get_total_column(&self) -> PyResult<i32> {
         Ok(self.total_column)
@@ -66,6 +283,125 @@ impl CsvMeta {
     fn get_error_message(&self) -> PyResult<String> {
         Ok(self.error_message.clone())
     }       
+
+    #[getter]
+    fn get_compression(&self) -> PyResult<String> {
+        Ok(self.compression.clone())
+    }
+
+    #[getter]
+    fn get_compressed_size(&self) -> PyResult<i64> {
+        Ok(self.compressed_size)
+    }
+
+    #[getter]
+    fn get_encoding(&self) -> PyResult<String> {
+        Ok(self.encoding.clone())
+    }
+    // Fields can be corrected in Python, e.g. meta.delimiter = 59 before reading with the meta information
+
+    #[setter]
+    fn set_total_column(&mut self, value: i32) -> PyResult<()> {
+        self.total_column = value;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_validate_row(&mut self, value: i64) -> PyResult<()> {
+        self.validate_row = value;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_estimate_row(&mut self, value: i64) -> PyResult<()> {
+        self.estimate_row = value;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_is_line_br_13_exist(&mut self, value: bool) -> PyResult<()> {
+        self.is_line_br_13_exist = value;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_is_line_br_10_exist(&mut self, value: bool) -> PyResult<()> {
+        self.is_line_br_10_exist = value;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_column_name(&mut self, value: Vec<String>) -> PyResult<()> {
+        self.column_name = value;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_file_size(&mut self, value: i64) -> PyResult<()> {
+        self.file_size = value;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_delimiter(&mut self, value: u8) -> PyResult<()> {
+        self.delimiter = value;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_error_message(&mut self, value: String) -> PyResult<()> {
+        self.error_message = value;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_compression(&mut self, value: String) -> PyResult<()> {
+        self.compression = value;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_compressed_size(&mut self, value: i64) -> PyResult<()> {
+        self.compressed_size = value;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_encoding(&mut self, value: String) -> PyResult<()> {
+        self.encoding = value;
+        Ok(())
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for CsvMeta {
+    fn __repr__(&self) -> String {
+        let line_break = match (self.is_line_br_13_exist, self.is_line_br_10_exist) {
+            (true, true) => "\\r\\n",
+            (true, false) => "\\r",
+            _ => "\\n",
+        };
+
+        let mut repr = format!(
+            "CsvMeta(delimiter={:?}, column=[{}], total_column={}, validate_row={}, estimate_row={}, file_size={}, line_break={}, encoding={}, compression={}",
+            self.delimiter as char,
+            self.column_name.join(", "),
+            self.total_column,
+            self.validate_row,
+            self.estimate_row,
+            self.file_size,
+            line_break,
+            self.encoding,
+            self.compression
+        );
+
+        if !self.error_message.is_empty() {
+            repr.push_str(&format!(", error_message={:?}", self.error_message.trim_end()));
+        }
+
+        repr.push(')');
+        repr
+    }
 }
 
 
@@ -80,7 +416,17 @@ fn get_byte_array_frequency_distribution(byte_array: &Vec<u8>) -> HashMap<u8, i3
     frequency_distribution
 }
 
-fn get_current_row_frequency_distribution(file: &mut File, start_byte: i64) -> (usize, HashMap<u8, i32>, Vec<u8>) {
+// Read up to sample_size bytes, fewer if the end of file is reached
+fn read_sample_bytes<R: Read + Seek>(file: &mut R, start_byte: i64, sample_size: usize) -> Vec<u8> {
+    let mut byte_array = Vec::with_capacity(sample_size);
+
+    file.seek(SeekFrom::Start(start_byte as u64)).unwrap();
+    Read::by_ref(file).take(sample_size as u64).read_to_end(&mut byte_array).unwrap();
+
+    byte_array
+}
+
+fn get_current_row_frequency_distribution<R: Read + Seek>(file: &mut R, start_byte: i64) -> (usize, HashMap<u8, i32>, Vec<u8>) {
 
     let mut frequency_distribution = HashMap::new();
     let mut is_valid_row_exist = false;
@@ -91,10 +437,8 @@ fn get_current_row_frequency_distribution(file: &mut File, start_byte: i64) -> (
     while !is_valid_row_exist && sample_size < 10000 {
         sample_size += 100;
 
-        let mut byte_array = vec![0; sample_size];
-        
-        file.seek(SeekFrom::Start(start_byte as u64)).unwrap();
-        file.read_exact(&mut byte_array).unwrap();
+        let byte_array = read_sample_bytes(file, start_byte, sample_size);
+        let read_count = byte_array.len();
 
         let mut n = 0;
         current_row.clear();
@@ -105,7 +449,7 @@ fn get_current_row_frequency_distribution(file: &mut File, start_byte: i64) -> (
             is_first_line_break_exist = true;
         }
 
-        while n < sample_size && !is_second_line_break_exist {
+        while n < read_count && !is_second_line_break_exist {
             if !is_first_line_break_exist && byte_array[n] == 10 {
                 is_first_line_break_exist = true;
             } else if is_first_line_break_exist && byte_array[n] == 10 {
@@ -132,12 +476,17 @@ fn get_current_row_frequency_distribution(file: &mut File, start_byte: i64) -> (
                 get_byte_array_frequency_distribution(&current_row.clone());
             is_valid_row_exist = true;
         }
+
+        // End of file
+        if read_count < sample_size {
+            break;
+        }
     }
 
     (current_row.len(), frequency_distribution, current_row)
 }
 
-fn skip_white_space(byte_array: &Vec<u8>, mut start_byte: i64, mut end_byte: i64) -> (i64, i64) {
+fn skip_white_space(byte_array: &[u8], mut start_byte: i64, mut end_byte: i64) -> (i64, i64) {
     
     while start_byte < end_byte && byte_array[start_byte as usize] == 32 {
         start_byte += 1;
@@ -150,7 +499,7 @@ fn skip_white_space(byte_array: &Vec<u8>, mut start_byte: i64, mut end_byte: i64
     (start_byte, end_byte)
 }
 
-fn get_column_name(file: &mut File, delimiter: u8) -> Vec<String> {
+fn get_column_name<R: Read + Seek>(file: &mut R, delimiter: u8) -> Vec<String> {
    
     let mut is_valid_row_exist = false;
     let mut sample_size = 0;
@@ -164,15 +513,14 @@ fn get_column_name(file: &mut File, delimiter: u8) -> Vec<String> {
 
         sample_size += 100;
 
-        let mut byte_array = vec![0; sample_size as usize];
-        file.seek(SeekFrom::Start(0)).unwrap();
-        file.read_exact(&mut byte_array).unwrap();
+        let byte_array = read_sample_bytes(file, 0, sample_size as usize);
+        let read_count = byte_array.len() as i64;
 
         let mut n = 0;
 
         cell_address.push(0);
 
-        while n < sample_size {
+        while n < read_count {
             if byte_array[n as usize] == delimiter {
                 if double_quote_count % 2 == 0 {
                     cell_address.push(n + 1);
@@ -204,12 +552,17 @@ fn get_column_name(file: &mut File, delimiter: u8) -> Vec<String> {
                     .unwrap(),
             );
         }
+
+        // End of file
+        if read_count < sample_size as i64 {
+            break;
+        }
     }
 
     column_name
 }
 
-fn cell_address(byte_array: &Vec<u8>, csv_meta: CsvMeta) -> Vec<i64> {
+fn cell_address(byte_array: &[u8], csv_meta: &CsvMeta) -> Vec<i64> {
     
     let read_csv_delimiter = csv_meta.delimiter;
 
@@ -221,12 +574,15 @@ fn cell_address(byte_array: &Vec<u8>, csv_meta: CsvMeta) -> Vec<i64> {
 
     for (i, &byte) in byte_array.iter().enumerate() {
         if byte == read_csv_delimiter {
-            if double_quote_count != 1 {
+            if double_quote_count % 2 == 0 {
                 cell_address.push(i as i64 + 1);
                 double_quote_count = 0;
             }
+        } else if double_quote_count % 2 == 1 && (byte == 10 || byte == 13) {
+            // A line break inside double quotes belongs to the cell
         } else if byte == 10 {
             cell_address.push(i as i64 + 1);
+            double_quote_count = 0;
             _row += 1;
         } else if byte == 13 {
             cell_address.push(i as i64 + 1);
@@ -238,14 +594,86 @@ fn cell_address(byte_array: &Vec<u8>, csv_meta: CsvMeta) -> Vec<i64> {
     cell_address
 }
 
-fn current_view(byte_array: &Vec<u8>, csv_meta: CsvMeta, start_column: i32, end_column: i32, total_row: i32) {
+// A row ends at a line break outside double quotes, the same rule as cell_address
+fn count_row(byte_array: &[u8]) -> i64 {
+    let mut double_quote_count = 0;
+    let mut total_row = 0;
+
+    for &byte in byte_array {
+        if byte == 34 {
+            double_quote_count += 1;
+        } else if byte == 10 && double_quote_count % 2 == 0 {
+            total_row += 1;
+        }
+    }
+
+    total_row
+}
+
+// "column, column" to column index
+fn get_column_id(column_list: &str, csv_meta: &CsvMeta) -> Result<Vec<usize>, String> {
+    let mut column_id = Vec::new();
+    let mut error_message = String::new();
+
+    for column in column_list.split(',') {
+        let column = column.trim();
+
+        if column.is_empty() {
+            continue;
+        }
+
+        match csv_meta.column_name.iter().position(|x| x == column) {
+            Some(n) => column_id.push(n),
+            None => error_message.push_str(&format!("** Column {} is not found ** \n", column)),
+        }
+    }
+
+    if column_id.is_empty() && error_message.is_empty() {
+        error_message.push_str("** Fail to find any column ** \n");
+    }
+
+    if error_message.is_empty() {
+        Ok(column_id)
+    } else {
+        Err(error_message)
+    }
+}
+
+fn cell_per_row(csv_meta: &CsvMeta) -> usize {
+    let extra_line_br_char = if csv_meta.is_line_br_13_exist { 1 } else { 0 };
+    (csv_meta.total_column + extra_line_br_char) as usize
+}
+
+fn cell_range(byte_array: &[u8], cell_address: &[i64], current_cell: usize) -> (usize, usize) {
+    let start_byte = cell_address[current_cell];
+    let end_byte = cell_address[current_cell + 1] - 1;
+    let (start_byte, end_byte) = skip_white_space(byte_array, start_byte, end_byte);
+    (start_byte as usize, end_byte as usize)
+}
+
+// 123, -123.45 or (123.45) for negative. The one rule for a number cell, view_csv keeps its own
+// looser alignment rule in max_column_width
+fn parse_real_number(byte_array: &[u8]) -> Option<f64> {
+    if byte_array.len() > 2 && byte_array[0] == b'(' && byte_array[byte_array.len() - 1] == b')' {
+        return parse_real_number(&byte_array[1..byte_array.len() - 1]).map(|x| -x);
+    }
+
+    if byte_array.is_empty() || !byte_array.iter().all(|x| x.is_ascii_digit() || *x == b'.' || *x == b'-') {
+        return None;
+    }
+
+    str::from_utf8(byte_array).ok()?.parse::<f64>().ok()
+}
+
+#[allow(clippy::same_item_push)]
+fn current_view(byte_array: &[u8], csv_meta: CsvMeta, start_column: i32, end_column: i32, total_row: i32) {
     
-    let cell_address = cell_address(byte_array, csv_meta.clone());
+    let cell_address = cell_address(byte_array, &csv_meta);
 
     let extra_line_br_char = if csv_meta.is_line_br_13_exist { 1 } else { 0 };
     
     let max_col = csv_meta.total_column + extra_line_br_char - 1;    
-    let cell = cell_address.len() as u32 - max_col as u32 + 1;
+    let cell = cell_address.len() as u32 - (max_col as u32 + 1);
     let mut n = 0u32;
     let mut current_row = 0i32;    
     let mut temp_bytes: Vec<u8> = Vec::new();
@@ -274,7 +702,7 @@ fn current_view(byte_array: &Vec<u8>, csv_meta: CsvMeta, start_column: i32, end_
             temp_bytes.push(read_csv_delimiter);
         }
 
-        let current_cell = (max_col + 1) as i32 * current_row + current_column as i32;
+        let current_cell = (max_col + 1) * current_row + current_column;
         let start_byte = cell_address[current_cell as usize];
         let end_byte = cell_address[(current_cell + 1) as usize] - 1;
         let (start_byte, end_byte) = skip_white_space(byte_array, start_byte, end_byte);
@@ -380,7 +808,7 @@ fn current_view(byte_array: &Vec<u8>, csv_meta: CsvMeta, start_column: i32, end_
     n += (max_col + 1) as u32;
     current_row += 1;
 
-    if current_row >= total_row as i32 {
+    if current_row >= total_row {
         break;
     }
 
@@ -456,7 +884,7 @@ fn current_view(byte_array: &Vec<u8>, csv_meta: CsvMeta, start_column: i32, end_
         result_bytes.push(32);
 
         for current_column in start_column..end_column {
-            let current_cell = (max_col as i32 + 1) * current_row + current_column as i32;
+            let current_cell = (max_col + 1) * current_row + current_column;
             let start_byte = cell_address[current_cell as usize];
             let end_byte = cell_address[current_cell as usize + 1] - 1;
             let (start_byte, end_byte) = skip_white_space(byte_array, start_byte, end_byte);
@@ -548,7 +976,7 @@ fn current_view(byte_array: &Vec<u8>, csv_meta: CsvMeta, start_column: i32, end_
 
         n += max_col as u32 + 1;
         current_row += 1;
-        if current_row >= total_row as i32 {
+        if current_row >= total_row {
             break;
         }
     }    
@@ -556,9 +984,9 @@ fn current_view(byte_array: &Vec<u8>, csv_meta: CsvMeta, start_column: i32, end_
     println!("{}", String::from_utf8_lossy(&result_bytes));
 }
 
-fn max_column_width(byte_array: &Vec<u8>, csv_meta: CsvMeta) -> (bool, HashMap<i32, i32>) {
+fn max_column_width(byte_array: &[u8], csv_meta: CsvMeta) -> (bool, HashMap<i32, i32>) {
     
-    let cell_address = cell_address(byte_array, csv_meta.clone());
+    let cell_address = cell_address(byte_array, &csv_meta);
     let is_zero_row = byte_array.is_empty();   
     let extra_line_br_char = if csv_meta.is_line_br_13_exist { 1 } else { 0 };   
     let max_col = csv_meta.total_column + extra_line_br_char - 1;
@@ -592,7 +1020,7 @@ fn max_column_width(byte_array: &Vec<u8>, csv_meta: CsvMeta) -> (bool, HashMap<i
                 }
 
                 let current_cell =
-                    (max_col + 1) as i32 * current_row + current_column as i32;
+                    (max_col + 1) * current_row + current_column;
                 let start_byte = cell_address[current_cell as usize];
                 let end_byte = cell_address[(current_cell + 1) as usize] - 1;
                 let (start_byte, end_byte) =
@@ -724,9 +1152,50 @@ fn max_column_width(byte_array: &Vec<u8>, csv_meta: CsvMeta) -> (bool, HashMap<i
     (is_zero_row, max_column_width)
 }
 
+// Text of a cell enclosed in double quotes, with "" as "
+fn unquote_cell(byte_array: &[u8]) -> Cow<'_, [u8]> {
+    match byte_array {
+        [b'"', x @ .., b'"'] if x.contains(&b'"') => {
+            let mut text = Vec::with_capacity(x.len());
+            let mut is_previous_quote = false;
+
+            for &byte in x {
+                if byte == b'"' && is_previous_quote {
+                    is_previous_quote = false;
+                    continue;
+                }
+                is_previous_quote = byte == b'"';
+                text.push(byte);
+            }
+
+            Cow::Owned(text)
+        }
+        [b'"', x @ .., b'"'] => Cow::Borrowed(x),
+        x => Cow::Borrowed(x),
+    }
+}
+
+// A text with the delimiter, double quote or line break is enclosed in double quotes
+fn write_csv_cell(text: &[u8], delimiter: u8, result_bytes: &mut Vec<u8>) {
+    if text.iter().any(|&x| x == delimiter || x == b'"' || x == b'\r' || x == b'\n') {
+        result_bytes.push(b'"');
+        for &byte in text {
+            if byte == b'"' {
+                result_bytes.push(b'"');
+            }
+            result_bytes.push(byte);
+        }
+        result_bytes.push(b'"');
+    } else {
+        result_bytes.extend_from_slice(text);
+    }
+}
+
 #[pyfunction]
-fn view_csv(byte_array: Vec<u8>, csv_meta: CsvMeta) {
-    let (is_zero_row, max_column_width) = max_column_width(&byte_array, csv_meta.clone());
+fn view_csv(df: &Dataframe) {
+    let byte_array = &df.byte_array;
+    let csv_meta = df.csv_meta.clone();
+    let (is_zero_row, max_column_width) = max_column_width(byte_array, csv_meta.clone());
 
     if !is_zero_row {
         let mut total_width = 0;
@@ -751,7 +1220,7 @@ fn view_csv(byte_array: Vec<u8>, csv_meta: CsvMeta) {
                 current_width += max_column_width[&(current_column as i32)];
                 if current_width > 100 * table_count {
                     current_view(
-                        &byte_array,
+                        byte_array,
                         csv_meta.clone(),
                         start_column,
                         current_column as i32,
@@ -765,7 +1234,7 @@ fn view_csv(byte_array: Vec<u8>, csv_meta: CsvMeta) {
             }
 
             current_view(
-                &byte_array,
+                byte_array,
                 csv_meta.clone(),
                 start_column,
                 max_column_width.len() as i32,
@@ -778,7 +1247,7 @@ fn view_csv(byte_array: Vec<u8>, csv_meta: CsvMeta) {
                 current_width += max_column_width[&(current_column as i32)];
                 if current_width > total_width / 2 {
                     current_view(
-                        &byte_array,
+                        byte_array,
                         csv_meta.clone(),
                         0,
                         current_column as i32,
@@ -791,7 +1260,7 @@ fn view_csv(byte_array: Vec<u8>, csv_meta: CsvMeta) {
             }
 
             current_view(
-                &byte_array,
+                byte_array,
                 csv_meta.clone(),
                 start_column,
                 max_column_width.len() as i32,
@@ -799,7 +1268,7 @@ fn view_csv(byte_array: Vec<u8>, csv_meta: CsvMeta) {
             );
         } else {
             current_view(
-                &byte_array,
+                byte_array,
                 csv_meta,
                 0,
                 max_column_width.len() as i32,
@@ -809,31 +1278,85 @@ fn view_csv(byte_array: Vec<u8>, csv_meta: CsvMeta) {
     }
 }
 
-#[pyfunction]
-fn write_csv(byte_array: Vec<u8>, csv_meta: CsvMeta) {
+#[pyfunction(file_name = "\"%Sample.csv\"")]
+fn write_csv(df: &Dataframe, file_name: &str) {
+    let byte_array = &df.byte_array;
+    let csv_meta = &df.csv_meta;
     let mut csv_string = String::new();
+    let delimiter = if csv_meta.delimiter == 0 { ',' } else { csv_meta.delimiter as char };
 
     csv_string.push_str(&csv_meta.column_name[0]);
 
     for i in 1..csv_meta.column_name.len() {
-        csv_string.push_str(",");
+        csv_string.push(delimiter);
         csv_string.push_str(&csv_meta.column_name[i]);
     }
 
     csv_string.push_str("\r\n");
 
-    let f = File::create("%Sample.csv").expect("Unable to create file");
+    let f = File::create(file_name).expect("Unable to create file");
     let mut f = BufWriter::new(f);
 
     f.write_all(csv_string.as_bytes()).expect("Unable to write data");
-    f.write_all(&byte_array).expect("Unable to write data");
+    f.write_all(byte_array).expect("Unable to write data");
 
-    println!("A file named %Sample.csv is created from the rows that executed validation.");
+    if file_name == "%Sample.csv" {
+        println!("A file named %Sample.csv is created from the rows that executed validation.");
+    } else {
+        println!("A file named {} is created.", file_name);
+    }
     println!();
 }
 
-#[pyfunction]
-fn get_csv_sample(filepath: &str, mut sample_row: i32) -> PyResult<(Vec<u8>, CsvMeta)> {
+// Plain csv file only, the commands seeking the file cannot read a compressed file
+fn read_csv_sample(filepath: &str, sample_row: i32) -> PyResult<(Vec<u8>, CsvMeta)> {
+    let compression = get_compression(filepath)?;
+
+    if compression != Compression::None {
+        let csv_meta = CsvMeta {
+            error_message: format!(
+                "** File {} is compressed by {}, which can be read by read_csv or get_csv_sample only ** \n",
+                filepath,
+                compression.name()
+            ),
+            compression: compression.name().to_string(),
+            ..Default::default()
+        };
+        return Ok((Vec::new(), csv_meta));
+    }
+
+    let mut file = File::open(filepath)?;
+    let file_size = metadata(filepath)?.len() as i64;
+
+    Ok(read_sample(&mut file, file_size, sample_row))
+}
+
+// A compressed file is sampled from a decompressed prefix of it, file_size is the estimated
+// uncompressed size
+fn read_any_csv_sample(filepath: &str, sample_row: i32) -> PyResult<(Vec<u8>, CsvMeta)> {
+    Ok(sample_compressed(filepath, sample_row, |mut reader, file_size, sample_row| {
+        Ok(read_sample(&mut reader, file_size, sample_row))
+    })?)
+}
+
+// Encoding of the header and sampled rows, utf-8-sig if led by a byte order mark. A file which is not
+// UTF-8 is usually in a legacy code page such as Windows-1252, which cannot be told apart by bytes.
+fn detect_encoding(header_byte: &[u8], sample_byte: &[u8]) -> &'static str {
+    let (is_bom_exist, header_byte) = match header_byte.strip_prefix(b"\xEF\xBB\xBF") {
+        Some(x) => (true, x),
+        None => (false, header_byte),
+    };
+
+    if str::from_utf8(header_byte).is_err() || str::from_utf8(sample_byte).is_err() {
+        "non-utf-8"
+    } else if is_bom_exist {
+        "utf-8-sig"
+    } else {
+        "utf-8"
+    }
+}
+
+fn read_sample<R: Read + Seek>(file: &mut R, file_size: i64, mut sample_row: i32) -> (Vec<u8>, CsvMeta) {
     
     let mut csv_meta = CsvMeta {
         total_column: 0,
@@ -842,9 +1365,12 @@ fn get_csv_sample(filepath: &str, mut sample_row: i32) -> PyResult<(Vec<u8>, Csv
         is_line_br_13_exist: false,
         is_line_br_10_exist: false,
         column_name: Vec::new(),
-        file_size: 0,
+        file_size,
         delimiter: 0,
         error_message: String::new(), 
+        compression: Compression::None.name().to_string(),
+        compressed_size: file_size,
+        encoding: String::new(),
     };       
     
     let mut _is_error: bool = false;
@@ -857,13 +1383,6 @@ fn get_csv_sample(filepath: &str, mut sample_row: i32) -> PyResult<(Vec<u8>, Csv
   
     let mut _delimiter_scenario = HashMap::new(); 
 
-    let file = File::open(filepath);
-    let fileinfo = metadata(filepath);     
-    let mut file = file.unwrap();  
-    let fileinfo = fileinfo.unwrap();
-
-    csv_meta.file_size = fileinfo.len() as i64;
-
     // Default output number of sample rows 
     if sample_row <= 0 || csv_meta.file_size <= 10000 {
         sample_row = 10;
@@ -874,15 +1393,16 @@ fn get_csv_sample(filepath: &str, mut sample_row: i32) -> PyResult<(Vec<u8>, Csv
     }
 
     // Column Name
-   let (_current_row_byte_count, _frequency_distribution, _current_row_byte) = get_current_row_frequency_distribution(&mut file, 0);
+   let (_current_row_byte_count, _frequency_distribution, _current_row_byte) = get_current_row_frequency_distribution(file, 0);
+   let header_byte = _current_row_byte;
    
     _delimiter_scenario = _frequency_distribution.clone();
 
     // Data Row
-    while n <= sample_row as i64 - 1 {
+    while n < sample_row as i64 {
         start_byte += 1;
 
-        let (_current_row_byte_count, _frequency_distribution, _current_row_byte) = get_current_row_frequency_distribution(&mut file, start_byte);
+        let (_current_row_byte_count, _frequency_distribution, _current_row_byte) = get_current_row_frequency_distribution(file, start_byte);
    
         csv_vector.extend(_current_row_byte);
         sample_byte_count += _current_row_byte_count;
@@ -892,10 +1412,10 @@ fn get_csv_sample(filepath: &str, mut sample_row: i32) -> PyResult<(Vec<u8>, Csv
         let mut temp_delimiter_scenario = HashMap::new();
 
         for key in _delimiter_scenario.keys() {
-            if frequency_distribution_by_sample[&n].contains_key(key) {
-                if frequency_distribution_by_sample[&n][key] == _delimiter_scenario[key] {
-                    temp_delimiter_scenario.insert(*key, _delimiter_scenario[key]);
-                }
+            if frequency_distribution_by_sample[&n].contains_key(key)
+                && frequency_distribution_by_sample[&n][key] == _delimiter_scenario[key]
+            {
+                temp_delimiter_scenario.insert(*key, _delimiter_scenario[key]);
             }
         }
 
@@ -906,6 +1426,7 @@ fn get_csv_sample(filepath: &str, mut sample_row: i32) -> PyResult<(Vec<u8>, Csv
     }
 
     csv_meta.validate_row = n;
+    csv_meta.encoding = detect_encoding(&header_byte, &csv_vector).to_string();
 
     // Remove line break from current delimiters
     let mut delimiter_exclude_line_br = HashMap::new();
@@ -978,11 +1499,21 @@ fn get_csv_sample(filepath: &str, mut sample_row: i32) -> PyResult<(Vec<u8>, Csv
     }
 
     // Record error messages
-   if _is_error == false  {
+   if !_is_error  {
+
+         csv_meta.column_name = get_column_name(file, csv_meta.delimiter);
 
-         csv_meta.column_name = get_column_name(&mut file, csv_meta.delimiter);
-         csv_meta.estimate_row =
-             csv_meta.file_size as i64 / sample_byte_count as i64 * sample_row as i64;
+         // A byte order mark is not part of the first column name
+         if let Some(x) = csv_meta.column_name.first_mut() {
+             if let Some(y) = x.strip_prefix('\u{feff}') {
+                 *x = y.to_string();
+             }
+         }
+         // No data row, e.g. an empty standard input
+         if sample_byte_count > 0 {
+             csv_meta.estimate_row =
+                 csv_meta.file_size / sample_byte_count as i64 * sample_row as i64;
+         }
 
          if delimiter_exclude_line_br.is_empty() {            
              error_message.push_str("** Fail to find delimiter ** \n");
@@ -1011,7 +1542,88 @@ fn get_csv_sample(filepath: &str, mut sample_row: i32) -> PyResult<(Vec<u8>, Csv
          csv_meta.error_message = error_message
      }    
 
-     Ok((csv_vector, csv_meta))    
+     (csv_vector, csv_meta)
+}
+
+// Rows after the header of a csv read from start to end, file_size is then the bytes read
+fn read_all_row<R: BufRead>(mut reader: R, csv_meta: &mut CsvMeta) -> std::io::Result<Vec<u8>> {
+    let mut header = Vec::new();
+    let mut byte_array = Vec::new();
+    read_record(&mut reader, &mut header)?;
+    reader.read_to_end(&mut byte_array)?;
+    csv_meta.file_size = (header.len() + byte_array.len()) as i64;
+
+    if !byte_array.is_empty() && byte_array.last() != Some(&10) {
+        if csv_meta.is_line_br_13_exist {
+            byte_array.push(13);
+        }
+        byte_array.push(10);
+    }
+
+    Ok(byte_array)
+}
+
+fn read_csv_file(filepath: &str) -> PyResult<(Vec<u8>, CsvMeta)> {
+    let (_, mut csv_meta) = read_any_csv_sample(filepath, 10)?;
+
+    if !csv_meta.error_message.is_empty() {
+        return Ok((Vec::new(), csv_meta));
+    }
+
+    let byte_array = if csv_meta.compression == Compression::None.name() {
+        let mut file = File::open(filepath)?;
+        let header_byte_count = get_header_byte_count(&mut file);
+        let (byte_array, _) = read_partition(
+            &mut file,
+            header_byte_count,
+            csv_meta.file_size - header_byte_count,
+            csv_meta.file_size,
+            &csv_meta,
+        );
+        byte_array
+    } else {
+        // Streaming decompression of the whole file, then the exact uncompressed size is known
+        read_all_row(BufReader::with_capacity(1 << 20, open_decoder(filepath)?), &mut csv_meta)?
+    };
+
+    csv_meta.validate_row = count_row(&byte_array);
+    csv_meta.estimate_row = csv_meta.validate_row;
+
+    Ok((byte_array, csv_meta))
+}
+
+// mode is even, uniform, reservoir or stratified by key_column. filepath is a path, a glob or a
+// list of them, several files are sampled as one dataframe with a source_file column.
+#[pyfunction(mode = "\"even\"", seed = "None", key_column = "\"\"")]
+fn get_csv_sample(
+    py: Python,
+    mut filepath: FilePath,
+    sample_row: i32,
+    mode: &str,
+    seed: Option<u64>,
+    key_column: &str,
+) -> PyResult<Dataframe> {
+    if let (FilePath::Seekable(x), "even") = (&filepath, mode) {
+        let (byte_array, csv_meta) = read_seekable_sample(x.as_ref(py), sample_row)?;
+        return Ok(Dataframe::new(byte_array, csv_meta));
+    }
+
+    let (byte_array, csv_meta) = match (filepath.read_memory(py, Some(STREAM_SAMPLE_SIZE))?, filepath.single_file()) {
+        (Some(data), _) => read_buffer_sample(&data, sample_row, mode, seed, key_column)?,
+        (None, Some(x)) => read_csv_sample_by_mode(x, sample_row, mode, seed, key_column)?,
+        (None, None) => read_multi_file(py, filepath.file_list(), sample_row, |x, n| read_csv_sample_by_mode(x, n, mode, seed, key_column))?,
+    };
+    Ok(Dataframe::new(byte_array, csv_meta))
+}
+
+#[pyfunction]
+fn read_csv(py: Python, mut filepath: FilePath) -> PyResult<Dataframe> {
+    let (byte_array, csv_meta) = match (filepath.read_memory(py, None)?, filepath.single_file()) {
+        (Some(data), _) => read_buffer_file(&data)?,
+        (None, Some(x)) => read_csv_file(x)?,
+        (None, None) => read_multi_file(py, filepath.file_list(), 0, |x, _| read_csv_file(x))?,
+    };
+    Ok(Dataframe::new(byte_array, csv_meta))
 }
 
 #[pymodule]
@@ -1019,6 +1631,153 @@ fn peakrs(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(get_csv_sample, m)?)?;
     m.add_function(wrap_pyfunction!(view_csv, m)?)?;
     m.add_function(wrap_pyfunction!(write_csv, m)?)?;    
+    m.add_function(wrap_pyfunction!(orderby, m)?)?;
+    m.add_function(wrap_pyfunction!(orderby_file, m)?)?;
+    m.add_function(wrap_pyfunction!(add_column, m)?)?;
+    m.add_function(wrap_pyfunction!(read_csv, m)?)?;
+    m.add_function(wrap_pyfunction!(build_keyvalue, m)?)?;
+    m.add_function(wrap_pyfunction!(join_keyvalue, m)?)?;
+    m.add_function(wrap_pyfunction!(join_keyvalue_file, m)?)?;
+    m.add_function(wrap_pyfunction!(drop_keyvalue, m)?)?;
+    m.add_function(wrap_pyfunction!(jointable, m)?)?;
+    m.add_function(wrap_pyfunction!(join_cardinality, m)?)?;
+    m.add_function(wrap_pyfunction!(split_file, m)?)?;
+    m.add_function(wrap_pyfunction!(create_folder_lake, m)?)?;
+    m.add_function(wrap_pyfunction!(create_folder_lake_file, m)?)?;
+    m.add_function(wrap_pyfunction!(read_folder_lake, m)?)?;
+    m.add_function(wrap_pyfunction!(filter, m)?)?;
+    m.add_function(wrap_pyfunction!(filter_unmatch, m)?)?;
+    m.add_function(wrap_pyfunction!(select, m)?)?;
+    m.add_function(wrap_pyfunction!(select_unmatch, m)?)?;
+    m.add_function(wrap_pyfunction!(distinct, m)?)?;
+    m.add_function(wrap_pyfunction!(groupby, m)?)?;
+    m.add_function(wrap_pyfunction!(validate_script, m)?)?;
+    m.add_function(wrap_pyfunction!(run_script, m)?)?;
+    m.add_function(wrap_pyfunction!(stream_file, m)?)?;
+    m.add_function(wrap_pyfunction!(scan_csv, m)?)?;
+    m.add_function(wrap_pyfunction!(from_arrow, m)?)?;
+    m.add_function(wrap_pyfunction!(iter_batches, m)?)?;
+    m.add_function(wrap_pyfunction!(profile, m)?)?;
+    m.add_function(wrap_pyfunction!(get_jsonl_sample, m)?)?;
+    m.add_function(wrap_pyfunction!(read_jsonl, m)?)?;
+    m.add_function(wrap_pyfunction!(write_jsonl, m)?)?;
+    m.add_function(wrap_pyfunction!(read_parquet, m)?)?;
+    m.add_function(wrap_pyfunction!(infer_fixed_width_layout, m)?)?;
+    m.add_function(wrap_pyfunction!(get_fixed_width_sample, m)?)?;
+    m.add_function(wrap_pyfunction!(read_fixed_width, m)?)?;
+    m.add_function(wrap_pyfunction!(read_xlsx, m)?)?;
+    m.add_function(wrap_pyfunction!(write_parquet, m)?)?;
+    m.add_function(wrap_pyfunction!(validate, m)?)?;
+    m.add_function(wrap_pyfunction!(fingerprint, m)?)?;
+    m.add_function(wrap_pyfunction!(read_fingerprint, m)?)?;
+    m.add_function(wrap_pyfunction!(compare_meta, m)?)?;
+    m.add_function(wrap_pyfunction!(check_fingerprint, m)?)?;
     m.add_class::<CsvMeta>().unwrap();
+    m.add_class::<Dataframe>().unwrap();
+    m.add_class::<LazyFrame>().unwrap();
+    m.add_class::<NumericArray>().unwrap();
+    m.add_class::<Profile>().unwrap();
+    m.add_class::<Validation>().unwrap();
+    m.add_class::<Fingerprint>().unwrap();
+    m.add_class::<MetaDiff>().unwrap();
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::test_csv_meta;
+
+    #[test]
+    fn json_default_is_the_same_as_csv_meta() {
+        let new_meta = CsvMeta::new(0, 0, 0, false, true, Vec::new(), 0, 44, "", "none", 0, "");
+        let json_meta = CsvMeta::from_value(&json!({})).unwrap();
+
+        assert_eq!(CsvMeta::default().compression, "none");
+        assert_eq!(json_meta.to_json(), new_meta.to_json());
+
+        let new_meta = CsvMeta::new(0, 0, 0, false, true, vec!["a".to_string(), "b".to_string()], 0, 44, "", "none", 0, "");
+        let json_meta = CsvMeta::from_value(&json!({"column_name": ["a", "b"]})).unwrap();
+
+        assert_eq!(json_meta.total_column, 2);
+        assert_eq!(json_meta.to_json(), new_meta.to_json());
+    }
+
+    #[test]
+    fn csv_meta_json_round_trip() {
+        let mut x = CsvMeta::new(0, 5, 6, true, true, vec!["a".to_string(), "b, c".to_string()], 120, b';', "", "gzip", 40, "utf-8-sig");
+        x.error_message = "** Fail ** \n".to_string();
+
+        let y = CsvMeta::from_json(&x.to_json()).unwrap();
+        assert_eq!(y.to_json(), x.to_json());
+        assert_eq!(y.total_column, 2);
+        assert_eq!(y.delimiter, b';');
+        assert_eq!(y.column_name, vec!["a", "b, c"]);
+
+        assert!(CsvMeta::from_value(&json!({"delimiter": 300})).is_err());
+        assert!(CsvMeta::from_value(&json!({"row": 1})).is_err());
+    }
+
+    #[test]
+    fn csv_meta_pickle_round_trip() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let module = pyo3::wrap_pymodule!(peakrs)(py);
+            py.import("sys").unwrap().getattr("modules").unwrap().set_item("peakrs", module).unwrap();
+
+            let x = CsvMeta::new(0, 5, 6, false, true, vec!["id".to_string()], 120, b',', "", "zstd", 40, "utf-8");
+            let pickle = py.import("pickle").unwrap();
+            let state = pickle.call_method1("dumps", (Py::new(py, x.clone()).unwrap(),)).unwrap();
+            let y: CsvMeta = pickle.call_method1("loads", (state,)).unwrap().extract().unwrap();
+
+            assert_eq!(y.to_json(), x.to_json());
+        });
+    }
+
+    #[test]
+    fn repr_has_total_column_and_validate_row() {
+        let x = CsvMeta::new(0, 5, 6, false, true, vec!["a".to_string(), "b".to_string()], 120, b',', "", "none", 120, "utf-8");
+        assert_eq!(
+            x.__repr__(),
+            "CsvMeta(delimiter=',', column=[a, b], total_column=2, validate_row=5, estimate_row=6, file_size=120, line_break=\\n, encoding=utf-8, compression=none)"
+        );
+    }
+
+    #[test]
+    fn view_keeps_a_bare_minus_sign_aligned_as_a_number() {
+        let (_, width) = max_column_width(b"-,(12)\n1.25,3\n", test_csv_meta(&["a", "b"]));
+
+        // Integer part 1 and decimal part 2 of 1.25 with its dot; (12) counts its brackets
+        assert_eq!(width[&0], 4);
+        assert_eq!(width[&1], 6);
+    }
+
+    #[test]
+    fn cell_address_splits_on_double_quote_parity() {
+        let text = b"\"a\"\"b,c\",1\n\"x\ny\",2\n";
+        let address = cell_address(text, &test_csv_meta(&["a", "b"]));
+
+        // "a""b,c" and "x<LF>y" each stay one cell
+        assert_eq!(address, vec![0, 9, 11, 17, 19]);
+        assert_eq!(&text[0..8], b"\"a\"\"b,c\"");
+        assert_eq!(&text[11..16], b"\"x\ny\"");
+    }
+
+    #[test]
+    fn row_count_skips_quoted_line_breaks() {
+        assert_eq!(count_row(b"1,\"a\nb\"\n2,\"c\"\"\nd\"\n"), 2);
+        assert_eq!(count_row(b"1,a\r\n2,b\r\n"), 2);
+        assert_eq!(count_row(b""), 0);
+    }
+
+    #[test]
+    fn real_number() {
+        assert_eq!(parse_real_number(b"-12.5"), Some(-12.5));
+        assert_eq!(parse_real_number(b"(12.5)"), Some(-12.5));
+        assert_eq!(parse_real_number(b"1."), Some(1.0));
+        assert_eq!(parse_real_number(b"-"), None);
+        assert_eq!(parse_real_number(b"()"), None);
+        assert_eq!(parse_real_number(b"1-2"), None);
+        assert_eq!(parse_real_number(b""), None);
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{cell_address, cell_per_row, write_csv_cell, CsvMeta};
+use pyo3::prelude::*;
+use std::fs::metadata;
+
+pub(crate) fn is_glob(file_path: &str) -> bool {
+    file_path.contains(['*', '?', '['])
+}
+
+// Matching files of each glob in name order
+fn expand_file_path(file_path: &[String]) -> Result<Vec<String>, String> {
+    let mut expanded_path = Vec::new();
+
+    for pattern in file_path {
+        if !is_glob(pattern) {
+            expanded_path.push(pattern.clone());
+            continue;
+        }
+
+        let path = glob::glob(pattern).map_err(|x| format!("** {} is not a valid glob: {} ** \n", pattern, x))?;
+        let mut matched_path: Vec<String> = path.filter_map(|x| x.ok()).filter(|x| x.is_file()).map(|x| x.to_string_lossy().to_string()).collect();
+
+        if matched_path.is_empty() {
+            return Err(format!("** No file matches {} ** \n", pattern));
+        }
+
+        matched_path.sort();
+        expanded_path.extend(matched_path);
+    }
+
+    if expanded_path.is_empty() {
+        return Err("** No file is given ** \n".to_string());
+    }
+
+    Ok(expanded_path)
+}
+
+// A file which cannot be read or has drifted from the first file is skipped, the rows of the other
+// files are still valid, so it is not an error
+fn check_file(file_meta: &CsvMeta, first_meta: &CsvMeta, current_path: &str, first_path: &str) -> Option<String> {
+    if !file_meta.error_message.is_empty() {
+        return Some(format!("File {} cannot be read: {}", current_path, file_meta.error_message.trim_end()));
+    }
+
+    if file_meta.delimiter != first_meta.delimiter {
+        return Some(format!(
+            "Delimiter of {} is ASCII{}, but ASCII{} in {}",
+            current_path, file_meta.delimiter, first_meta.delimiter, first_path
+        ));
+    }
+
+    if file_meta.column_name != first_meta.column_name {
+        return Some(format!(
+            "Column names of {} are {:?}, but {:?} in {}",
+            current_path, file_meta.column_name, first_meta.column_name, first_path
+        ));
+    }
+
+    None
+}
+
+// Files read by read_file as one dataframe with a source_file column appended. Every file should
+// have the delimiter and column names of the first file, a file which does not is skipped with a
+// UserWarning. sample_row is shared by file size, at least one row for each file.
+pub(crate) fn read_multi_file<F>(py: Python, file_path: &[String], sample_row: i32, mut read_file: F) -> PyResult<(Vec<u8>, CsvMeta)>
+where
+    F: FnMut(&str, i32) -> PyResult<(Vec<u8>, CsvMeta)>,
+{
+    let file_path = match expand_file_path(file_path) {
+        Ok(x) => x,
+        Err(error_message) => {
+            let csv_meta = CsvMeta {
+                error_message,
+                ..Default::default()
+            };
+            return Ok((Vec::new(), csv_meta));
+        }
+    };
+
+    let mut file_size = Vec::new();
+
+    for current_path in &file_path {
+        file_size.push(metadata(current_path)?.len() as f64);
+    }
+
+    let total_size: f64 = file_size.iter().sum::<f64>().max(1.0);
+    let share = |n: usize| ((sample_row.max(0) as f64 * file_size[n] / total_size).round() as i32).max(1);
+
+    let (first_bytes, first_meta) = read_file(&file_path[0], share(0))?;
+
+    if !first_meta.error_message.is_empty() {
+        return Ok((Vec::new(), first_meta));
+    }
+
+    let file_column_count = first_meta.total_column as usize;
+    let line_br: &[u8] = if first_meta.is_line_br_13_exist { b"\r\n" } else { b"\n" };
+
+    let mut csv_meta = first_meta.clone();
+    csv_meta.column_name.push("source_file".to_string());
+    csv_meta.total_column += 1;
+    csv_meta.validate_row = 0;
+    csv_meta.estimate_row = 0;
+    csv_meta.file_size = 0;
+    csv_meta.compressed_size = 0;
+
+    let mut first_file = Some((first_bytes, first_meta.clone()));
+    let mut result_bytes = Vec::new();
+
+    for (n, current_path) in file_path.iter().enumerate() {
+        let (byte_array, file_meta) = match first_file.take() {
+            Some(x) => x,
+            None => read_file(current_path, share(n))?,
+        };
+
+        if let Some(message) = check_file(&file_meta, &first_meta, current_path, &file_path[0]) {
+            let user_warning = py.import("builtins")?.getattr("UserWarning")?;
+            PyErr::warn(py, user_warning, &format!("{}, the file is skipped", message), 1)?;
+            continue;
+        }
+
+        if file_meta.compression != csv_meta.compression {
+            csv_meta.compression = "mixed".to_string();
+        }
+
+        if file_meta.encoding != csv_meta.encoding {
+            csv_meta.encoding = "mixed".to_string();
+        }
+
+        csv_meta.file_size += file_meta.file_size;
+        csv_meta.compressed_size += file_meta.compressed_size;
+        csv_meta.validate_row += file_meta.validate_row;
+        csv_meta.estimate_row += file_meta.estimate_row;
+
+        let mut source_file = vec![first_meta.delimiter];
+        write_csv_cell(current_path.as_bytes(), first_meta.delimiter, &mut source_file);
+
+        // Line breaks follow the first file, so that every row has the same cells
+        let cell_address = cell_address(&byte_array, &file_meta);
+        let cell_per_row = cell_per_row(&file_meta);
+        let total_row = (cell_address.len() - 1) / cell_per_row;
+
+        for current_row in 0..total_row {
+            let first_cell = current_row * cell_per_row;
+            let start_byte = cell_address[first_cell] as usize;
+            let end_byte = cell_address[first_cell + file_column_count] as usize - 1;
+
+            result_bytes.extend_from_slice(&byte_array[start_byte..end_byte]);
+            result_bytes.extend_from_slice(&source_file);
+            result_bytes.extend_from_slice(line_br);
+        }
+    }
+
+    Ok((result_bytes, csv_meta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_csv_file;
+    use pyo3::types::PyDict;
+    use std::fs;
+
+    #[test]
+    fn drifted_file_is_a_warning_not_an_error() {
+        pyo3::prepare_freethreaded_python();
+
+        let folder = std::env::temp_dir().join(format!("peakrs_multi_file_{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join("a.csv"), b"id,v\n1,x\n").unwrap();
+        fs::write(folder.join("b.csv"), b"id,w\n2,y\n").unwrap();
+        fs::write(folder.join("c.csv"), b"id,v\n3,z\n").unwrap();
+        let file_path = vec![folder.join("*.csv").to_string_lossy().to_string()];
+
+        Python::with_gil(|py| {
+            let locals = PyDict::new(py);
+            py.run("import warnings\ncaught = warnings.catch_warnings(record=True)\nrecord = caught.__enter__()\nwarnings.simplefilter('always')", None, Some(locals)).unwrap();
+
+            let (byte_array, csv_meta) = read_multi_file(py, &file_path, 0, |x, _| read_csv_file(x)).unwrap();
+            py.run("caught.__exit__(None, None, None)", None, Some(locals)).unwrap();
+            fs::remove_dir_all(&folder).unwrap();
+
+            let message: Vec<String> = py.eval("[str(x.message) for x in record]", None, Some(locals)).unwrap().extract().unwrap();
+
+            assert_eq!(csv_meta.error_message, "");
+            assert_eq!(csv_meta.column_name, vec!["id", "v", "source_file"]);
+            assert_eq!(csv_meta.validate_row, 2);
+            assert_eq!(message.len(), 1);
+            assert!(message[0].starts_with("Column names of ") && message[0].ends_with(", the file is skipped"));
+            assert!(byte_array.starts_with(b"1,x,"));
+        });
+    }
+
+    #[test]
+    fn source_file_is_quoted_for_the_delimiter() {
+        pyo3::prepare_freethreaded_python();
+
+        let folder = std::env::temp_dir().join(format!("peakrs_multi;file_{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join("a.csv"), b"id;v;w\n1;x;y\n").unwrap();
+        let file_path = vec![folder.join("*.csv").to_string_lossy().to_string()];
+
+        Python::with_gil(|py| {
+            let (byte_array, csv_meta) = read_multi_file(py, &file_path, 0, |x, _| read_csv_file(x)).unwrap();
+            fs::remove_dir_all(&folder).unwrap();
+
+            let source_file = folder.join("a.csv").to_string_lossy().to_string();
+            assert_eq!(csv_meta.delimiter, b';');
+            assert_eq!(byte_array, format!("1;x;y;\"{}\"\n", source_file).into_bytes());
+        });
+    }
+}
@@ -0,0 +1,342 @@
+use crate::dataframe::Dataframe;
+use crate::{cell_per_row, cell_range, parse_real_number};
+use pyo3::class::buffer::PyBufferProtocol;
+use pyo3::class::mapping::PyMappingProtocol;
+use pyo3::exceptions::{PyBufferError, PyKeyError, PyValueError};
+use pyo3::ffi;
+use pyo3::prelude::*;
+use pyo3::AsPyPointer;
+use std::collections::HashMap;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+use std::str;
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum NumericType {
+    Integer,
+    Float,
+}
+
+#[derive(Clone)]
+pub(crate) enum ArrayData {
+    Integer(Vec<i64>),
+    Float(Vec<f64>),
+    Bool(Vec<bool>),
+}
+
+// A contiguous column of int64, float64 or bool, readable without copy through the buffer protocol,
+// e.g. numpy.asarray(array). A null cell is 0 or NaN in the data and true in the mask.
+#[pyclass]
+pub struct NumericArray {
+    data: ArrayData,
+    null_mask: Vec<bool>,
+    shape: isize,
+    stride: isize,
+}
+
+impl NumericArray {
+    pub(crate) fn new(data: ArrayData, null_mask: Vec<bool>) -> NumericArray {
+        let shape = match &data {
+            ArrayData::Integer(x) => x.len(),
+            ArrayData::Float(x) => x.len(),
+            ArrayData::Bool(x) => x.len(),
+        } as isize;
+
+        let stride = match &data {
+            ArrayData::Bool(_) => 1,
+            _ => 8,
+        };
+
+        NumericArray {
+            data,
+            null_mask,
+            shape,
+            stride,
+        }
+    }
+
+    pub(crate) fn into_parts(self) -> (ArrayData, Vec<bool>) {
+        (self.data, self.null_mask)
+    }
+
+    // Buffer format of the struct module, q for int64, d for float64 and ? for bool
+    fn format(&self) -> &'static [u8] {
+        match self.data {
+            ArrayData::Integer(_) => b"q\0",
+            ArrayData::Float(_) => b"d\0",
+            ArrayData::Bool(_) => b"?\0",
+        }
+    }
+
+    fn data_pointer(&self) -> *mut c_void {
+        match &self.data {
+            ArrayData::Integer(x) => x.as_ptr() as *mut c_void,
+            ArrayData::Float(x) => x.as_ptr() as *mut c_void,
+            ArrayData::Bool(x) => x.as_ptr() as *mut c_void,
+        }
+    }
+}
+
+#[pymethods]
+impl NumericArray {
+    // int64, float64 or bool
+    #[getter]
+    fn get_dtype(&self) -> &'static str {
+        match self.data {
+            ArrayData::Integer(_) => "int64",
+            ArrayData::Float(_) => "float64",
+            ArrayData::Bool(_) => "bool",
+        }
+    }
+
+    // True for a null cell, e.g. numpy.ma.masked_array(numpy.asarray(array), numpy.asarray(array.mask))
+    #[getter]
+    fn get_mask(&self) -> NumericArray {
+        NumericArray::new(ArrayData::Bool(self.null_mask.clone()), vec![false; self.null_mask.len()])
+    }
+
+    #[getter]
+    fn get_null_count(&self) -> usize {
+        self.null_mask.iter().filter(|&&x| x).count()
+    }
+}
+
+#[pyproto]
+impl PyMappingProtocol for NumericArray {
+    fn __len__(&self) -> usize {
+        self.shape as usize
+    }
+}
+
+#[pyproto]
+impl PyBufferProtocol for NumericArray {
+    fn bf_getbuffer(slf: PyRefMut<Self>, view: *mut ffi::Py_buffer, flags: c_int) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("View is null"));
+        }
+
+        if (flags & ffi::PyBUF_WRITABLE) == ffi::PyBUF_WRITABLE {
+            return Err(PyBufferError::new_err("NumericArray is read only"));
+        }
+
+        // The view keeps a reference to the array, so the data, shape and stride stay in place
+        unsafe {
+            (*view).obj = slf.as_ptr();
+            ffi::Py_INCREF((*view).obj);
+
+            (*view).buf = slf.data_pointer();
+            (*view).len = slf.shape * slf.stride;
+            (*view).readonly = 1;
+            (*view).itemsize = slf.stride;
+
+            (*view).format = ptr::null_mut();
+            if (flags & ffi::PyBUF_FORMAT) == ffi::PyBUF_FORMAT {
+                (*view).format = slf.format().as_ptr() as *mut _;
+            }
+
+            (*view).ndim = 1;
+            (*view).shape = ptr::null_mut();
+            if (flags & ffi::PyBUF_ND) == ffi::PyBUF_ND {
+                (*view).shape = &slf.shape as *const isize as *mut isize;
+            }
+
+            (*view).strides = ptr::null_mut();
+            if (flags & ffi::PyBUF_STRIDES) == ffi::PyBUF_STRIDES {
+                (*view).strides = &slf.stride as *const isize as *mut isize;
+            }
+
+            (*view).suboffsets = ptr::null_mut();
+            (*view).internal = ptr::null_mut();
+        }
+
+        Ok(())
+    }
+
+    fn bf_releasebuffer(_slf: PyRefMut<Self>, _view: *mut ffi::Py_buffer) {}
+}
+
+// An ID such as 00123 or an accounting negative such as (123) would not read back as written, so
+// its column stays text in to_numpy, Arrow and Parquet
+pub(crate) fn is_kept_as_text(cell: &[u8]) -> bool {
+    let digit = match cell {
+        [b'(', .., b')'] => return true,
+        [b'-' | b'+', x @ ..] => x,
+        x => x,
+    };
+
+    matches!(digit, [b'0', x, ..] if x.is_ascii_digit())
+}
+
+// Integer if every cell is empty or a real number without a dot, Float if some have a dot,
+// None if any cell is not a real number or no cell has a value
+pub(crate) fn get_numeric_type(df: &Dataframe, current_column: usize) -> Option<NumericType> {
+    let cell_address = df.cell_address();
+    let cell_per_row = cell_per_row(&df.csv_meta);
+
+    get_cell_numeric_type((0..df.total_row()).map(|current_row| {
+        let (start_byte, end_byte) = cell_range(&df.byte_array, cell_address, current_row * cell_per_row + current_column);
+        &df.byte_array[start_byte..end_byte]
+    }))
+}
+
+// The rule of get_numeric_type for cells taken in another way, e.g. unquoted without null text
+pub(crate) fn get_cell_numeric_type<T: AsRef<[u8]>>(cells: impl Iterator<Item = T>) -> Option<NumericType> {
+    let mut numeric_type = None;
+
+    for cell in cells {
+        let cell = cell.as_ref();
+
+        if cell.is_empty() {
+            continue;
+        }
+
+        if is_kept_as_text(cell) {
+            return None;
+        }
+
+        parse_real_number(cell)?;
+
+        if cell.contains(&b'.') {
+            numeric_type = Some(NumericType::Float);
+        } else if numeric_type.is_none() {
+            numeric_type = Some(NumericType::Integer);
+        }
+    }
+
+    numeric_type
+}
+
+// Same rule as parse_real_number for an integer: 123, -123 or (123) for negative
+fn parse_integer(byte_array: &[u8]) -> Option<i64> {
+    match byte_array {
+        [b'(', x @ .., b')'] => parse_integer(x).map(|x| -x),
+        x => str::from_utf8(x).ok()?.parse::<i64>().ok(),
+    }
+}
+
+// An integer column with a value out of the range of i64 is read as Float
+pub(crate) fn get_numeric_array(df: &Dataframe, current_column: usize, numeric_type: NumericType) -> NumericArray {
+    let cell_address = df.cell_address();
+    let cell_per_row = cell_per_row(&df.csv_meta);
+    let total_row = df.total_row();
+    let mut null_mask = Vec::with_capacity(total_row);
+    let mut integer_value = Vec::new();
+    let mut float_value = Vec::with_capacity(total_row);
+    let mut is_integer = numeric_type == NumericType::Integer;
+
+    for current_row in 0..total_row {
+        let (start_byte, end_byte) = cell_range(&df.byte_array, cell_address, current_row * cell_per_row + current_column);
+        let cell = &df.byte_array[start_byte..end_byte];
+        let value = parse_real_number(cell);
+
+        null_mask.push(value.is_none());
+        float_value.push(value.unwrap_or(f64::NAN));
+
+        if is_integer && value.is_some() {
+            match parse_integer(cell) {
+                Some(x) => integer_value.push(x),
+                None => is_integer = false,
+            }
+        } else if is_integer {
+            integer_value.push(0);
+        }
+    }
+
+    if is_integer {
+        NumericArray::new(ArrayData::Integer(integer_value), null_mask)
+    } else {
+        NumericArray::new(ArrayData::Float(float_value), null_mask)
+    }
+}
+
+// First non-empty cell which is not a number of numeric_type, e.g. for a column type fixed by a sample
+pub(crate) fn get_type_mismatch(df: &Dataframe, current_column: usize, numeric_type: NumericType) -> Option<String> {
+    let cell_address = df.cell_address();
+    let cell_per_row = cell_per_row(&df.csv_meta);
+
+    (0..df.total_row()).find_map(|current_row| {
+        let (start_byte, end_byte) = cell_range(&df.byte_array, cell_address, current_row * cell_per_row + current_column);
+        let cell = &df.byte_array[start_byte..end_byte];
+
+        let is_match = match numeric_type {
+            NumericType::Integer => parse_integer(cell).is_some(),
+            NumericType::Float => parse_real_number(cell).is_some(),
+        };
+
+        match cell.is_empty() || (is_match && !is_kept_as_text(cell)) {
+            true => None,
+            false => Some(String::from_utf8_lossy(cell).into_owned()),
+        }
+    })
+}
+
+// Numeric columns as {column_name: NumericArray}, all numeric columns if columns is not given
+pub(crate) fn to_numeric_array(df: &Dataframe, columns: &Option<Vec<String>>) -> PyResult<HashMap<String, NumericArray>> {
+    let column: Vec<usize> = match columns {
+        Some(columns) => {
+            let mut column = Vec::new();
+
+            for column_name in columns {
+                match df.csv_meta.column_name.iter().position(|x| x == column_name) {
+                    Some(n) => column.push(n),
+                    None => return Err(PyKeyError::new_err(format!("Column {} is not found", column_name))),
+                }
+            }
+
+            column
+        }
+        None => (0..df.csv_meta.total_column as usize).collect(),
+    };
+
+    let mut numeric_array = HashMap::new();
+
+    for current_column in column {
+        let column_name = &df.csv_meta.column_name[current_column];
+
+        match get_numeric_type(df, current_column) {
+            Some(numeric_type) => {
+                numeric_array.insert(column_name.clone(), get_numeric_array(df, current_column, numeric_type));
+            }
+            None if columns.is_some() => {
+                return Err(PyValueError::new_err(format!("Column {} is not numeric", column_name)));
+            }
+            None => {}
+        }
+    }
+
+    Ok(numeric_array)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::test_dataframe;
+
+    #[test]
+    fn numeric_type_follows_parse_real_number() {
+        let df = test_dataframe(b"1.\n.5\n-2\n\n", &["a"]);
+        assert!(get_numeric_type(&df, 0) == Some(NumericType::Float));
+
+        let (data, null_mask) = get_numeric_array(&df, 0, NumericType::Float).into_parts();
+        assert!(matches!(data, ArrayData::Float(x) if x[..3] == [1.0, 0.5, -2.0]));
+        assert_eq!(null_mask, vec![false, false, false, true]);
+
+        assert!(get_numeric_type(&test_dataframe(b"1\n-\n", &["a"]), 0).is_none());
+        assert!(get_numeric_type(&test_dataframe(b"1\n-2\n", &["a"]), 0) == Some(NumericType::Integer));
+    }
+
+    #[test]
+    fn leading_zero_and_bracket_stay_text() {
+        assert!(is_kept_as_text(b"00123"));
+        assert!(is_kept_as_text(b"-0123"));
+        assert!(is_kept_as_text(b"(123)"));
+        assert!(!is_kept_as_text(b"0"));
+        assert!(!is_kept_as_text(b"0.5"));
+        assert!(!is_kept_as_text(b"120"));
+
+        let df = test_dataframe(b"1\n00123\n", &["a"]);
+        assert!(get_numeric_type(&df, 0).is_none());
+        assert!(get_numeric_type(&test_dataframe(b"1\n(2)\n", &["a"]), 0).is_none());
+        assert_eq!(get_type_mismatch(&df, 0, NumericType::Integer), Some("00123".to_string()));
+    }
+}
@@ -0,0 +1,483 @@
+use crate::dataframe::Dataframe;
+use crate::folder_lake::write_folder_lake;
+use crate::partition::{get_header_byte_count, read_partition};
+use crate::split_file::read_record;
+use crate::{cell_address, cell_per_row, cell_range, get_column_id, parse_real_number, read_csv_sample, CsvMeta};
+use pyo3::prelude::*;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::str;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum SortType {
+    Text,
+    Integer,
+    Float,
+}
+
+#[derive(Clone)]
+pub(crate) struct SortKey {
+    pub(crate) column: usize,
+    pub(crate) sort_type: SortType,
+    pub(crate) is_descending: bool,
+    pub(crate) is_null_first: bool,
+}
+
+// Text is kept as a byte range of the row buffer, so sorting never copies cell bytes
+#[derive(Clone, Copy)]
+enum SortValue {
+    Null,
+    Text(usize, usize),
+    Integer(i64),
+    Float(f64),
+}
+
+// e.g. "primary_col(A) secondary_col(floatD)"
+// Sorting order: A, D, intA, intD, floatA, floatD, optionally followed by nulls_first or nulls_last
+pub(crate) fn parse_orderby_setting(setting: &str, csv_meta: &CsvMeta) -> Result<Vec<SortKey>, String> {
+    let mut sort_key = Vec::new();
+    let mut error_message = String::new();
+
+    for item in setting.split(')') {
+        let item = item.trim_matches(|x: char| x == ',' || x.is_whitespace());
+
+        if item.is_empty() {
+            continue;
+        }
+
+        let (column, order) = match item.find('(') {
+            Some(n) => (item[..n].trim(), item[n + 1..].trim()),
+            None => (item, "A"),
+        };
+
+        let column_id = match csv_meta.column_name.iter().position(|x| x == column) {
+            Some(n) => n,
+            None => {
+                error_message.push_str(&format!("** Column {} is not found ** \n", column));
+                continue;
+            }
+        };
+
+        let mut current_key = SortKey {
+            column: column_id,
+            sort_type: SortType::Text,
+            is_descending: false,
+            is_null_first: false,
+        };
+
+        let mut order_token = order.split_whitespace();
+
+        let sort_order = order_token.next().unwrap_or("A");
+
+        match sort_order.to_lowercase().as_str() {
+            "a" => {}
+            "d" => current_key.is_descending = true,
+            "inta" => current_key.sort_type = SortType::Integer,
+            "intd" => {
+                current_key.sort_type = SortType::Integer;
+                current_key.is_descending = true;
+            }
+            "floata" => current_key.sort_type = SortType::Float,
+            "floatd" => {
+                current_key.sort_type = SortType::Float;
+                current_key.is_descending = true;
+            }
+            _ => {
+                error_message.push_str(&format!("** Sorting order {} of column {} is not supported ** \n", sort_order, column));
+            }
+        }
+
+        for option in order_token {
+            match option.to_lowercase().as_str() {
+                "nulls_first" | "nullsfirst" => current_key.is_null_first = true,
+                "nulls_last" | "nullslast" => current_key.is_null_first = false,
+                _ => {
+                    error_message.push_str(&format!("** Sorting option {} of column {} is not supported ** \n", option, column));
+                }
+            }
+        }
+
+        sort_key.push(current_key);
+    }
+
+    if sort_key.is_empty() && error_message.is_empty() {
+        error_message.push_str("** Fail to find any sorting column ** \n");
+    }
+
+    if error_message.is_empty() {
+        Ok(sort_key)
+    } else {
+        Err(error_message)
+    }
+}
+
+fn parse_integer(byte_array: &[u8]) -> Option<i64> {
+    if byte_array.len() > 2 && byte_array[0] == b'(' && byte_array[byte_array.len() - 1] == b')' {
+        return parse_integer(&byte_array[1..byte_array.len() - 1]).map(|x| -x);
+    }
+
+    str::from_utf8(byte_array).ok()?.parse::<i64>().ok()
+}
+
+// Empty cells and cells which cannot be parsed as the requested number type are null
+fn get_sort_value(byte_array: &[u8], cell_address: &[i64], current_cell: usize, sort_type: SortType) -> SortValue {
+    let (start_byte, end_byte) = cell_range(byte_array, cell_address, current_cell);
+
+    if start_byte >= end_byte {
+        return SortValue::Null;
+    }
+
+    let cell = &byte_array[start_byte..end_byte];
+
+    match sort_type {
+        SortType::Text => SortValue::Text(start_byte, end_byte),
+        SortType::Integer => parse_integer(cell).map_or(SortValue::Null, SortValue::Integer),
+        SortType::Float => parse_real_number(cell).map_or(SortValue::Null, SortValue::Float),
+    }
+}
+
+fn compare_sort_value(a_bytes: &[u8], a: &SortValue, b_bytes: &[u8], b: &SortValue, sort_key: &SortKey) -> Ordering {
+    let null_order = if sort_key.is_null_first { Ordering::Less } else { Ordering::Greater };
+
+    let ordering = match (a, b) {
+        (SortValue::Null, SortValue::Null) => return Ordering::Equal,
+        (SortValue::Null, _) => return null_order,
+        (_, SortValue::Null) => return null_order.reverse(),
+        (SortValue::Text(a_start, a_end), SortValue::Text(b_start, b_end)) => {
+            a_bytes[*a_start..*a_end].cmp(&b_bytes[*b_start..*b_end])
+        }
+        (SortValue::Integer(x), SortValue::Integer(y)) => x.cmp(y),
+        (SortValue::Float(x), SortValue::Float(y)) => x.total_cmp(y),
+        _ => Ordering::Equal,
+    };
+
+    if sort_key.is_descending {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+fn compare_row(a_bytes: &[u8], a: &[SortValue], b_bytes: &[u8], b: &[SortValue], sort_key: &[SortKey]) -> Ordering {
+    for (n, current_key) in sort_key.iter().enumerate() {
+        let ordering = compare_sort_value(a_bytes, &a[n], b_bytes, &b[n], current_key);
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    Ordering::Equal
+}
+
+// Stable sort of the row offsets; returns (start_byte, end_byte) of each row in sorted order
+pub(crate) fn sort_row_range(byte_array: &[u8], csv_meta: &CsvMeta, sort_key: &[SortKey]) -> Vec<(usize, usize)> {
+    let cell_address = cell_address(byte_array, csv_meta);
+    let cell_per_row = cell_per_row(csv_meta);
+    let total_row = (cell_address.len() - 1) / cell_per_row;
+    let mut sort_value = Vec::with_capacity(total_row * sort_key.len());
+
+    for current_row in 0..total_row {
+        for current_key in sort_key {
+            let current_cell = current_row * cell_per_row + current_key.column;
+            sort_value.push(get_sort_value(byte_array, &cell_address, current_cell, current_key.sort_type));
+        }
+    }
+
+    let key_count = sort_key.len();
+    let mut row_order: Vec<usize> = (0..total_row).collect();
+
+    row_order.sort_by(|&a, &b| {
+        compare_row(
+            byte_array,
+            &sort_value[a * key_count..(a + 1) * key_count],
+            byte_array,
+            &sort_value[b * key_count..(b + 1) * key_count],
+            sort_key,
+        )
+    });
+
+    row_order
+        .iter()
+        .map(|&current_row| {
+            (
+                cell_address[current_row * cell_per_row] as usize,
+                cell_address[(current_row + 1) * cell_per_row] as usize,
+            )
+        })
+        .collect()
+}
+
+pub(crate) fn sort_byte_array(byte_array: &[u8], csv_meta: &CsvMeta, sort_key: &[SortKey]) -> Vec<u8> {
+    let mut result_bytes = Vec::with_capacity(byte_array.len());
+
+    for (start_byte, end_byte) in sort_row_range(byte_array, csv_meta, sort_key) {
+        result_bytes.extend_from_slice(&byte_array[start_byte..end_byte]);
+    }
+
+    result_bytes
+}
+
+// "secondary_col(sorting order) => create_folder_lake(primary_col, folder_name)" also writes
+// the sorted rows into a folder lake partitioned by primary_col
+fn parse_create_folder_lake(setting: &str, csv_meta: &CsvMeta) -> Result<(Vec<usize>, String), String> {
+    let (function_name, argument) = match (setting.find('('), setting.rfind(')')) {
+        (Some(m), Some(n)) if m < n => (setting[..m].trim(), &setting[m + 1..n]),
+        _ => return Err(format!("** {} should be written as create_folder_lake(primary_col, folder_name) ** \n", setting)),
+    };
+
+    if function_name != "create_folder_lake" {
+        return Err(format!("** {} is not supported after orderby ** \n", function_name));
+    }
+
+    match argument.rfind(',') {
+        Some(n) => Ok((get_column_id(&argument[..n], csv_meta)?, argument[n + 1..].trim().to_string())),
+        None => Err("** Fail to find folder name ** \n".to_string()),
+    }
+}
+
+#[pyfunction]
+pub fn orderby(df: &Dataframe, setting: &str) -> PyResult<Dataframe> {
+    let (sort_setting, folder_lake_setting) = match setting.find("=>") {
+        Some(n) => (&setting[..n], Some(setting[n + 2..].trim())),
+        None => (setting, None),
+    };
+
+    let folder_lake = match folder_lake_setting.map(|x| parse_create_folder_lake(x, &df.csv_meta)).transpose() {
+        Ok(x) => x,
+        Err(error_message) => return Ok(df.with_error(&error_message)),
+    };
+
+    match parse_orderby_setting(sort_setting, &df.csv_meta) {
+        Ok(sort_key) => {
+            let result_bytes = sort_byte_array(&df.byte_array, &df.csv_meta, &sort_key);
+
+            if let Some((partition_column, folder)) = folder_lake {
+                write_folder_lake(&result_bytes, &df.csv_meta, partition_column, &folder, 64)?;
+            }
+
+            Ok(Dataframe::new(result_bytes, df.csv_meta.clone()))
+        }
+        Err(error_message) => Ok(df.with_error(&error_message)),
+    }
+}
+
+// The current row of a sorted run spilled to disk. The heap pops the smallest row,
+// and ties go to the earlier run, which keeps the merge stable
+struct RunRow<'a> {
+    row: Vec<u8>,
+    sort_value: Vec<SortValue>,
+    run: usize,
+    sort_key: &'a [SortKey],
+}
+
+impl Ord for RunRow<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_row(&other.row, &other.sort_value, &self.row, &self.sort_value, self.sort_key)
+            .then_with(|| other.run.cmp(&self.run))
+    }
+}
+
+impl PartialOrd for RunRow<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for RunRow<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for RunRow<'_> {}
+
+// A row may hold a line break inside double quotes, so it is read as a whole record
+fn read_run_row<'a>(
+    reader: &mut BufReader<File>,
+    mut row: Vec<u8>,
+    run: usize,
+    csv_meta: &CsvMeta,
+    sort_key: &'a [SortKey],
+) -> io::Result<Option<RunRow<'a>>> {
+    if read_record(reader, &mut row)? == 0 {
+        return Ok(None);
+    }
+
+    let cell_address = cell_address(&row, csv_meta);
+    let sort_value = sort_key
+        .iter()
+        .map(|current_key| get_sort_value(&row, &cell_address, current_key.column, current_key.sort_type))
+        .collect();
+
+    Ok(Some(RunRow {
+        row,
+        sort_value,
+        run,
+        sort_key,
+    }))
+}
+
+static TEMP_FOLDER_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+// Each call gets its own temp folder, which is removed when dropped, also on an early return
+struct TempFolder(PathBuf);
+
+impl TempFolder {
+    fn new() -> io::Result<TempFolder> {
+        let mut temp_folder = std::env::temp_dir();
+        let count = TEMP_FOLDER_COUNT.fetch_add(1, AtomicOrdering::Relaxed);
+        temp_folder.push(format!("peakrs_orderby_{}_{}", std::process::id(), count));
+        fs::create_dir_all(&temp_folder)?;
+        Ok(TempFolder(temp_folder))
+    }
+}
+
+impl Drop for TempFolder {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+// Sort a whole file. If the data is larger than memory_budget bytes, sorted runs of
+// memory_budget bytes are spilled to a temp folder and then merged into output_file.
+#[pyfunction(memory_budget = "1_073_741_824")]
+pub fn orderby_file(filepath: &str, setting: &str, output_file: &str, memory_budget: i64) -> PyResult<CsvMeta> {
+    let (_, mut csv_meta) = read_csv_sample(filepath, 10)?;
+
+    if !csv_meta.error_message.is_empty() {
+        return Ok(csv_meta);
+    }
+
+    let sort_key = match parse_orderby_setting(setting, &csv_meta) {
+        Ok(sort_key) => sort_key,
+        Err(error_message) => {
+            csv_meta.error_message.push_str(&error_message);
+            return Ok(csv_meta);
+        }
+    };
+
+    let memory_budget = if memory_budget <= 0 { 1_073_741_824 } else { memory_budget };
+
+    let mut file = File::open(filepath)?;
+    let header_byte_count = get_header_byte_count(&mut file);
+    let mut header_bytes = vec![0; header_byte_count as usize];
+
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut header_bytes)?;
+
+    let f = File::create(output_file)?;
+    let mut f = BufWriter::new(f);
+    f.write_all(&header_bytes)?;
+
+    let mut start_byte = header_byte_count;
+    let mut total_row = 0;
+
+    if csv_meta.file_size - header_byte_count <= memory_budget {
+        let (byte_array, _) = read_partition(&mut file, start_byte, memory_budget, csv_meta.file_size, &csv_meta);
+
+        for (start_byte, end_byte) in sort_row_range(&byte_array, &csv_meta, &sort_key) {
+            f.write_all(&byte_array[start_byte..end_byte])?;
+            total_row += 1;
+        }
+    } else {
+        let temp_folder = TempFolder::new()?;
+        let mut run_reader = Vec::new();
+
+        while start_byte < csv_meta.file_size {
+            let (byte_array, consumed_byte_count) =
+                read_partition(&mut file, start_byte, memory_budget, csv_meta.file_size, &csv_meta);
+            start_byte += consumed_byte_count;
+
+            let run_path = temp_folder.0.join(format!("run_{}.csv", run_reader.len()));
+            let mut run_writer = BufWriter::new(File::create(&run_path)?);
+
+            for (start_byte, end_byte) in sort_row_range(&byte_array, &csv_meta, &sort_key) {
+                run_writer.write_all(&byte_array[start_byte..end_byte])?;
+            }
+
+            run_writer.flush()?;
+            run_reader.push(run_path);
+        }
+
+        let mut run_reader = run_reader
+            .iter()
+            .map(|run_path| File::open(run_path).map(BufReader::new))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let mut heap = BinaryHeap::with_capacity(run_reader.len());
+
+        for (run, reader) in run_reader.iter_mut().enumerate() {
+            if let Some(run_row) = read_run_row(reader, Vec::new(), run, &csv_meta, &sort_key)? {
+                heap.push(run_row);
+            }
+        }
+
+        while let Some(run_row) = heap.pop() {
+            f.write_all(&run_row.row)?;
+            total_row += 1;
+
+            if let Some(run_row) = read_run_row(&mut run_reader[run_row.run], run_row.row, run_row.run, &csv_meta, &sort_key)? {
+                heap.push(run_row);
+            }
+        }
+    }
+
+    f.flush()?;
+
+    csv_meta.validate_row = total_row;
+    csv_meta.estimate_row = total_row;
+
+    Ok(csv_meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sort_file(text: &[u8], setting: &str, memory_budget: i64) -> (CsvMeta, Vec<u8>) {
+        let filepath = std::env::temp_dir().join(format!("peakrs_orderby_test_{}_{}.csv", std::process::id(), memory_budget));
+        let output_file = filepath.with_extension("out.csv");
+        fs::write(&filepath, text).unwrap();
+
+        let csv_meta = orderby_file(filepath.to_str().unwrap(), setting, output_file.to_str().unwrap(), memory_budget).unwrap();
+        let result_bytes = fs::read(&output_file).unwrap();
+
+        fs::remove_file(&filepath).unwrap();
+        fs::remove_file(&output_file).unwrap();
+        (csv_meta, result_bytes)
+    }
+
+    #[test]
+    fn spilled_runs_keep_quoted_line_break() {
+        let text = b"id,note\n3,\"c\nline\"\n1,a\n2,\"b, x\"\n5,e\n4,d\n";
+        let (csv_meta, result_bytes) = sort_file(text, "id(intA)", 12);
+        assert_eq!(result_bytes, b"id,note\n1,a\n2,\"b, x\"\n3,\"c\nline\"\n4,d\n5,e\n");
+        assert_eq!(csv_meta.validate_row, 5);
+    }
+
+    #[test]
+    fn merge_is_stable_and_matches_in_memory_sort() {
+        let text = b"k,v\nb,1\na,2\nb,3\na,4\nc,5\na,6\n";
+        let (_, spilled) = sort_file(text, "k(A)", 8);
+        let (_, in_memory) = sort_file(text, "k(A)", 0);
+        assert_eq!(spilled, b"k,v\na,2\na,4\na,6\nb,1\nb,3\nc,5\n");
+        assert_eq!(spilled, in_memory);
+    }
+
+    #[test]
+    fn temp_folder_is_removed_on_drop() {
+        let temp_folder = TempFolder::new().unwrap();
+        let other_folder = TempFolder::new().unwrap();
+        let path = temp_folder.0.clone();
+
+        assert_ne!(path, other_folder.0);
+        assert!(path.is_dir());
+        drop(temp_folder);
+        assert!(!path.exists());
+    }
+}
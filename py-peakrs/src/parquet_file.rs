@@ -0,0 +1,297 @@
+use crate::dataframe::Dataframe;
+use crate::numeric::{get_numeric_array, get_numeric_type, ArrayData};
+use crate::{cell_per_row, cell_range, unquote_cell, write_csv_cell, CsvMeta};
+use parquet::basic::{Compression, GzipLevel, LogicalType, Repetition, Type as PhysicalType, ZstdLevel};
+use parquet::column::writer::ColumnWriter;
+use parquet::data_type::ByteArray;
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::file::writer::SerializedFileWriter;
+use parquet::record::Field;
+use parquet::schema::types::Type;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use std::fs::File;
+use std::sync::Arc;
+
+// Values of a column without its nulls, and the null mask of every row
+enum ColumnData {
+    Integer(Vec<i64>),
+    Float(Vec<f64>),
+    Bool(Vec<bool>),
+    Text(Vec<ByteArray>),
+}
+
+struct ParquetColumn {
+    data: ColumnData,
+    null_mask: Vec<bool>,
+}
+
+fn to_py_error(error: ParquetError) -> PyErr {
+    PyIOError::new_err(error.to_string())
+}
+
+pub(crate) fn get_parquet_compression(compression: &str) -> PyResult<Compression> {
+    match compression {
+        "snappy" => Ok(Compression::SNAPPY),
+        "gzip" => Ok(Compression::GZIP(GzipLevel::default())),
+        "zstd" => Ok(Compression::ZSTD(ZstdLevel::default())),
+        "none" | "uncompressed" => Ok(Compression::UNCOMPRESSED),
+        _ => Err(PyValueError::new_err(format!(
+            "Compression {} is not supported, use snappy, gzip, zstd or none",
+            compression
+        ))),
+    }
+}
+
+// int64 or double by the same rule as to_numpy, so a leading zero or brackets stay text,
+// boolean if every cell is true or false, otherwise utf8. An empty cell is null.
+fn get_parquet_column(df: &Dataframe, current_column: usize) -> ParquetColumn {
+    if let Some(numeric_type) = get_numeric_type(df, current_column) {
+        let (data, null_mask) = get_numeric_array(df, current_column, numeric_type).into_parts();
+        let is_valid = |n: &usize| !null_mask[*n];
+
+        let data = match data {
+            ArrayData::Integer(x) => ColumnData::Integer((0..x.len()).filter(is_valid).map(|n| x[n]).collect()),
+            ArrayData::Float(x) => ColumnData::Float((0..x.len()).filter(is_valid).map(|n| x[n]).collect()),
+            ArrayData::Bool(x) => ColumnData::Bool((0..x.len()).filter(is_valid).map(|n| x[n]).collect()),
+        };
+
+        return ParquetColumn { data, null_mask };
+    }
+
+    let cell_address = df.cell_address();
+    let cell_per_row = cell_per_row(&df.csv_meta);
+    let mut null_mask = Vec::with_capacity(df.total_row());
+    let mut text = Vec::new();
+
+    for current_row in 0..df.total_row() {
+        let (start_byte, end_byte) = cell_range(&df.byte_array, cell_address, current_row * cell_per_row + current_column);
+        let cell = unquote_cell(&df.byte_array[start_byte..end_byte]);
+
+        null_mask.push(cell.is_empty());
+
+        if !cell.is_empty() {
+            text.push(ByteArray::from(cell.to_vec()));
+        }
+    }
+
+    let is_bool = !text.is_empty() && text.iter().all(|x| x.data() == b"true" || x.data() == b"false");
+
+    let data = if is_bool {
+        ColumnData::Bool(text.iter().map(|x| x.data() == b"true").collect())
+    } else {
+        ColumnData::Text(text)
+    };
+
+    ParquetColumn { data, null_mask }
+}
+
+fn get_field_type(column_name: &str, column: &ParquetColumn) -> Result<Type, ParquetError> {
+    let (physical_type, logical_type) = match column.data {
+        ColumnData::Integer(_) => (PhysicalType::INT64, None),
+        ColumnData::Float(_) => (PhysicalType::DOUBLE, None),
+        ColumnData::Bool(_) => (PhysicalType::BOOLEAN, None),
+        ColumnData::Text(_) => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+    };
+
+    Type::primitive_type_builder(column_name, physical_type)
+        .with_repetition(Repetition::OPTIONAL)
+        .with_logical_type(logical_type)
+        .build()
+}
+
+fn write_parquet_file(df: &Dataframe, file_name: &str, compression: Compression, row_group_size: usize) -> Result<(), ParquetError> {
+    let column: Vec<ParquetColumn> = (0..df.csv_meta.column_name.len()).map(|x| get_parquet_column(df, x)).collect();
+    let mut field = Vec::new();
+
+    for (column_name, current_column) in df.csv_meta.column_name.iter().zip(&column) {
+        field.push(Arc::new(get_field_type(column_name, current_column)?));
+    }
+
+    let schema = Type::group_type_builder("schema").with_fields(field).build()?;
+    let property = WriterProperties::builder()
+        .set_compression(compression)
+        .set_max_row_group_size(row_group_size)
+        .build();
+
+    let mut writer = SerializedFileWriter::new(File::create(file_name)?, Arc::new(schema), Arc::new(property))?;
+
+    // Position of the next value of each column, the values exclude nulls
+    let mut value_start = vec![0; column.len()];
+    let mut start_row = 0;
+    let total_row = df.total_row();
+
+    // One row group for an empty dataframe
+    loop {
+        let end_row = (start_row + row_group_size).min(total_row);
+        let mut row_group_writer = writer.next_row_group()?;
+
+        for (current_column, parquet_column) in column.iter().enumerate() {
+            let null_mask = &parquet_column.null_mask[start_row..end_row];
+            let definition_level: Vec<i16> = null_mask.iter().map(|&x| if x { 0 } else { 1 }).collect();
+            let value_count = null_mask.iter().filter(|&&x| !x).count();
+            let value_range = value_start[current_column]..value_start[current_column] + value_count;
+            value_start[current_column] += value_count;
+
+            let mut column_writer = match row_group_writer.next_column()? {
+                Some(x) => x,
+                None => break,
+            };
+
+            match (column_writer.untyped(), &parquet_column.data) {
+                (ColumnWriter::Int64ColumnWriter(x), ColumnData::Integer(value)) => {
+                    x.write_batch(&value[value_range], Some(&definition_level), None)?;
+                }
+                (ColumnWriter::DoubleColumnWriter(x), ColumnData::Float(value)) => {
+                    x.write_batch(&value[value_range], Some(&definition_level), None)?;
+                }
+                (ColumnWriter::BoolColumnWriter(x), ColumnData::Bool(value)) => {
+                    x.write_batch(&value[value_range], Some(&definition_level), None)?;
+                }
+                (ColumnWriter::ByteArrayColumnWriter(x), ColumnData::Text(value)) => {
+                    x.write_batch(&value[value_range], Some(&definition_level), None)?;
+                }
+                _ => return Err(ParquetError::General("Column type does not match its writer".to_string())),
+            }
+
+            column_writer.close()?;
+        }
+
+        row_group_writer.close()?;
+        start_row = end_row;
+
+        if start_row >= total_row {
+            break;
+        }
+    }
+
+    writer.close()?;
+
+    Ok(())
+}
+
+// A double keeps its decimal point, so it is read back as a Float column
+fn field_to_text(field: &Field) -> String {
+    match field {
+        Field::Null => String::new(),
+        Field::Str(x) => x.clone(),
+        Field::Float(x) => float_text(*x as f64),
+        Field::Double(x) => float_text(*x),
+        x => x.to_string(),
+    }
+}
+
+fn float_text(value: f64) -> String {
+    let text = value.to_string();
+
+    if text.contains('.') || !value.is_finite() {
+        text
+    } else {
+        text + ".0"
+    }
+}
+
+// Up to max_row rows of a parquet file as csv rows with a comma delimiter, a null is an empty cell
+pub(crate) fn read_parquet_file(filepath: &str, max_row: Option<usize>) -> Result<(Vec<u8>, CsvMeta), ParquetError> {
+    let reader = SerializedFileReader::new(File::open(filepath)?)?;
+    let file_metadata = reader.metadata().file_metadata();
+    let column_name: Vec<String> = file_metadata.schema_descr().root_schema().get_fields().iter().map(|x| x.name().to_string()).collect();
+    let estimate_row = file_metadata.num_rows();
+    let mut byte_array = Vec::new();
+    let mut total_row = 0;
+
+    for row in reader.get_row_iter(None)? {
+        if max_row.is_some_and(|x| total_row >= x) {
+            break;
+        }
+
+        for (n, (_, field)) in row?.get_column_iter().enumerate() {
+            if n > 0 {
+                byte_array.push(b',');
+            }
+            write_csv_cell(field_to_text(field).as_bytes(), b',', &mut byte_array);
+        }
+
+        byte_array.extend_from_slice(b"\r\n");
+        total_row += 1;
+    }
+
+    let csv_meta = CsvMeta {
+        total_column: column_name.len() as i32,
+        validate_row: total_row as i64,
+        estimate_row,
+        is_line_br_13_exist: true,
+        is_line_br_10_exist: true,
+        column_name,
+        file_size: byte_array.len() as i64,
+        delimiter: 44,
+        ..Default::default()
+    };
+
+    Ok((byte_array, csv_meta))
+}
+
+// Columns are int64, double, boolean or utf8 by their cells, an empty cell is null.
+// A number column with a leading zero such as 00123 or a bracketed negative such as (123) is utf8.
+#[pyfunction(file_name = "\"%Sample.parquet\"", compression = "\"snappy\"", row_group_size = "1_000_000")]
+pub fn write_parquet(df: &Dataframe, file_name: &str, compression: &str, row_group_size: usize) -> PyResult<()> {
+    let compression = get_parquet_compression(compression)?;
+
+    if row_group_size == 0 {
+        return Err(PyValueError::new_err("row_group_size should be larger than 0"));
+    }
+
+    write_parquet_file(df, file_name, compression, row_group_size).map_err(to_py_error)?;
+
+    println!("A file named {} is created.", file_name);
+    println!();
+
+    Ok(())
+}
+
+#[pyfunction]
+pub fn read_parquet(filepath: &str) -> PyResult<Dataframe> {
+    match read_parquet_file(filepath, None) {
+        Ok((byte_array, csv_meta)) => Ok(Dataframe::new(byte_array, csv_meta)),
+        Err(error) => Ok(Dataframe::default().with_error(&format!("** File {} cannot be read as parquet: {} ** \n", filepath, error))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::test_dataframe;
+
+    fn round_trip(df: &Dataframe, name: &str) -> (Vec<u8>, CsvMeta) {
+        let file_name = std::env::temp_dir().join(format!("peakrs_parquet_{}_{}.parquet", std::process::id(), name));
+        let file_name = file_name.to_str().unwrap();
+
+        write_parquet_file(df, file_name, Compression::SNAPPY, 2).unwrap();
+        let result = read_parquet_file(file_name, None).unwrap();
+        std::fs::remove_file(file_name).unwrap();
+        result
+    }
+
+    #[test]
+    fn leading_zero_and_bracket_stay_text() {
+        let df = test_dataframe(b"00123,(12)\n00045,5\n", &["id", "amount"]);
+        let (byte_array, _) = round_trip(&df, "text");
+        assert_eq!(byte_array, b"00123,(12)\r\n00045,5\r\n");
+    }
+
+    #[test]
+    fn every_type_round_trips() {
+        let df = test_dataframe(
+            b"1,1.5,true,\"a,b\"\n2,,false,\n3,-2.25,true,c\n",
+            &["qty", "price", "flag", "name"],
+        );
+        let (byte_array, csv_meta) = round_trip(&df, "type");
+
+        assert_eq!(byte_array, b"1,1.5,true,\"a,b\"\r\n2,,false,\r\n3,-2.25,true,c\r\n");
+        assert_eq!(csv_meta.column_name, vec!["qty", "price", "flag", "name"]);
+        assert_eq!(csv_meta.validate_row, 3);
+        assert_eq!(csv_meta.estimate_row, 3);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::CsvMeta;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+// Byte count of the column name row including its line break
+pub(crate) fn get_header_byte_count(file: &mut File) -> i64 {
+    let mut byte_array = vec![0; 4096];
+    let mut header_byte_count = 0;
+
+    file.seek(SeekFrom::Start(0)).unwrap();
+
+    loop {
+        let read_count = file.read(&mut byte_array).unwrap();
+
+        if read_count == 0 {
+            return header_byte_count;
+        }
+
+        for &byte in &byte_array[..read_count] {
+            header_byte_count += 1;
+
+            if byte == 10 {
+                return header_byte_count;
+            }
+        }
+    }
+}
+
+// Read from start_byte for about partition_size bytes and continue to the next line break
+// outside double quotes, so that every partition holds complete rows. Returns the rows and
+// the number of bytes consumed from the file.
+pub(crate) fn read_partition(file: &mut File, start_byte: i64, partition_size: i64, file_size: i64, csv_meta: &CsvMeta) -> (Vec<u8>, i64) {
+    let mut read_size = partition_size;
+
+    if start_byte + read_size > file_size {
+        read_size = file_size - start_byte;
+    }
+
+    let mut byte_array = vec![0; read_size as usize];
+
+    file.seek(SeekFrom::Start(start_byte as u64)).unwrap();
+    file.read_exact(&mut byte_array).unwrap();
+
+    let mut double_quote_count = byte_array.iter().filter(|&&x| x == 34).count();
+
+    if byte_array.last() != Some(&10) || double_quote_count % 2 == 1 {
+        let mut extra_byte_array = vec![0; 4096];
+
+        'search: loop {
+            let read_count = file.read(&mut extra_byte_array).unwrap();
+
+            if read_count == 0 {
+                break;
+            }
+
+            for &byte in &extra_byte_array[..read_count] {
+                byte_array.push(byte);
+
+                if byte == 34 {
+                    double_quote_count += 1;
+                } else if byte == 10 && double_quote_count % 2 == 0 {
+                    break 'search;
+                }
+            }
+        }
+    }
+
+    let consumed_byte_count = byte_array.len() as i64;
+
+    // Last row of a file may have no line break
+    if !byte_array.is_empty() && byte_array.last() != Some(&10) {
+        if csv_meta.is_line_br_13_exist {
+            byte_array.push(13);
+        }
+        byte_array.push(10);
+    }
+
+    (byte_array, consumed_byte_count)
+}
@@ -0,0 +1,533 @@
+use crate::dataframe::Dataframe;
+use crate::{cell_per_row, cell_range, parse_real_number, unquote_cell, write_csv_cell, CsvMeta};
+use pyo3::class::basic::PyObjectProtocol;
+use pyo3::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{BufWriter, Write};
+
+// 2^12 registers, about 1.6% standard error
+const HLL_PRECISION: u32 = 12;
+
+struct HyperLogLog {
+    register: Vec<u8>,
+}
+
+impl HyperLogLog {
+    fn new() -> HyperLogLog {
+        HyperLogLog {
+            register: vec![0; 1 << HLL_PRECISION],
+        }
+    }
+
+    fn insert(&mut self, byte_array: &[u8]) {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(byte_array);
+        let hash = hasher.finish();
+
+        let n = (hash >> (64 - HLL_PRECISION)) as usize;
+        let rank = ((hash << HLL_PRECISION) | (1 << (HLL_PRECISION - 1))).leading_zeros() as u8 + 1;
+        self.register[n] = self.register[n].max(rank);
+    }
+
+    // Linear counting while many registers are still zero
+    fn estimate(&self) -> i64 {
+        let m = self.register.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self.register.iter().map(|&x| 2f64.powi(-(x as i32))).sum();
+        let zero_count = self.register.iter().filter(|&&x| x == 0).count();
+        let estimate = alpha * m * m / sum;
+
+        if estimate <= 2.5 * m && zero_count > 0 {
+            (m * (m / zero_count as f64).ln()).round() as i64
+        } else {
+            estimate.round() as i64
+        }
+    }
+}
+
+// Misra-Gries summary, the counts are exact while the column has at most capacity distinct values
+struct FrequentValue {
+    count: HashMap<Vec<u8>, i64>,
+    capacity: usize,
+}
+
+impl FrequentValue {
+    fn new(capacity: usize) -> FrequentValue {
+        FrequentValue {
+            count: HashMap::new(),
+            capacity,
+        }
+    }
+
+    fn insert(&mut self, byte_array: &[u8]) {
+        if let Some(count) = self.count.get_mut(byte_array) {
+            *count += 1;
+        } else if self.count.len() < self.capacity {
+            self.count.insert(byte_array.to_vec(), 1);
+        } else {
+            self.count.retain(|_, x| {
+                *x -= 1;
+                *x > 0
+            });
+        }
+    }
+
+    fn top(&self, top_k: usize) -> Vec<(String, i64)> {
+        let mut top: Vec<(String, i64)> = self.count.iter().map(|(x, &n)| (String::from_utf8_lossy(x).to_string(), n)).collect();
+        top.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(top_k);
+        top
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ColumnProfile {
+    name: String,
+    column_type: &'static str,
+    count: i64,
+    null_count: i64,
+    empty_count: i64,
+    distinct_estimate: i64,
+    min: String,
+    max: String,
+    mean: Option<f64>,
+    stddev: Option<f64>,
+    max_length: usize,
+    top: Vec<(String, i64)>,
+    histogram: Vec<(f64, f64, i64)>,
+}
+
+// An empty cell is empty, a cell of null is null, like the null written by add_column
+pub(crate) fn is_null_text(byte_array: &[u8]) -> bool {
+    byte_array.eq_ignore_ascii_case(b"null")
+}
+
+fn get_column_cell(df: &Dataframe, current_column: usize) -> Vec<&[u8]> {
+    let cell_address = df.cell_address();
+    let cell_per_row = cell_per_row(&df.csv_meta);
+
+    (0..df.total_row())
+        .map(|current_row| {
+            let (start_byte, end_byte) = cell_range(&df.byte_array, cell_address, current_row * cell_per_row + current_column);
+            &df.byte_array[start_byte..end_byte]
+        })
+        .collect()
+}
+
+fn profile_column(df: &Dataframe, current_column: usize, top_k: usize, bins: usize) -> ColumnProfile {
+    let cell = get_column_cell(df, current_column);
+    let mut hyper_log_log = HyperLogLog::new();
+    let mut frequent_value = FrequentValue::new((top_k * 100).max(1000));
+    let mut value = Vec::new();
+    let mut null_count = 0;
+    let mut empty_count = 0;
+    let mut max_length = 0;
+    let mut is_numeric = true;
+    let mut is_dot_exist = false;
+    let mut min: Option<Vec<u8>> = None;
+    let mut max: Option<Vec<u8>> = None;
+
+    for &byte_array in &cell {
+        let byte_array: &[u8] = &unquote_cell(byte_array);
+        max_length = max_length.max(String::from_utf8_lossy(byte_array).chars().count());
+
+        if byte_array.is_empty() {
+            empty_count += 1;
+            continue;
+        }
+
+        if is_null_text(byte_array) {
+            null_count += 1;
+            continue;
+        }
+
+        hyper_log_log.insert(byte_array);
+        frequent_value.insert(byte_array);
+
+        if min.as_deref().is_none_or(|x| byte_array < x) {
+            min = Some(byte_array.to_vec());
+        }
+
+        if max.as_deref().is_none_or(|x| byte_array > x) {
+            max = Some(byte_array.to_vec());
+        }
+
+        match parse_real_number(byte_array).filter(|_| is_numeric) {
+            Some(x) => {
+                is_dot_exist |= byte_array.contains(&b'.');
+                value.push(x);
+            }
+            None => is_numeric = false,
+        }
+    }
+
+    let count = cell.len() as i64 - null_count - empty_count;
+    let is_numeric = is_numeric && count > 0;
+
+    let mut column_profile = ColumnProfile {
+        name: df.csv_meta.column_name[current_column].clone(),
+        column_type: if !is_numeric { "text" } else if is_dot_exist { "float" } else { "integer" },
+        count,
+        null_count,
+        empty_count,
+        distinct_estimate: hyper_log_log.estimate().min(count),
+        min: min.map(|x| String::from_utf8_lossy(&x).to_string()).unwrap_or_default(),
+        max: max.map(|x| String::from_utf8_lossy(&x).to_string()).unwrap_or_default(),
+        mean: None,
+        stddev: None,
+        max_length,
+        top: frequent_value.top(top_k),
+        histogram: Vec::new(),
+    };
+
+    if is_numeric {
+        let (min_value, max_value) = value.iter().fold((f64::MAX, f64::MIN), |(a, b), &x| (a.min(x), b.max(x)));
+
+        // Welford's algorithm
+        let mut mean = 0.0;
+        let mut m2 = 0.0;
+
+        for (n, &x) in value.iter().enumerate() {
+            let delta = x - mean;
+            mean += delta / (n + 1) as f64;
+            m2 += delta * (x - mean);
+        }
+
+        column_profile.min = min_value.to_string();
+        column_profile.max = max_value.to_string();
+        column_profile.mean = Some(mean);
+        column_profile.stddev = if value.len() > 1 { Some((m2 / (value.len() - 1) as f64).sqrt()) } else { None };
+        column_profile.histogram = get_histogram(&value, min_value, max_value, bins);
+    }
+
+    column_profile
+}
+
+// bins of equal width from min to max, the last bin includes max
+fn get_histogram(value: &[f64], min_value: f64, max_value: f64, bins: usize) -> Vec<(f64, f64, i64)> {
+    if bins == 0 {
+        return Vec::new();
+    }
+
+    let bins = if min_value == max_value { 1 } else { bins };
+    let width = (max_value - min_value) / bins as f64;
+    let mut count = vec![0i64; bins];
+
+    for &x in value {
+        let n = if width == 0.0 { 0 } else { (((x - min_value) / width) as usize).min(bins - 1) };
+        count[n] += 1;
+    }
+
+    count
+        .into_iter()
+        .enumerate()
+        .map(|(n, x)| {
+            let end = if n == bins - 1 { max_value } else { min_value + width * (n + 1) as f64 };
+            (min_value + width * n as f64, end, x)
+        })
+        .collect()
+}
+
+pub(crate) fn json_text(text: &str) -> String {
+    let mut json = String::from("\"");
+
+    for x in text.chars() {
+        match x {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            x if (x as u32) < 32 => json.push_str(&format!("\\u{:04x}", x as u32)),
+            x => json.push(x),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
+fn json_number(value: Option<f64>) -> String {
+    match value {
+        Some(x) if x.is_finite() => x.to_string(),
+        _ => "null".to_string(),
+    }
+}
+
+fn round_text(value: Option<f64>) -> String {
+    match value {
+        Some(x) => format!("{:.4}", x).trim_end_matches('0').trim_end_matches('.').to_string(),
+        None => String::new(),
+    }
+}
+
+// Rows of text to a dataframe, so a profile can be viewed or written like any other dataframe
+pub(crate) fn text_to_dataframe(column_name: &[&str], row: &[Vec<String>]) -> Dataframe {
+    let mut byte_array = Vec::new();
+
+    for current_row in row {
+        for (n, text) in current_row.iter().enumerate() {
+            if n > 0 {
+                byte_array.push(b',');
+            }
+            write_csv_cell(text.as_bytes(), b',', &mut byte_array);
+        }
+        byte_array.extend_from_slice(b"\r\n");
+    }
+
+    let csv_meta = CsvMeta {
+        total_column: column_name.len() as i32,
+        validate_row: row.len() as i64,
+        estimate_row: row.len() as i64,
+        is_line_br_13_exist: true,
+        is_line_br_10_exist: true,
+        column_name: column_name.iter().map(|x| x.to_string()).collect(),
+        file_size: byte_array.len() as i64,
+        delimiter: 44,
+        ..Default::default()
+    };
+
+    Dataframe::new(byte_array, csv_meta)
+}
+
+// Print rows of text as a table with a column name row
+pub(crate) fn format_table(column_name: &[&str], row: &[Vec<String>]) -> String {
+    let mut width: Vec<usize> = column_name.iter().map(|x| x.chars().count()).collect();
+
+    for current_row in row {
+        for (n, text) in current_row.iter().enumerate() {
+            width[n] = width[n].max(text.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    let column_name: Vec<String> = column_name.iter().map(|x| x.to_string()).collect();
+
+    for current_row in std::iter::once(&column_name).chain(row.iter()) {
+        table.push_str("  ");
+        for (n, text) in current_row.iter().enumerate() {
+            table.push_str(&format!("{:<width$}  ", text, width = width[n]));
+        }
+        table.truncate(table.trim_end().len());
+        table.push('\n');
+    }
+
+    table
+}
+
+// Column statistics of a dataframe: null and empty counts, estimated distinct count, min, max,
+// mean and stddev of numeric columns, the most frequent values and a histogram of numeric columns
+#[pyclass]
+#[derive(Clone)]
+pub struct Profile {
+    row_count: i64,
+    column: Vec<ColumnProfile>,
+}
+
+const SUMMARY_COLUMN: [&str; 13] = [
+    "column", "type", "count", "null", "empty", "distinct", "min", "max", "mean", "stddev", "max_length", "top", "top_count",
+];
+
+const HISTOGRAM_COLUMN: [&str; 4] = ["column", "bin_start", "bin_end", "count"];
+
+impl Profile {
+    // One row per column, with the most frequent value as top
+    fn summary_row(&self) -> Vec<Vec<String>> {
+        self.column
+            .iter()
+            .map(|x| {
+                vec![
+                    x.name.clone(),
+                    x.column_type.to_string(),
+                    x.count.to_string(),
+                    x.null_count.to_string(),
+                    x.empty_count.to_string(),
+                    x.distinct_estimate.to_string(),
+                    x.min.clone(),
+                    x.max.clone(),
+                    round_text(x.mean),
+                    round_text(x.stddev),
+                    x.max_length.to_string(),
+                    x.top.first().map(|x| x.0.clone()).unwrap_or_default(),
+                    x.top.first().map(|x| x.1.to_string()).unwrap_or_default(),
+                ]
+            })
+            .collect()
+    }
+
+    fn histogram_row(&self) -> Vec<Vec<String>> {
+        self.column
+            .iter()
+            .flat_map(|x| {
+                x.histogram
+                    .iter()
+                    .map(move |&(start, end, count)| vec![x.name.clone(), round_text(Some(start)), round_text(Some(end)), count.to_string()])
+            })
+            .collect()
+    }
+}
+
+#[pymethods]
+impl Profile {
+    #[getter]
+    fn get_row_count(&self) -> i64 {
+        self.row_count
+    }
+
+    fn view(&self) {
+        println!();
+        println!("{}", format_table(&SUMMARY_COLUMN, &self.summary_row()));
+
+        for column_profile in &self.column {
+            if column_profile.top.len() > 1 {
+                let top: Vec<String> = column_profile.top.iter().map(|(x, n)| format!("{} ({})", x, n)).collect();
+                println!("  {} top: {}", column_profile.name, top.join(", "));
+            }
+        }
+
+        let histogram_row = self.histogram_row();
+
+        if !histogram_row.is_empty() {
+            println!();
+            println!("{}", format_table(&HISTOGRAM_COLUMN, &histogram_row));
+        }
+    }
+
+    // The summary as a dataframe, one row per column
+    fn to_dataframe(&self) -> Dataframe {
+        text_to_dataframe(&SUMMARY_COLUMN, &self.summary_row())
+    }
+
+    // The histograms as a dataframe, one row per bin
+    fn histogram(&self) -> Dataframe {
+        text_to_dataframe(&HISTOGRAM_COLUMN, &self.histogram_row())
+    }
+
+    fn to_json(&self) -> String {
+        let mut column = Vec::new();
+
+        for x in &self.column {
+            let top: Vec<String> = x
+                .top
+                .iter()
+                .map(|(value, count)| format!("{{\"value\": {}, \"count\": {}}}", json_text(value), count))
+                .collect();
+
+            let histogram: Vec<String> = x
+                .histogram
+                .iter()
+                .map(|&(start, end, count)| {
+                    format!("{{\"start\": {}, \"end\": {}, \"count\": {}}}", json_number(Some(start)), json_number(Some(end)), count)
+                })
+                .collect();
+
+            let (min, max) = if x.column_type == "text" {
+                (json_text(&x.min), json_text(&x.max))
+            } else {
+                (x.min.clone(), x.max.clone())
+            };
+
+            let (min, max) = if x.count == 0 { ("null".to_string(), "null".to_string()) } else { (min, max) };
+
+            column.push(format!(
+                "{{\"name\": {}, \"type\": \"{}\", \"count\": {}, \"null_count\": {}, \"empty_count\": {}, \"distinct_estimate\": {}, \
+                 \"min\": {}, \"max\": {}, \"mean\": {}, \"stddev\": {}, \"max_length\": {}, \"top\": [{}], \"histogram\": [{}]}}",
+                json_text(&x.name),
+                x.column_type,
+                x.count,
+                x.null_count,
+                x.empty_count,
+                x.distinct_estimate,
+                min,
+                max,
+                json_number(x.mean),
+                json_number(x.stddev),
+                x.max_length,
+                top.join(", "),
+                histogram.join(", ")
+            ));
+        }
+
+        format!("{{\"row_count\": {}, \"column\": [\n  {}\n]}}\n", self.row_count, column.join(",\n  "))
+    }
+
+    fn write_json(&self, file_name: &str) -> PyResult<()> {
+        let mut f = BufWriter::new(File::create(file_name)?);
+        f.write_all(self.to_json().as_bytes())?;
+        f.flush()?;
+        Ok(())
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for Profile {
+    fn __str__(&self) -> String {
+        format_table(&SUMMARY_COLUMN, &self.summary_row())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Profile(row={}, column=[{}])",
+            self.row_count,
+            self.column.iter().map(|x| x.name.clone()).collect::<Vec<String>>().join(", ")
+        )
+    }
+}
+
+// Profile every column, with top_k frequent values and a histogram of bins for numeric columns
+#[pyfunction(top_k = "5", bins = "10")]
+pub fn profile(df: &Dataframe, top_k: usize, bins: usize) -> Profile {
+    Profile {
+        row_count: df.total_row() as i64,
+        column: (0..df.csv_meta.total_column as usize).map(|x| profile_column(df, x, top_k, bins)).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::test_dataframe;
+
+    #[test]
+    fn histogram_bins() {
+        assert!(get_histogram(&[1.0, 2.0], 1.0, 2.0, 0).is_empty());
+        assert_eq!(get_histogram(&[5.0, 5.0], 5.0, 5.0, 10), vec![(5.0, 5.0, 2)]);
+        assert_eq!(get_histogram(&[0.0, 1.0, 2.0, 3.0, 4.0], 0.0, 4.0, 2), vec![(0.0, 2.0, 2), (2.0, 4.0, 3)]);
+    }
+
+    #[test]
+    fn distinct_estimate_of_small_cardinality() {
+        assert_eq!(HyperLogLog::new().estimate(), 0);
+
+        for n in [1, 10, 100, 1000] {
+            let mut hyper_log_log = HyperLogLog::new();
+
+            // Each value twice, which should not change the estimate
+            for x in (0..n).chain(0..n) {
+                hyper_log_log.insert(x.to_string().as_bytes());
+            }
+
+            let estimate = hyper_log_log.estimate();
+            assert!((estimate - n).abs() <= n / 20 + 1, "{} distinct values estimated as {}", n, estimate);
+        }
+    }
+
+    #[test]
+    fn null_and_empty_are_not_counted() {
+        let df = test_dataframe(b"1,a\nnull,\nNULL,b\n,a\n\"3\",\n2,c\n", &["n", "t"]);
+
+        let n = profile_column(&df, 0, 3, 10);
+        assert_eq!((n.count, n.null_count, n.empty_count, n.distinct_estimate), (3, 2, 1, 3));
+        assert_eq!((n.column_type, n.min.as_str(), n.max.as_str()), ("integer", "1", "3"));
+        assert_eq!(n.mean, Some(2.0));
+
+        let t = profile_column(&df, 1, 1, 10);
+        assert_eq!((t.count, t.null_count, t.empty_count), (4, 0, 2));
+        assert_eq!(t.column_type, "text");
+        assert_eq!(t.top, vec![("a".to_string(), 2)]);
+        assert!(t.histogram.is_empty());
+    }
+}
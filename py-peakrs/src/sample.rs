@@ -0,0 +1,361 @@
+use crate::compression::open_decoder;
+use crate::split_file::read_record;
+use crate::{cell_address, cell_range, read_any_csv_sample, CsvMeta};
+use pyo3::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufReader, Read};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// SplitMix64, enough for a repeatable sample or shuffle for a seed, seeded by the clock if no seed
+pub(crate) struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: Option<u64>) -> SplitMix64 {
+        let seed = seed.unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_nanos() as u64).unwrap_or(0));
+        SplitMix64 { state: seed }
+    }
+
+    pub(crate) fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // 0 to n - 1
+    pub(crate) fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    pub(crate) fn shuffle<T>(&mut self, item: &mut [T]) {
+        for n in (1..item.len()).rev() {
+            item.swap(n, self.below(n as u64 + 1) as usize);
+        }
+    }
+}
+
+// (row number, record) of the sampled rows
+type SampleRow = Vec<(i64, Vec<u8>)>;
+
+// Reservoir of up to capacity rows, every row seen so far is kept with the same probability
+struct Reservoir {
+    row: SampleRow,
+    seen_row: i64,
+}
+
+impl Reservoir {
+    fn new() -> Reservoir {
+        Reservoir {
+            row: Vec::new(),
+            seen_row: 0,
+        }
+    }
+
+    fn push(&mut self, current_row: i64, record: &[u8], capacity: usize, random: &mut SplitMix64) {
+        self.seen_row += 1;
+
+        if self.row.len() < capacity {
+            self.row.push((current_row, record.to_vec()));
+        } else {
+            let n = random.below(self.seen_row as u64) as usize;
+            if n < capacity {
+                self.row[n] = (current_row, record.to_vec());
+            }
+        }
+    }
+}
+
+// A csv file, or a csv in memory such as bytes or the standard input
+pub(crate) enum RecordSource<'a> {
+    File(&'a str),
+    Buffer(&'a [u8]),
+}
+
+impl<'a> RecordSource<'a> {
+    fn open(&self) -> std::io::Result<Box<dyn Read + 'a>> {
+        match self {
+            RecordSource::File(filepath) => open_decoder(filepath),
+            RecordSource::Buffer(data) => Ok(Box::new(*data)),
+        }
+    }
+}
+
+// Rows after the header, a compressed file is decompressed while it is read
+struct RecordReader<'a> {
+    reader: BufReader<Box<dyn Read + 'a>>,
+    record: Vec<u8>,
+}
+
+impl<'a> RecordReader<'a> {
+    fn new(source: &RecordSource<'a>) -> std::io::Result<RecordReader<'a>> {
+        let mut reader = BufReader::with_capacity(1 << 20, source.open()?);
+        let mut record = Vec::new();
+        read_record(&mut reader, &mut record)?;
+
+        Ok(RecordReader { reader, record })
+    }
+
+    fn next(&mut self) -> std::io::Result<Option<&[u8]>> {
+        match read_record(&mut self.reader, &mut self.record)? {
+            0 => Ok(None),
+            _ => Ok(Some(&self.record)),
+        }
+    }
+}
+
+// Sample rows in file order, with every row ending with a line break
+fn sample_to_byte_array(mut row: SampleRow, csv_meta: &CsvMeta) -> Vec<u8> {
+    row.sort_unstable_by_key(|x| x.0);
+
+    let mut byte_array = Vec::new();
+
+    for (_, record) in row {
+        byte_array.extend_from_slice(&record);
+
+        if record.last() != Some(&10) {
+            if csv_meta.is_line_br_13_exist {
+                byte_array.push(13);
+            }
+            byte_array.push(10);
+        }
+    }
+
+    byte_array
+}
+
+// Every row has the same chance, by counting the rows and then reading the drawn row numbers
+fn uniform_sample(source: &RecordSource, sample_row: usize, random: &mut SplitMix64) -> std::io::Result<(SampleRow, i64)> {
+    let mut record_reader = RecordReader::new(source)?;
+    let mut total_row = 0;
+
+    while record_reader.next()?.is_some() {
+        total_row += 1;
+    }
+
+    // Floyd's algorithm draws sample_row distinct row numbers
+    let mut selected_row = HashSet::new();
+
+    for n in (total_row - sample_row.min(total_row as usize) as i64)..total_row {
+        let current_row = random.below(n as u64 + 1) as i64;
+
+        if !selected_row.insert(current_row) {
+            selected_row.insert(n);
+        }
+    }
+
+    let mut record_reader = RecordReader::new(source)?;
+    let mut row = Vec::new();
+    let mut current_row = 0;
+
+    while let Some(record) = record_reader.next()? {
+        if selected_row.contains(&current_row) {
+            row.push((current_row, record.to_vec()));
+        }
+        current_row += 1;
+    }
+
+    Ok((row, total_row))
+}
+
+// One scan keeping a reservoir of sample_row rows
+fn reservoir_sample(source: &RecordSource, sample_row: usize, random: &mut SplitMix64) -> std::io::Result<(SampleRow, i64)> {
+    let mut record_reader = RecordReader::new(source)?;
+    let mut reservoir = Reservoir::new();
+
+    while let Some(record) = record_reader.next()? {
+        reservoir.push(reservoir.seen_row, record, sample_row, random);
+    }
+
+    Ok((reservoir.row, reservoir.seen_row))
+}
+
+// One scan keeping a reservoir for each key, then each key takes its share of sample_row by its
+// row count, at least one row. The sample has more than sample_row rows only if there are more
+// keys than sample_row.
+fn stratified_sample(
+    source: &RecordSource,
+    sample_row: usize,
+    key_column: usize,
+    csv_meta: &CsvMeta,
+    random: &mut SplitMix64,
+) -> std::io::Result<(SampleRow, i64)> {
+    let mut record_reader = RecordReader::new(source)?;
+    let mut reservoir: HashMap<Vec<u8>, Reservoir> = HashMap::new();
+    let mut total_row = 0;
+
+    while let Some(record) = record_reader.next()? {
+        let address = cell_address(record, csv_meta);
+
+        let key = if key_column + 1 < address.len() {
+            let (start_byte, end_byte) = cell_range(record, &address, key_column);
+            record[start_byte..end_byte].to_vec()
+        } else {
+            Vec::new()
+        };
+
+        reservoir.entry(key).or_insert_with(Reservoir::new).push(total_row, record, sample_row, random);
+        total_row += 1;
+    }
+
+    // In key order, so the same seed gives the same sample
+    let mut reservoir: Vec<(Vec<u8>, Reservoir)> = reservoir.into_iter().collect();
+    reservoir.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+    let mut share: Vec<usize> = reservoir
+        .iter()
+        .map(|(_, x)| ((sample_row as f64 * x.seen_row as f64 / total_row as f64).round() as usize).max(1))
+        .collect();
+
+    // Rounding up and the one row of a small key can go over sample_row, then the largest shares give
+    // back a row each
+    let mut extra_row = share.iter().sum::<usize>().saturating_sub(sample_row);
+
+    while extra_row > 0 {
+        match (0..share.len()).filter(|&n| share[n] > 1).max_by_key(|&n| share[n]) {
+            Some(n) => share[n] -= 1,
+            None => break,
+        }
+        extra_row -= 1;
+    }
+
+    let mut row = Vec::new();
+
+    for ((_, mut current_reservoir), share) in reservoir.into_iter().zip(share) {
+        random.shuffle(&mut current_reservoir.row);
+        row.extend(current_reservoir.row.into_iter().take(share));
+    }
+
+    Ok((row, total_row))
+}
+
+// Sample by mode: even takes the first row after evenly spaced byte offsets, uniform draws rows
+// with the same chance, reservoir draws rows in one scan, stratified draws rows of every key_column value
+pub(crate) fn read_csv_sample_by_mode(
+    filepath: &str,
+    sample_row: i32,
+    mode: &str,
+    seed: Option<u64>,
+    key_column: &str,
+) -> PyResult<(Vec<u8>, CsvMeta)> {
+    if mode == "even" {
+        return read_any_csv_sample(filepath, sample_row);
+    }
+
+    let (_, csv_meta) = read_any_csv_sample(filepath, 10)?;
+    sample_by_mode(&RecordSource::File(filepath), csv_meta, sample_row, mode, seed, key_column)
+}
+
+// Modes other than even, with csv_meta of the source
+pub(crate) fn sample_by_mode(
+    source: &RecordSource,
+    mut csv_meta: CsvMeta,
+    sample_row: i32,
+    mode: &str,
+    seed: Option<u64>,
+    key_column: &str,
+) -> PyResult<(Vec<u8>, CsvMeta)> {
+    if !csv_meta.error_message.is_empty() {
+        return Ok((Vec::new(), csv_meta));
+    }
+
+    let sample_row = if sample_row <= 0 { 10 } else { sample_row as usize };
+    let mut random = SplitMix64::new(seed);
+
+    let (row, total_row) = match mode {
+        "uniform" => uniform_sample(source, sample_row, &mut random)?,
+        "reservoir" => reservoir_sample(source, sample_row, &mut random)?,
+        "stratified" if key_column.is_empty() => {
+            csv_meta.error_message.push_str("** Stratified sampling needs a key_column ** \n");
+            return Ok((Vec::new(), csv_meta));
+        }
+        "stratified" => match csv_meta.column_name.iter().position(|x| x == key_column) {
+            Some(n) => stratified_sample(source, sample_row, n, &csv_meta, &mut random)?,
+            None => {
+                csv_meta.error_message.push_str(&format!("** Key column {} is not found ** \n", key_column));
+                return Ok((Vec::new(), csv_meta));
+            }
+        },
+        _ => {
+            csv_meta.error_message.push_str(&format!(
+                "** Sampling mode {} is not supported, use even, uniform, reservoir or stratified ** \n",
+                mode
+            ));
+            return Ok((Vec::new(), csv_meta));
+        }
+    };
+
+    csv_meta.validate_row = row.len() as i64;
+    csv_meta.estimate_row = total_row;
+
+    Ok((sample_to_byte_array(row, &csv_meta), csv_meta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::test_csv_meta;
+
+    // key of each row: a for 90 rows, b for 9 rows and c for 1 row
+    fn data() -> Vec<u8> {
+        let mut data = b"k,v\n".to_vec();
+
+        for n in 0..100 {
+            let key = match n {
+                0..=89 => "a",
+                90..=98 => "b",
+                _ => "c",
+            };
+            data.extend_from_slice(format!("{},{}\n", key, n).as_bytes());
+        }
+
+        data
+    }
+
+    fn sample(data: &[u8], sample_row: i32, mode: &str, seed: u64) -> (Vec<u8>, CsvMeta) {
+        sample_by_mode(&RecordSource::Buffer(data), test_csv_meta(&["k", "v"]), sample_row, mode, Some(seed), "k").unwrap()
+    }
+
+    fn row_number(byte_array: &[u8]) -> Vec<i64> {
+        String::from_utf8_lossy(byte_array).lines().map(|x| x[2..].parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn uniform_and_reservoir_are_repeatable_for_a_seed() {
+        let data = data();
+
+        for mode in ["uniform", "reservoir"] {
+            let (byte_array, csv_meta) = sample(&data, 10, mode, 7);
+            let row = row_number(&byte_array);
+
+            assert_eq!(csv_meta.validate_row, 10);
+            assert_eq!(csv_meta.estimate_row, 100);
+            assert!(row.windows(2).all(|x| x[0] < x[1]), "{} rows are distinct and in file order", mode);
+            assert_eq!(sample(&data, 10, mode, 7).0, byte_array);
+            assert_ne!(sample(&data, 10, mode, 8).0, byte_array);
+
+            let (byte_array, csv_meta) = sample(&data, 500, mode, 7);
+            assert_eq!(csv_meta.validate_row, 100);
+            assert_eq!(row_number(&byte_array), (0..100).collect::<Vec<i64>>());
+        }
+    }
+
+    #[test]
+    fn stratified_keeps_every_key_within_sample_row() {
+        let data = data();
+        let (byte_array, csv_meta) = sample(&data, 10, "stratified", 7);
+        let key: Vec<char> = String::from_utf8_lossy(&byte_array).lines().map(|x| x.chars().next().unwrap()).collect();
+
+        // a takes 9, b 0.9 and c 0.1 rounded up to 1, then a gives back one row
+        assert_eq!(csv_meta.validate_row, 10);
+        assert_eq!(key.iter().filter(|&&x| x == 'a').count(), 8);
+        assert_eq!(key.iter().filter(|&&x| x == 'b').count(), 1);
+        assert_eq!(key.iter().filter(|&&x| x == 'c').count(), 1);
+        assert_eq!(sample(&data, 10, "stratified", 7).0, byte_array);
+
+        // More keys than sample_row, one row of each
+        assert_eq!(sample(&data, 2, "stratified", 7).1.validate_row, 3);
+    }
+}
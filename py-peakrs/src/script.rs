@@ -0,0 +1,495 @@
+use crate::add_column::add_column;
+use crate::dataframe::Dataframe;
+use crate::filter::{filter, filter_unmatch};
+use crate::folder_lake::{create_folder_lake, parse_folder_lake_setting, read_folder_lake};
+use crate::groupby::groupby;
+use crate::jointable::jointable;
+use crate::jsonl::{get_jsonl_sample, read_jsonl, write_jsonl_file};
+use crate::keyvalue::{build_keyvalue, join_keyvalue, restore_keyvalue_registry, save_keyvalue_registry};
+use crate::orderby::orderby;
+use crate::parquet_file::{get_parquet_compression, read_parquet_file, write_parquet};
+use crate::select::{distinct, select, select_unmatch};
+use crate::source::FilePath;
+use crate::split_file::{manifest_to_dataframe, split_file};
+use crate::{get_csv_sample, read_csv, view_csv, write_csv};
+use pyo3::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+
+#[derive(Clone, Debug)]
+enum Value {
+    Text(String),
+    Number(i64),
+    Variable(String),
+}
+
+// One line of a script, e.g. summary = groupby(df, "region => count() sum(amount)")
+struct ScriptStep {
+    line: usize,
+    output: String,
+    command: String,
+    argument: HashMap<&'static str, Value>,
+}
+
+// Parameter names of each command, of which the first required_count must be given
+fn get_parameter(command: &str) -> Option<(&'static [&'static str], usize)> {
+    let parameter: (&'static [&'static str], usize) = match command {
+        "read_csv" => (&["file_path"], 1),
+        "get_csv_sample" => (&["file_path", "sample_row", "mode", "seed", "key_column"], 1),
+        "read_jsonl" => (&["file_path"], 1),
+        "get_jsonl_sample" => (&["file_path", "sample_row"], 1),
+        "read_parquet" => (&["file_path"], 1),
+        "read_folder_lake" => (&["folder", "setting"], 1),
+        "filter" | "filter_unmatch" | "select" | "select_unmatch" | "distinct" | "groupby" | "orderby"
+        | "build_keyvalue" | "join_keyvalue" => (&["df", "setting"], 2),
+        "add_column" => (&["df", "setting", "decimal_place", "divide_by_zero"], 2),
+        "jointable" => (&["df", "right_df", "setting", "suffix"], 3),
+        "create_folder_lake" => (&["df", "setting", "max_open_file"], 2),
+        "write_csv" | "write_jsonl" => (&["df", "file_name"], 1),
+        "write_parquet" => (&["df", "file_name", "compression", "row_group_size"], 1),
+        "view_csv" | "view" => (&["df"], 1),
+        "split_file" => (&["file_path", "number_of_split", "max_bytes", "output_folder"], 1),
+        _ => return None,
+    };
+
+    Some(parameter)
+}
+
+fn is_variable_name(text: &str) -> bool {
+    !text.is_empty()
+        && !text.as_bytes()[0].is_ascii_digit()
+        && text.bytes().all(|x| x.is_ascii_alphanumeric() || x == b'_')
+}
+
+// Split by comma outside double or single quotes
+fn split_argument(text: &str) -> Result<Vec<&str>, String> {
+    let mut argument = Vec::new();
+    let mut quote = None;
+    let mut start_byte = 0;
+
+    for (i, x) in text.char_indices() {
+        match (quote, x) {
+            (None, '"' | '\'') => quote = Some(x),
+            (Some(q), _) if q == x => quote = None,
+            (None, ',') => {
+                argument.push(text[start_byte..i].trim());
+                start_byte = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if quote.is_some() {
+        return Err("** Missing closing quote ** \n".to_string());
+    }
+
+    let last = text[start_byte..].trim();
+
+    if !last.is_empty() || !argument.is_empty() {
+        argument.push(last);
+    }
+
+    Ok(argument)
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    let bytes = text.as_bytes();
+
+    if bytes.len() >= 2 && (bytes[0] == b'"' || bytes[0] == b'\'') && bytes[bytes.len() - 1] == bytes[0] {
+        Ok(Value::Text(text[1..text.len() - 1].to_string()))
+    } else if let Ok(x) = text.replace('_', "").parse::<i64>() {
+        Ok(Value::Number(x))
+    } else if is_variable_name(text) {
+        Ok(Value::Variable(text.to_string()))
+    } else {
+        Err(format!("** {} is not a quoted text, a number or a dataframe name ** \n", text))
+    }
+}
+
+// e.g. df = pr.read_csv("data.csv") or write_csv(df, file_name = "result.csv")
+fn parse_script_line(text: &str, line: usize) -> Result<ScriptStep, String> {
+    let (left, right) = match (text.find('('), text.ends_with(')')) {
+        (Some(n), true) => (&text[..n], &text[n + 1..text.len() - 1]),
+        _ => return Err(format!("** {} should be written as df = command(argument) ** \n", text)),
+    };
+
+    let (output, command) = match left.find('=') {
+        Some(n) => (left[..n].trim(), left[n + 1..].trim()),
+        None => ("", left.trim()),
+    };
+
+    let command = command.strip_prefix("pr.").unwrap_or(command).trim();
+
+    if !output.is_empty() && !is_variable_name(output) {
+        return Err(format!("** {} is not a valid dataframe name ** \n", output));
+    }
+
+    let (parameter, required_count) = match get_parameter(command) {
+        Some(x) => x,
+        None => return Err(format!("** Command {} is not supported ** \n", command)),
+    };
+
+    if !output.is_empty() && matches!(command, "write_csv" | "write_jsonl" | "write_parquet" | "view_csv" | "view") {
+        return Err(format!("** {} does not return a dataframe ** \n", command));
+    }
+
+    let mut argument = HashMap::new();
+    let mut is_named = false;
+
+    for (n, item) in split_argument(right)?.into_iter().enumerate() {
+        let named = item.find('=').filter(|&x| is_variable_name(item[..x].trim()));
+
+        let (name, value) = match named {
+            Some(x) => match parameter.iter().find(|&&y| y == item[..x].trim()) {
+                Some(&name) => {
+                    is_named = true;
+                    (name, parse_value(item[x + 1..].trim())?)
+                }
+                None => return Err(format!("** {} has no argument named {} ** \n", command, item[..x].trim())),
+            },
+            None if is_named => {
+                return Err(format!("** Positional argument {} follows a named argument ** \n", item));
+            }
+            None => match parameter.get(n) {
+                Some(&name) => (name, parse_value(item)?),
+                None => return Err(format!("** {} takes at most {} arguments ** \n", command, parameter.len())),
+            },
+        };
+
+        if argument.insert(name, value).is_some() {
+            return Err(format!("** Argument {} of {} is given twice ** \n", name, command));
+        }
+    }
+
+    for name in &parameter[..required_count] {
+        if !argument.contains_key(name) {
+            return Err(format!("** Argument {} of {} is missing ** \n", name, command));
+        }
+    }
+
+    Ok(ScriptStep {
+        line,
+        output: output.to_string(),
+        command: command.to_string(),
+        argument,
+    })
+}
+
+// One command per line, lines starting with # are comments
+fn parse_script(script: &str) -> (Vec<ScriptStep>, Vec<String>) {
+    let mut script_step = Vec::new();
+    let mut error_message = Vec::new();
+
+    for (n, text) in script.lines().enumerate() {
+        let text = text.trim();
+
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+
+        match parse_script_line(text, n + 1) {
+            Ok(x) => script_step.push(x),
+            Err(x) => error_message.push(format!("line {}: {}", n + 1, x.trim_end())),
+        }
+    }
+
+    (script_step, error_message)
+}
+
+struct ScriptRunner {
+    dataframe: HashMap<String, Dataframe>,
+    failed_dataframe: HashSet<String>,
+    is_dry_run: bool,
+    stdin: Vec<u8>,
+}
+
+impl ScriptRunner {
+    fn text<'a>(&self, step: &'a ScriptStep, name: &str, default: &'a str) -> Result<&'a str, String> {
+        match step.argument.get(name) {
+            Some(Value::Text(x)) => Ok(x),
+            Some(_) => Err(format!("** Argument {} of {} should be a quoted text ** \n", name, step.command)),
+            None => Ok(default),
+        }
+    }
+
+    fn number(&self, step: &ScriptStep, name: &str, default: i64) -> Result<i64, String> {
+        match step.argument.get(name) {
+            Some(Value::Number(x)) => Ok(*x),
+            Some(_) => Err(format!("** Argument {} of {} should be a number ** \n", name, step.command)),
+            None => Ok(default),
+        }
+    }
+
+    // A path or a glob of csv files, or "-" for the standard input read before the dry run
+    fn file_path(&self, step: &ScriptStep) -> Result<FilePath, String> {
+        match FilePath::from_text(self.text(step, "file_path", "")?) {
+            FilePath::Stdin => Ok(FilePath::Buffer(self.stdin.clone())),
+            x => Ok(x),
+        }
+    }
+
+    fn dataframe(&self, step: &ScriptStep, name: &str) -> Result<&Dataframe, String> {
+        match step.argument.get(name) {
+            Some(Value::Variable(x)) => match self.dataframe.get(x) {
+                Some(dataframe) => Ok(dataframe),
+                None => Err(format!("** Dataframe {} is not defined ** \n", x)),
+            },
+            _ => Err(format!("** Argument {} of {} should be a dataframe name ** \n", name, step.command)),
+        }
+    }
+
+    // Commands reading files still read their sample and column names in a dry run, while commands
+    // writing files are skipped, so that every column reference is checked without touching the output.
+    fn run_step(&self, step: &ScriptStep) -> Result<Option<Dataframe>, String> {
+        let to_error = |x: PyErr| format!("** {} ** \n", x);
+
+        let result = match step.command.as_str() {
+            "read_csv" if self.is_dry_run => {
+                let file_path = self.file_path(step)?;
+                Python::with_gil(|py| get_csv_sample(py, file_path, 10, "even", None, ""))
+            }
+            "read_csv" => {
+                let file_path = self.file_path(step)?;
+                Python::with_gil(|py| read_csv(py, file_path))
+            }
+            "get_csv_sample" => {
+                let file_path = self.file_path(step)?;
+                let sample_row = self.number(step, "sample_row", 10)? as i32;
+                let mode = self.text(step, "mode", "even")?;
+                let seed = Some(self.number(step, "seed", -1)?).filter(|&x| x >= 0).map(|x| x as u64);
+                let key_column = self.text(step, "key_column", "")?;
+                Python::with_gil(|py| get_csv_sample(py, file_path, sample_row, mode, seed, key_column))
+            }
+            "read_jsonl" if self.is_dry_run => get_jsonl_sample(self.text(step, "file_path", "")?, 10),
+            "read_jsonl" => read_jsonl(self.text(step, "file_path", "")?),
+            "get_jsonl_sample" => get_jsonl_sample(self.text(step, "file_path", "")?, self.number(step, "sample_row", 10)? as i32),
+            "read_parquet" => {
+                let file_path = self.text(step, "file_path", "")?;
+
+                // A dry run reads the column names and 10 rows
+                match read_parquet_file(file_path, if self.is_dry_run { Some(10) } else { None }) {
+                    Ok((byte_array, csv_meta)) => Ok(Dataframe::new(byte_array, csv_meta)),
+                    Err(error) => return Err(format!("** File {} cannot be read as parquet: {} ** \n", file_path, error)),
+                }
+            }
+            "read_folder_lake" => read_folder_lake(self.text(step, "folder", "")?, self.text(step, "setting", "")?),
+            "split_file" if self.is_dry_run => return Ok(None),
+            "split_file" => split_file(
+                self.text(step, "file_path", "")?,
+                self.number(step, "number_of_split", 0)?,
+                self.number(step, "max_bytes", 0)?,
+                self.text(step, "output_folder", "")?,
+            ),
+            _ => {
+                let df = self.dataframe(step, "df")?;
+
+                match step.command.as_str() {
+                    "filter" => filter(df, self.text(step, "setting", "")?),
+                    "filter_unmatch" => filter_unmatch(df, self.text(step, "setting", "")?),
+                    "select" => select(df, self.text(step, "setting", "")?),
+                    "select_unmatch" => select_unmatch(df, self.text(step, "setting", "")?),
+                    "distinct" => distinct(df, self.text(step, "setting", "")?),
+                    "groupby" => groupby(df, self.text(step, "setting", "")?),
+                    "orderby" => orderby(df, self.text(step, "setting", "")?),
+                    "build_keyvalue" => build_keyvalue(df, self.text(step, "setting", "")?),
+                    "join_keyvalue" => join_keyvalue(df, self.text(step, "setting", "")?),
+                    "add_column" => add_column(
+                        df,
+                        self.text(step, "setting", "")?,
+                        self.number(step, "decimal_place", -1)? as i32,
+                        self.text(step, "divide_by_zero", "null")?,
+                    ),
+                    "jointable" => jointable(
+                        df,
+                        self.dataframe(step, "right_df")?,
+                        self.text(step, "setting", "")?,
+                        self.text(step, "suffix", "")?,
+                    ),
+                    "create_folder_lake" if self.is_dry_run => {
+                        let (byte_array, mut manifest_meta) = manifest_to_dataframe(&[]);
+
+                        if let Err(error_message) = parse_folder_lake_setting(self.text(step, "setting", "")?, &df.csv_meta) {
+                            manifest_meta.error_message = error_message;
+                        }
+
+                        Ok(Dataframe::new(byte_array, manifest_meta))
+                    }
+                    "create_folder_lake" => create_folder_lake(
+                        df,
+                        self.text(step, "setting", "")?,
+                        self.number(step, "max_open_file", 64)? as usize,
+                    ),
+                    "write_csv" => {
+                        if !self.is_dry_run {
+                            write_csv(df, self.text(step, "file_name", "%Sample.csv")?);
+                        }
+                        return Ok(None);
+                    }
+                    "write_parquet" => {
+                        let compression = self.text(step, "compression", "snappy")?;
+                        let row_group_size = self.number(step, "row_group_size", 1_000_000)?;
+
+                        if row_group_size <= 0 {
+                            return Err("** row_group_size should be larger than 0 ** \n".to_string());
+                        }
+
+                        get_parquet_compression(compression).map_err(to_error)?;
+
+                        if !self.is_dry_run {
+                            write_parquet(df, self.text(step, "file_name", "%Sample.parquet")?, compression, row_group_size as usize)
+                                .map_err(to_error)?;
+                        }
+                        return Ok(None);
+                    }
+                    "write_jsonl" => {
+                        if !self.is_dry_run {
+                            write_jsonl_file(df, self.text(step, "file_name", "%Sample.jsonl")?).map_err(|x| format!("** {} ** \n", x))?;
+                        }
+                        return Ok(None);
+                    }
+                    _ => {
+                        if !self.is_dry_run {
+                            view_csv(df);
+                        }
+                        return Ok(None);
+                    }
+                }
+            }
+        };
+
+        let dataframe = result.map_err(to_error)?;
+
+        if !dataframe.csv_meta.error_message.is_empty() {
+            return Err(dataframe.csv_meta.error_message);
+        }
+
+        if self.is_dry_run {
+            return Ok(Some(Dataframe::new(Vec::new(), dataframe.csv_meta)));
+        }
+
+        Ok(Some(dataframe))
+    }
+
+    // Returns the last dataframe assigned, and error messages prefixed by the line number.
+    // Once a step fails, later steps using its dataframe are skipped instead of reporting the same error again.
+    fn run(&mut self, script_step: &[ScriptStep]) -> (Option<Dataframe>, Vec<String>) {
+        let mut last_output = String::new();
+        let mut error_message = Vec::new();
+
+        for step in script_step {
+            let is_skipped = step.argument.values().any(|x| match x {
+                Value::Variable(y) => self.failed_dataframe.contains(y),
+                _ => false,
+            });
+
+            let result = if is_skipped { Err(String::new()) } else { self.run_step(step) };
+
+            match result {
+                Ok(Some(dataframe)) => {
+                    if !step.output.is_empty() {
+                        self.failed_dataframe.remove(&step.output);
+                        self.dataframe.insert(step.output.clone(), dataframe);
+                        last_output = step.output.clone();
+                    }
+                }
+                Ok(None) => {}
+                Err(x) => {
+                    for message in x.lines().filter(|y| !y.trim().is_empty()) {
+                        error_message.push(format!("line {}: {}", step.line, message.trim_end()));
+                    }
+
+                    if !step.output.is_empty() {
+                        self.dataframe.remove(&step.output);
+                        self.failed_dataframe.insert(step.output.clone());
+                    }
+
+                    if !self.is_dry_run {
+                        break;
+                    }
+                }
+            }
+        }
+
+        (self.dataframe.remove(&last_output), error_message)
+    }
+}
+
+// The standard input can be read only once, so a script reading "-" reads it before the dry run
+fn read_script_stdin(script_step: &[ScriptStep]) -> std::io::Result<Vec<u8>> {
+    let mut stdin = Vec::new();
+
+    if script_step.iter().any(|x| matches!(x.argument.get("file_path"), Some(Value::Text(y)) if y == "-")) {
+        std::io::stdin().read_to_end(&mut stdin)?;
+    }
+
+    Ok(stdin)
+}
+
+fn validate_script_step(script_step: &[ScriptStep], stdin: &[u8]) -> Vec<String> {
+    let keyvalue_table = save_keyvalue_registry();
+
+    let mut script_runner = ScriptRunner {
+        dataframe: HashMap::new(),
+        failed_dataframe: HashSet::new(),
+        is_dry_run: true,
+        stdin: stdin.to_vec(),
+    };
+
+    let (_, error_message) = script_runner.run(script_step);
+    restore_keyvalue_registry(keyvalue_table);
+
+    error_message
+}
+
+// Parse the script and check every command, file and column name without writing any output
+#[pyfunction]
+pub fn validate_script(script_file: &str) -> PyResult<Vec<String>> {
+    let script = fs::read_to_string(script_file)?;
+    let (script_step, mut error_message) = parse_script(&script);
+
+    if error_message.is_empty() {
+        error_message = validate_script_step(&script_step, &read_script_stdin(&script_step)?);
+    }
+
+    Ok(error_message)
+}
+
+// Validate and run a script of commands, e.g.
+//     df = read_csv("data.csv")
+//     df = filter(df, "amount(Float > 100)")
+//     summary = groupby(df, "region => count() sum(amount)")
+//     write_csv(summary, "summary.csv")
+// Nothing is run if validation fails. Returns the last dataframe assigned.
+#[pyfunction]
+pub fn run_script(script_file: &str) -> PyResult<Dataframe> {
+    let script = fs::read_to_string(script_file)?;
+    let (script_step, mut error_message) = parse_script(&script);
+    let stdin = if error_message.is_empty() { read_script_stdin(&script_step)? } else { Vec::new() };
+
+    if error_message.is_empty() {
+        error_message = validate_script_step(&script_step, &stdin);
+    }
+
+    let mut dataframe = None;
+
+    if error_message.is_empty() {
+        let mut script_runner = ScriptRunner {
+            dataframe: HashMap::new(),
+            failed_dataframe: HashSet::new(),
+            is_dry_run: false,
+            stdin,
+        };
+
+        (dataframe, error_message) = script_runner.run(&script_step);
+    }
+
+    let mut dataframe = dataframe.unwrap_or_default();
+
+    for message in error_message {
+        dataframe.csv_meta.error_message.push_str(&message);
+        dataframe.csv_meta.error_message.push_str(" \n");
+    }
+
+    Ok(dataframe)
+}
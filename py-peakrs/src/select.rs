@@ -0,0 +1,138 @@
+use crate::dataframe::Dataframe;
+use crate::keyvalue::get_composite_key;
+use crate::{cell_address, cell_per_row, cell_range, get_column_id, CsvMeta};
+use pyo3::prelude::*;
+use std::collections::HashSet;
+
+// Keep the given columns in the given order
+pub(crate) fn select_byte_array(byte_array: &[u8], csv_meta: &CsvMeta, column: &[usize]) -> Vec<u8> {
+    let cell_address = cell_address(byte_array, csv_meta);
+    let cell_per_row = cell_per_row(csv_meta);
+    let total_row = (cell_address.len() - 1) / cell_per_row;
+    let total_column = csv_meta.total_column as usize;
+    let mut result_bytes = Vec::with_capacity(byte_array.len());
+
+    for current_row in 0..total_row {
+        let first_cell = current_row * cell_per_row;
+
+        for (n, &current_column) in column.iter().enumerate() {
+            if n > 0 {
+                result_bytes.push(csv_meta.delimiter);
+            }
+
+            let start_byte = cell_address[first_cell + current_column] as usize;
+            let end_byte = cell_address[first_cell + current_column + 1] as usize - 1;
+            result_bytes.extend_from_slice(&byte_array[start_byte..end_byte]);
+        }
+
+        let line_br_byte = cell_address[first_cell + total_column] as usize - 1;
+        let end_byte = cell_address[first_cell + cell_per_row] as usize;
+        result_bytes.extend_from_slice(&byte_array[line_br_byte..end_byte]);
+    }
+
+    result_bytes
+}
+
+fn select_dataframe(df: &Dataframe, setting: &str, is_unmatch: bool) -> Dataframe {
+    match get_column_id(setting, &df.csv_meta) {
+        Ok(column) => {
+            let column: Vec<usize> = if is_unmatch {
+                (0..df.csv_meta.total_column as usize).filter(|x| !column.contains(x)).collect()
+            } else {
+                column
+            };
+
+            // A dataframe without any column has no cells to locate its rows
+            if column.is_empty() {
+                return df.with_error("** No column is left ** \n");
+            }
+
+            let result_bytes = select_byte_array(&df.byte_array, &df.csv_meta, &column);
+            let mut csv_meta = df.csv_meta.clone();
+            csv_meta.column_name = column.iter().map(|&x| df.csv_meta.column_name[x].clone()).collect();
+            csv_meta.total_column = column.len() as i32;
+            Dataframe::new(result_bytes, csv_meta)
+        }
+        Err(error_message) => df.with_error(&error_message),
+    }
+}
+
+#[pyfunction]
+pub fn select(df: &Dataframe, setting: &str) -> PyResult<Dataframe> {
+    Ok(select_dataframe(df, setting, false))
+}
+
+#[pyfunction]
+pub fn select_unmatch(df: &Dataframe, setting: &str) -> PyResult<Dataframe> {
+    Ok(select_dataframe(df, setting, true))
+}
+
+// Distinct values of the given columns, in order of first appearance
+#[pyfunction]
+pub fn distinct(df: &Dataframe, setting: &str) -> PyResult<Dataframe> {
+    let column = match get_column_id(setting, &df.csv_meta) {
+        Ok(column) => column,
+        Err(error_message) => return Ok(df.with_error(&error_message)),
+    };
+
+    let byte_array = &df.byte_array;
+    let mut csv_meta = df.csv_meta.clone();
+    let cell_address = df.cell_address();
+    let cell_per_row = cell_per_row(&csv_meta);
+    let total_row = (cell_address.len() - 1) / cell_per_row;
+    let mut distinct_key = HashSet::new();
+    let mut composite_key = Vec::new();
+    let mut result_bytes = Vec::new();
+
+    for current_row in 0..total_row {
+        let first_cell = current_row * cell_per_row;
+
+        get_composite_key(byte_array, cell_address, first_cell, &column, &mut composite_key);
+
+        if distinct_key.contains(&composite_key) {
+            continue;
+        }
+
+        distinct_key.insert(composite_key.clone());
+
+        for (n, &current_column) in column.iter().enumerate() {
+            if n > 0 {
+                result_bytes.push(csv_meta.delimiter);
+            }
+
+            let (start_byte, end_byte) = cell_range(byte_array, cell_address, first_cell + current_column);
+            result_bytes.extend_from_slice(&byte_array[start_byte..end_byte]);
+        }
+
+        if csv_meta.is_line_br_13_exist {
+            result_bytes.push(13);
+        }
+        result_bytes.push(10);
+    }
+
+    csv_meta.column_name = column.iter().map(|&x| csv_meta.column_name[x].clone()).collect();
+    csv_meta.total_column = column.len() as i32;
+    csv_meta.validate_row = distinct_key.len() as i64;
+
+    Ok(Dataframe::new(result_bytes, csv_meta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::test_dataframe;
+
+    #[test]
+    fn select_unmatch_of_every_column_is_an_error() {
+        let df = select_dataframe(&test_dataframe(b"1,2\n3,4\n", &["a", "b"]), "a, b", true);
+        assert_eq!(df.csv_meta.error_message, "** No column is left ** \n");
+        assert_eq!(df.csv_meta.total_column, 2);
+    }
+
+    #[test]
+    fn select_keeps_the_given_order() {
+        let df = select_dataframe(&test_dataframe(b"1,2\n3,4\n", &["a", "b"]), "b, a", false);
+        assert_eq!(df.byte_array, b"2,1\n4,3\n");
+        assert_eq!(df.csv_meta.column_name, vec!["b", "a"]);
+    }
+}
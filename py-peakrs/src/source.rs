@@ -0,0 +1,280 @@
+use crate::multi_file::is_glob;
+use crate::sample::{sample_by_mode, RecordSource};
+use crate::{count_row, read_all_row, read_sample, CsvMeta};
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyByteArray, PyBytes, PyString};
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+// A stream is sampled from its first 8MB, as it cannot seek to the evenly spaced offsets
+pub(crate) const STREAM_SAMPLE_SIZE: usize = 8_388_608;
+
+// A path, a glob such as sales_2023-08-*.csv or a list of paths and globs, a csv in memory such as
+// bytes, a binary file object which can seek such as io.BytesIO, a stream which cannot seek such as
+// an HTTP response, or "-" for the standard input
+pub enum FilePath {
+    One(String),
+    Many(Vec<String>),
+    Buffer(Vec<u8>),
+    Seekable(PyObject),
+    Stream(PyObject),
+    Stdin,
+}
+
+impl<'source> FromPyObject<'source> for FilePath {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        if let Ok(x) = ob.downcast::<PyString>() {
+            return Ok(FilePath::from_text(x.to_str()?));
+        }
+
+        if let Ok(x) = ob.downcast::<PyBytes>() {
+            return Ok(FilePath::Buffer(x.as_bytes().to_vec()));
+        }
+
+        if let Ok(x) = ob.downcast::<PyByteArray>() {
+            return Ok(FilePath::Buffer(x.to_vec()));
+        }
+
+        // pathlib.Path
+        if ob.hasattr("__fspath__")? {
+            return Ok(FilePath::from_text(&ob.call_method0("__fspath__")?.extract::<String>()?));
+        }
+
+        // A binary file object which can seek is sampled as a file. A text file object cannot seek
+        // to a byte offset, so it is read into memory.
+        if ob.hasattr("read")? {
+            let is_seekable = ob.hasattr("seekable")? && ob.call_method0("seekable")?.is_true()?;
+
+            if is_seekable && ob.call_method1("read", (0,))?.downcast::<PyBytes>().is_ok() {
+                return Ok(FilePath::Seekable(ob.into()));
+            }
+
+            if is_seekable {
+                return Ok(FilePath::Buffer(read_python_stream(ob, None)?));
+            }
+
+            return Ok(FilePath::Stream(ob.into()));
+        }
+
+        match ob.extract::<Vec<String>>() {
+            Ok(x) => Ok(FilePath::Many(x)),
+            Err(_) => Err(PyTypeError::new_err("filepath should be a path, a glob, a list of paths, bytes or a file object")),
+        }
+    }
+}
+
+impl FilePath {
+    pub(crate) fn from_text(text: &str) -> FilePath {
+        match text {
+            "-" => FilePath::Stdin,
+            x => FilePath::One(x.to_string()),
+        }
+    }
+
+    // One path without a glob is read as it is, without a source_file column
+    pub(crate) fn single_file(&self) -> Option<&str> {
+        match self {
+            FilePath::One(x) if !is_glob(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn file_list(&self) -> &[String] {
+        match self {
+            FilePath::One(x) => std::slice::from_ref(x),
+            FilePath::Many(x) => x,
+            _ => &[],
+        }
+    }
+
+    // The csv of a source in memory, a stream is read up to limit bytes, None for files. A file object
+    // which can seek is read in full, as a file is scanned in full by read_csv or a sample mode other than even.
+    pub(crate) fn read_memory(&mut self, py: Python, limit: Option<usize>) -> PyResult<Option<Vec<u8>>> {
+        let data = match self {
+            FilePath::One(_) | FilePath::Many(_) => return Ok(None),
+            FilePath::Buffer(x) => return Ok(Some(std::mem::take(x))),
+            FilePath::Seekable(x) => return Ok(Some(read_python_stream(x.as_ref(py), None)?)),
+            FilePath::Stream(x) => read_python_stream(x.as_ref(py), limit)?,
+            FilePath::Stdin => {
+                let mut data = Vec::new();
+                let stdin = std::io::stdin();
+                let mut reader = stdin.lock();
+
+                match limit {
+                    Some(limit) => reader.take(limit as u64).read_to_end(&mut data)?,
+                    None => reader.read_to_end(&mut data)?,
+                };
+
+                data
+            }
+        };
+
+        Ok(Some(cut_at_line_break(data, limit)))
+    }
+}
+
+// A prefix of a stream may end in the middle of a row
+fn cut_at_line_break(mut data: Vec<u8>, limit: Option<usize>) -> Vec<u8> {
+    if limit.is_some_and(|x| data.len() >= x) {
+        if let Some(n) = data.iter().rposition(|&x| x == 10) {
+            data.truncate(n + 1);
+        }
+    }
+
+    data
+}
+
+// read() of a Python file object in 1MB chunks, a text file is encoded as UTF-8
+fn read_python_stream(stream: &PyAny, limit: Option<usize>) -> PyResult<Vec<u8>> {
+    let mut data = Vec::new();
+
+    while limit.is_none_or(|x| data.len() < x) {
+        let chunk_size = limit.map_or(1_048_576, |x| (x - data.len()).min(1_048_576));
+        let chunk = stream.call_method1("read", (chunk_size,))?;
+
+        let read_count = if let Ok(x) = chunk.downcast::<PyBytes>() {
+            data.extend_from_slice(x.as_bytes());
+            x.as_bytes().len()
+        } else if let Ok(x) = chunk.downcast::<PyString>() {
+            data.extend_from_slice(x.to_str()?.as_bytes());
+            x.to_str()?.len()
+        } else if chunk.is_none() {
+            0
+        } else {
+            return Err(PyTypeError::new_err("read() of the file object should return bytes or str"));
+        };
+
+        if read_count == 0 {
+            break;
+        }
+    }
+
+    Ok(data)
+}
+
+// seek() and read() of a binary Python file object, from the position it was given at
+struct PythonFile<'py> {
+    file: &'py PyAny,
+    start_byte: u64,
+}
+
+fn to_io_error(error: PyErr) -> io::Error {
+    io::Error::other(error.to_string())
+}
+
+impl Read for PythonFile<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let chunk = self.file.call_method1("read", (buf.len(),)).map_err(to_io_error)?;
+
+        if chunk.is_none() {
+            return Ok(0);
+        }
+
+        let chunk = chunk.downcast::<PyBytes>().map_err(|e| to_io_error(e.into()))?.as_bytes();
+
+        if chunk.len() > buf.len() {
+            return Err(io::Error::other("read() of the file object returns more bytes than requested"));
+        }
+
+        buf[..chunk.len()].copy_from_slice(chunk);
+        Ok(chunk.len())
+    }
+}
+
+impl Seek for PythonFile<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(n) => self.file.call_method1("seek", (self.start_byte + n, 0)),
+            SeekFrom::End(n) => self.file.call_method1("seek", (n, 2)),
+            SeekFrom::Current(n) => self.file.call_method1("seek", (n, 1)),
+        };
+        let position = position.and_then(|x| x.extract::<u64>()).map_err(to_io_error)?;
+        Ok(position.saturating_sub(self.start_byte))
+    }
+}
+
+// Same as get_csv_sample of a file in even mode for a file object which can seek, only the rows
+// at the evenly spaced offsets are read. The file object is left at the position it was given at.
+pub(crate) fn read_seekable_sample(file: &PyAny, sample_row: i32) -> PyResult<(Vec<u8>, CsvMeta)> {
+    let start_byte = file.call_method0("tell")?.extract::<u64>()?;
+    let mut python_file = PythonFile { file, start_byte };
+    let file_size = python_file.seek(SeekFrom::End(0))?;
+
+    let result = read_sample(&mut python_file, file_size as i64, sample_row);
+    python_file.seek(SeekFrom::Start(0))?;
+    Ok(result)
+}
+
+// Same as get_csv_sample for a csv in memory
+pub(crate) fn read_buffer_sample(
+    data: &[u8],
+    sample_row: i32,
+    mode: &str,
+    seed: Option<u64>,
+    key_column: &str,
+) -> PyResult<(Vec<u8>, CsvMeta)> {
+    if mode == "even" {
+        return Ok(read_sample(&mut Cursor::new(data), data.len() as i64, sample_row));
+    }
+
+    let (_, csv_meta) = read_sample(&mut Cursor::new(data), data.len() as i64, 10);
+    sample_by_mode(&RecordSource::Buffer(data), csv_meta, sample_row, mode, seed, key_column)
+}
+
+// Same as read_csv for a csv in memory
+pub(crate) fn read_buffer_file(data: &[u8]) -> PyResult<(Vec<u8>, CsvMeta)> {
+    let (_, mut csv_meta) = read_sample(&mut Cursor::new(data), data.len() as i64, 10);
+
+    if !csv_meta.error_message.is_empty() {
+        return Ok((Vec::new(), csv_meta));
+    }
+
+    let byte_array = read_all_row(data, &mut csv_meta)?;
+    csv_meta.validate_row = count_row(&byte_array);
+    csv_meta.estimate_row = csv_meta.validate_row;
+
+    Ok((byte_array, csv_meta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seekable_sample_matches_file_sample() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let mut data = b"id,name\n".to_vec();
+            for n in 0..20_000 {
+                data.extend_from_slice(format!("{},name_{}\n", n, n).as_bytes());
+            }
+
+            // The csv starts at the position the file object is given at
+            let mut prefixed_data = b"xyz".to_vec();
+            prefixed_data.extend_from_slice(&data);
+            let file = py.import("io").unwrap().call_method1("BytesIO", (PyBytes::new(py, &prefixed_data),)).unwrap();
+            file.call_method1("seek", (3,)).unwrap();
+
+            let (seekable_sample, csv_meta) = read_seekable_sample(file, 100).unwrap();
+            let (sample, _) = read_sample(&mut Cursor::new(&data), data.len() as i64, 100);
+
+            assert_eq!(csv_meta.error_message, "");
+            assert_eq!(csv_meta.column_name, vec!["id", "name"]);
+            assert_eq!(csv_meta.file_size, data.len() as i64);
+            assert_eq!(seekable_sample, sample);
+            assert_eq!(file.call_method0("tell").unwrap().extract::<u64>().unwrap(), 3);
+        });
+    }
+
+    #[test]
+    fn buffer_is_moved_out() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let mut filepath = FilePath::Buffer(b"a,b\n1,2\n".to_vec());
+            assert_eq!(filepath.read_memory(py, None).unwrap().unwrap(), b"a,b\n1,2\n");
+            assert!(matches!(filepath, FilePath::Buffer(x) if x.is_empty()));
+        });
+    }
+}
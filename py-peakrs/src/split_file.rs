@@ -0,0 +1,212 @@
+use crate::dataframe::Dataframe;
+use crate::partition::get_header_byte_count;
+use crate::{write_csv_cell, CsvMeta};
+use pyo3::prelude::*;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+// Output file of one part, named file_name_1.csv, file_name_2.csv ...
+struct SplitPart {
+    file_name: String,
+    writer: BufWriter<File>,
+    byte_count: i64,
+    row_count: i64,
+}
+
+fn create_split_part(output_folder: &Path, file_stem: &str, extension: &str, part_number: usize, header_bytes: &[u8]) -> std::io::Result<SplitPart> {
+    let file_name = output_folder
+        .join(format!("{}_{}{}", file_stem, part_number, extension))
+        .to_string_lossy()
+        .to_string();
+
+    let mut writer = BufWriter::new(File::create(&file_name)?);
+    writer.write_all(header_bytes)?;
+
+    Ok(SplitPart {
+        file_name,
+        writer,
+        byte_count: header_bytes.len() as i64,
+        row_count: 0,
+    })
+}
+
+// Read one record; a line break inside double quotes does not end the record
+pub(crate) fn read_record<R: BufRead>(reader: &mut R, record: &mut Vec<u8>) -> std::io::Result<usize> {
+    record.clear();
+
+    let mut double_quote_count = 0;
+
+    loop {
+        let start_byte = record.len();
+
+        if reader.read_until(10, record)? == 0 {
+            break;
+        }
+
+        double_quote_count += record[start_byte..].iter().filter(|&&x| x == 34).count();
+
+        if double_quote_count % 2 == 0 {
+            break;
+        }
+    }
+
+    Ok(record.len())
+}
+
+pub(crate) fn manifest_to_dataframe(split_part: &[(String, i64, i64)]) -> (Vec<u8>, CsvMeta) {
+    let mut byte_array = Vec::new();
+
+    for (file_name, row_count, byte_count) in split_part {
+        write_csv_cell(file_name.as_bytes(), b',', &mut byte_array);
+        byte_array.extend_from_slice(format!(",{},{}\r\n", row_count, byte_count).as_bytes());
+    }
+
+    let csv_meta = CsvMeta {
+        total_column: 3,
+        validate_row: split_part.len() as i64,
+        estimate_row: split_part.len() as i64,
+        is_line_br_13_exist: true,
+        is_line_br_10_exist: true,
+        column_name: vec!["file_name".to_string(), "row_count".to_string(), "byte_count".to_string()],
+        file_size: byte_array.len() as i64,
+        delimiter: 44,
+        ..Default::default()
+    };
+
+    (byte_array, csv_meta)
+}
+
+// Cut a file into number_of_split parts at the first record boundary after file_size * n / number_of_split,
+// or into parts of at most max_bytes. Every part repeats the column name row.
+// Returns the manifest (file_name, row_count, byte_count) as a dataframe, also saved as file_name_manifest.csv.
+#[pyfunction(number_of_split = "0", max_bytes = "0", output_folder = "\"\"")]
+pub fn split_file(filepath: &str, number_of_split: i64, max_bytes: i64, output_folder: &str) -> PyResult<Dataframe> {
+    if number_of_split <= 0 && max_bytes <= 0 {
+        let (byte_array, mut csv_meta) = manifest_to_dataframe(&[]);
+        csv_meta.error_message.push_str("** Either number_of_split or max_bytes should be larger than 0 ** \n");
+        return Ok(Dataframe::new(byte_array, csv_meta));
+    }
+
+    let path = Path::new(filepath);
+    let file_stem = path.file_stem().map(|x| x.to_string_lossy().to_string()).unwrap_or_default();
+    let extension = path.extension().map(|x| format!(".{}", x.to_string_lossy())).unwrap_or_default();
+
+    let output_folder = if output_folder.is_empty() {
+        path.parent().map(|x| x.to_path_buf()).unwrap_or_default()
+    } else {
+        PathBuf::from(output_folder)
+    };
+
+    if !output_folder.as_os_str().is_empty() {
+        fs::create_dir_all(&output_folder)?;
+    }
+
+    let mut file = File::open(filepath)?;
+    let file_size = file.metadata()?.len() as i64;
+    let header_byte_count = get_header_byte_count(&mut file);
+    let mut header_bytes = vec![0; header_byte_count as usize];
+
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut header_bytes)?;
+
+    let data_size = file_size - header_byte_count;
+    let mut reader = BufReader::with_capacity(1 << 20, file);
+    let mut record = Vec::new();
+    let mut current_byte = header_byte_count;
+    let mut part_number = 1;
+    let mut split_part = Vec::new();
+    let mut current_part = create_split_part(&output_folder, &file_stem, &extension, part_number, &header_bytes)?;
+
+    while read_record(&mut reader, &mut record)? > 0 {
+        let is_new_part = if current_part.row_count == 0 {
+            false
+        } else if number_of_split > 0 {
+            (part_number as i64) < number_of_split
+                && current_byte - header_byte_count >= data_size * part_number as i64 / number_of_split
+        } else {
+            current_part.byte_count + record.len() as i64 > max_bytes
+        };
+
+        if is_new_part {
+            current_part.writer.flush()?;
+            split_part.push((current_part.file_name, current_part.row_count, current_part.byte_count));
+
+            part_number += 1;
+            current_part = create_split_part(&output_folder, &file_stem, &extension, part_number, &header_bytes)?;
+        }
+
+        current_part.writer.write_all(&record)?;
+        current_part.byte_count += record.len() as i64;
+        current_part.row_count += 1;
+        current_byte += record.len() as i64;
+    }
+
+    current_part.writer.flush()?;
+    split_part.push((current_part.file_name, current_part.row_count, current_part.byte_count));
+
+    let (byte_array, csv_meta) = manifest_to_dataframe(&split_part);
+
+    let manifest_file = output_folder.join(format!("{}_manifest.csv", file_stem));
+    let mut f = BufWriter::new(File::create(manifest_file)?);
+    f.write_all(b"file_name,row_count,byte_count\r\n")?;
+    f.write_all(&byte_array)?;
+    f.flush()?;
+
+    Ok(Dataframe::new(byte_array, csv_meta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The folder name has a comma, which the manifest should quote
+    fn split(name: &str, number_of_split: i64, max_bytes: i64) -> (PathBuf, Dataframe) {
+        let folder = std::env::temp_dir().join(format!("peakrs_split,{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+
+        let filepath = folder.join("data.csv");
+        fs::write(&filepath, b"a,b\n1,x\n2,\"p\nq\"\n3,y\n4,z\n").unwrap();
+
+        let manifest = split_file(filepath.to_str().unwrap(), number_of_split, max_bytes, "").unwrap();
+        (folder, manifest)
+    }
+
+    fn part_text(folder: &Path, part_number: usize) -> String {
+        fs::read_to_string(folder.join(format!("data_{}.csv", part_number))).unwrap()
+    }
+
+    #[test]
+    fn number_of_split_cuts_after_a_quoted_line_break() {
+        let (folder, manifest) = split("number", 2, 0);
+
+        // The middle of the rows falls inside "p<LF>q", so the cut is after that record
+        assert_eq!(part_text(&folder, 1), "a,b\n1,x\n2,\"p\nq\"\n");
+        assert_eq!(part_text(&folder, 2), "a,b\n3,y\n4,z\n");
+        assert_eq!(manifest.csv_meta.validate_row, 2);
+        assert_eq!(manifest.total_row(), 2);
+
+        let manifest_file = fs::read_to_string(folder.join("data_manifest.csv")).unwrap();
+        let part_file = folder.join("data_1.csv").to_string_lossy().to_string();
+        assert!(manifest_file.contains(&format!("\"{}\",2,16\r\n", part_file)));
+
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn max_bytes_counts_the_repeated_header() {
+        let (folder, manifest) = split("bytes", 0, 12);
+
+        assert_eq!(part_text(&folder, 1), "a,b\n1,x\n");
+        assert_eq!(part_text(&folder, 2), "a,b\n2,\"p\nq\"\n");
+        assert_eq!(part_text(&folder, 3), "a,b\n3,y\n4,z\n");
+
+        let manifest_file = fs::read_to_string(folder.join("data_manifest.csv")).unwrap();
+        let row_and_byte: Vec<&str> = manifest_file.lines().skip(1).map(|x| x.rsplit('"').next().unwrap()).collect();
+        assert_eq!(row_and_byte, vec![",1,8", ",1,12", ",2,12"]);
+        assert_eq!(manifest.csv_meta.validate_row, 3);
+
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+use crate::add_column::{add_column_byte_array, parse_add_column_setting, AddColumnSetting};
+use crate::filter::{filter_byte_array, parse_filter_setting, FilterCondition};
+use crate::partition::{get_header_byte_count, read_partition};
+use crate::select::select_byte_array;
+use crate::{count_row, get_column_id, read_csv_sample, CsvMeta};
+use pyo3::prelude::*;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+pub(crate) enum StreamOperator {
+    Filter(Vec<FilterCondition>, bool),
+    Select(Vec<usize>),
+    AddColumn(AddColumnSetting),
+}
+
+// Operators in order, each with the meta of its input partition
+pub(crate) struct StreamPlan {
+    pub(crate) operator: Vec<(StreamOperator, CsvMeta)>,
+    pub(crate) csv_meta: CsvMeta,
+}
+
+impl StreamPlan {
+    pub(crate) fn new(csv_meta: &CsvMeta) -> StreamPlan {
+        StreamPlan {
+            operator: Vec::new(),
+            csv_meta: csv_meta.clone(),
+        }
+    }
+
+    // e.g. ["filter", "amount(Float > 100)"], ["select", "id, amount"]
+    // or ["add_column", "amount, qty => multiply(total)", decimal_place, divide_by_zero]
+    pub(crate) fn push(&mut self, operation: &[String]) -> Result<(), String> {
+        let (command, setting) = match operation {
+            [command, setting, ..] => (command.as_str(), setting.as_str()),
+            _ => return Err(format!("** {:?} should be written as [command, setting] ** \n", operation)),
+        };
+
+        let input_meta = self.csv_meta.clone();
+
+        let stream_operator = match command {
+            "filter" | "filter_unmatch" => StreamOperator::Filter(
+                parse_filter_setting(setting, &self.csv_meta.column_name)?,
+                command == "filter_unmatch",
+            ),
+            "select" | "select_unmatch" => {
+                let column = get_column_id(setting, &self.csv_meta)?;
+
+                let column: Vec<usize> = if command == "select_unmatch" {
+                    (0..self.csv_meta.total_column as usize).filter(|x| !column.contains(x)).collect()
+                } else {
+                    column
+                };
+
+                if column.is_empty() {
+                    return Err("** No column is left ** \n".to_string());
+                }
+
+                self.csv_meta.column_name = column.iter().map(|&x| self.csv_meta.column_name[x].clone()).collect();
+                self.csv_meta.total_column = column.len() as i32;
+                StreamOperator::Select(column)
+            }
+            "add_column" => {
+                let decimal_place = match operation.get(2) {
+                    Some(x) => x.trim().parse().map_err(|_| format!("** decimal_place {} is not an integer ** \n", x))?,
+                    None => -1,
+                };
+                let divide_by_zero = operation.get(3).map(|x| x.as_str()).unwrap_or("null");
+                let add_column_setting = parse_add_column_setting(setting, &self.csv_meta, decimal_place, divide_by_zero)?;
+
+                self.csv_meta.column_name.push(add_column_setting.column_name.clone());
+                self.csv_meta.total_column += 1;
+                StreamOperator::AddColumn(add_column_setting)
+            }
+            _ => return Err(format!("** Command {} is not supported in a stream, use filter, select or add_column ** \n", command)),
+        };
+
+        self.operator.push((stream_operator, input_meta));
+        Ok(())
+    }
+
+    // Pass one partition through every operator, only the current input and output are kept
+    pub(crate) fn run(&self, mut byte_array: Vec<u8>) -> Result<Vec<u8>, String> {
+        for (stream_operator, csv_meta) in &self.operator {
+            if byte_array.is_empty() {
+                break;
+            }
+
+            byte_array = match stream_operator {
+                StreamOperator::Filter(filter_condition, is_unmatch) => {
+                    filter_byte_array(&byte_array, csv_meta, filter_condition, *is_unmatch)
+                }
+                StreamOperator::Select(column) => select_byte_array(&byte_array, csv_meta, column),
+                StreamOperator::AddColumn(add_column_setting) => add_column_byte_array(&byte_array, csv_meta, add_column_setting)?,
+            };
+        }
+
+        Ok(byte_array)
+    }
+}
+
+// A partition of n bytes needs about 8 * n bytes at peak: the partition, its cell addresses
+// of 8 bytes per cell and the output of the current operator
+fn get_stream_partition_size(partition_size: i64, memory_budget: i64) -> i64 {
+    let partition_size = if partition_size <= 0 { 67_108_864 } else { partition_size };
+
+    if memory_budget > 0 {
+        partition_size.min(memory_budget / 8).max(4096)
+    } else {
+        partition_size
+    }
+}
+
+// Read a file partition by partition through a chain of filter, select and add_column,
+// appending each result to output_file, so memory stays bounded whatever the file size.
+#[pyfunction(partition_size = "67_108_864", memory_budget = "0")]
+pub fn stream_file(filepath: &str, operation: Vec<Vec<String>>, output_file: &str, partition_size: i64, memory_budget: i64) -> PyResult<CsvMeta> {
+    let (_, mut csv_meta) = read_csv_sample(filepath, 10)?;
+
+    if !csv_meta.error_message.is_empty() {
+        return Ok(csv_meta);
+    }
+
+    let mut stream_plan = StreamPlan::new(&csv_meta);
+
+    for current_operation in &operation {
+        if let Err(error_message) = stream_plan.push(current_operation) {
+            csv_meta.error_message.push_str(&error_message);
+        }
+    }
+
+    if !csv_meta.error_message.is_empty() {
+        return Ok(csv_meta);
+    }
+
+    let partition_size = get_stream_partition_size(partition_size, memory_budget);
+    let output_meta = &stream_plan.csv_meta;
+
+    let mut header = output_meta.column_name.join(&(output_meta.delimiter as char).to_string());
+    header.push_str(if output_meta.is_line_br_13_exist { "\r\n" } else { "\n" });
+
+    let f = File::create(output_file)?;
+    let mut f = BufWriter::new(f);
+    f.write_all(header.as_bytes())?;
+
+    let mut file = File::open(filepath)?;
+    let mut start_byte = get_header_byte_count(&mut file);
+    let mut total_row = 0;
+
+    while start_byte < csv_meta.file_size {
+        let (byte_array, consumed_byte_count) =
+            read_partition(&mut file, start_byte, partition_size, csv_meta.file_size, &csv_meta);
+        start_byte += consumed_byte_count;
+
+        match stream_plan.run(byte_array) {
+            Ok(result_bytes) => {
+                total_row += count_row(&result_bytes);
+                f.write_all(&result_bytes)?;
+            }
+            Err(error_message) => {
+                csv_meta.error_message.push_str(&error_message);
+                break;
+            }
+        }
+    }
+
+    f.flush()?;
+
+    let error_message = csv_meta.error_message;
+    csv_meta = stream_plan.csv_meta;
+    csv_meta.error_message = error_message;
+    csv_meta.validate_row = total_row;
+    csv_meta.estimate_row = total_row;
+
+    Ok(csv_meta)
+}
@@ -0,0 +1,436 @@
+use crate::dataframe::Dataframe;
+use crate::profile::{format_table, is_null_text, text_to_dataframe};
+use crate::source::FilePath;
+use crate::{cell_per_row, cell_range, parse_real_number, read_csv, unquote_cell};
+use pyo3::class::basic::PyObjectProtocol;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+
+// Rows of a violated rule kept as examples
+const MAX_EXAMPLE: usize = 5;
+
+// The expected column of a schema, every rule but name is optional
+struct ColumnRule {
+    name: String,
+    column_type: String,
+    nullable: bool,
+    allowed: Option<Vec<String>>,
+    pattern: Option<Regex>,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+struct Schema {
+    column: Vec<ColumnRule>,
+    ordered: bool,
+    extra_column: bool,
+}
+
+// A number or boolean of allowed is compared as it is written, e.g. 1 matches a cell of 1
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(x) => x.clone(),
+        x => x.to_string(),
+    }
+}
+
+fn parse_column_rule(value: &Value) -> Result<ColumnRule, String> {
+    let object = value.as_object().ok_or("Each column of a schema should be a dict")?;
+    let name = object.get("name").and_then(|x| x.as_str()).ok_or("Each column of a schema should have a name")?;
+
+    let mut column_rule = ColumnRule {
+        name: name.to_string(),
+        column_type: "text".to_string(),
+        nullable: true,
+        allowed: None,
+        pattern: None,
+        min: None,
+        max: None,
+    };
+
+    let number = |key: &str, x: &Value| x.as_f64().ok_or(format!("{} of column {} should be a number", key, name));
+
+    for (key, x) in object {
+        match key.as_str() {
+            "name" => {}
+            "type" => match x.as_str() {
+                Some(column_type @ ("text" | "integer" | "float")) => column_rule.column_type = column_type.to_string(),
+                _ => return Err(format!("Type {} of column {} is not supported, use text, integer or float", x, name)),
+            },
+            "nullable" => column_rule.nullable = x.as_bool().ok_or(format!("nullable of column {} should be True or False", name))?,
+            "allowed" => {
+                let allowed = x.as_array().ok_or(format!("allowed of column {} should be a list", name))?;
+                column_rule.allowed = Some(allowed.iter().map(value_text).collect());
+            }
+            // A pattern matches the whole cell, e.g. [0-9]{5} does not accept 123456
+            "pattern" => {
+                let pattern = x.as_str().ok_or(format!("pattern of column {} should be a text", name))?;
+                let pattern = format!("^(?:{})$", pattern);
+                column_rule.pattern = Some(Regex::new(&pattern).map_err(|e| format!("pattern of column {} is not valid: {}", name, e))?);
+            }
+            "min" => column_rule.min = Some(number(key, x)?),
+            "max" => column_rule.max = Some(number(key, x)?),
+            _ => return Err(format!("{} of column {} is not a rule, use type, nullable, allowed, pattern, min or max", key, name)),
+        }
+    }
+
+    Ok(column_rule)
+}
+
+// A list of columns, or {"columns": [...], "ordered": True, "extra_column": False}
+fn parse_schema(value: &Value) -> Result<Schema, String> {
+    let empty = Map::new();
+
+    let (column, option) = match value {
+        Value::Array(x) => (x, &empty),
+        Value::Object(x) => (
+            x.get("columns").and_then(|x| x.as_array()).ok_or("A schema should have a list of columns")?,
+            x,
+        ),
+        _ => return Err("A schema should be a list of columns or a dict with columns".to_string()),
+    };
+
+    let mut schema = Schema {
+        column: column.iter().map(parse_column_rule).collect::<Result<_, _>>()?,
+        ordered: true,
+        extra_column: false,
+    };
+
+    for (n, column_rule) in schema.column.iter().enumerate() {
+        if schema.column[..n].iter().any(|x| x.name == column_rule.name) {
+            return Err(format!("Column {} is repeated in the schema", column_rule.name));
+        }
+    }
+
+    for (key, x) in option {
+        match key.as_str() {
+            "columns" => {}
+            "ordered" => schema.ordered = x.as_bool().ok_or("ordered should be True or False")?,
+            "extra_column" => schema.extra_column = x.as_bool().ok_or("extra_column should be True or False")?,
+            _ => return Err(format!("{} is not a schema option, use columns, ordered or extra_column", key)),
+        }
+    }
+
+    Ok(schema)
+}
+
+// A dict or list is read as it is, a text is JSON if it starts with { or [, otherwise a JSON file
+fn schema_value(py: Python, schema: &PyAny) -> PyResult<Value> {
+    let text = match schema.extract::<String>() {
+        Ok(x) if x.trim_start().starts_with(['{', '[']) => x,
+        Ok(x) => fs::read_to_string(x)?,
+        Err(_) => py.import("json")?.call_method1("dumps", (schema,))?.extract::<String>()?,
+    };
+
+    serde_json::from_str(&text).map_err(|e| PyValueError::new_err(format!("Schema is not valid JSON: {}", e)))
+}
+
+#[derive(Clone)]
+struct RuleResult {
+    column: String,
+    rule: &'static str,
+    expected: String,
+    violation: i64,
+    example: Vec<String>,
+}
+
+impl RuleResult {
+    fn new(column: &str, rule: &'static str, expected: String) -> RuleResult {
+        RuleResult {
+            column: column.to_string(),
+            rule,
+            expected,
+            violation: 0,
+            example: Vec::new(),
+        }
+    }
+
+    fn add_violation(&mut self, example: String) {
+        self.violation += 1;
+
+        if self.example.len() < MAX_EXAMPLE {
+            self.example.push(example);
+        }
+    }
+}
+
+// A null cell is checked by nullable only, a cell which is not a number by type only
+fn is_violation(rule: &str, column_rule: &ColumnRule, cell: &[u8]) -> bool {
+    let is_null = cell.is_empty() || is_null_text(cell);
+
+    match rule {
+        "nullable" => is_null,
+        _ if is_null => false,
+        "type" => parse_real_number(cell).is_none() || (column_rule.column_type == "integer" && cell.contains(&b'.')),
+        "allowed" => !column_rule.allowed.as_ref().is_some_and(|x| x.iter().any(|x| x.as_bytes() == cell)),
+        "pattern" => !column_rule.pattern.as_ref().is_some_and(|x| x.is_match(&String::from_utf8_lossy(cell))),
+        "min" => parse_real_number(cell).zip(column_rule.min).is_some_and(|(x, min)| x < min),
+        "max" => parse_real_number(cell).zip(column_rule.max).is_some_and(|(x, max)| x > max),
+        _ => false,
+    }
+}
+
+fn validate_column(df: &Dataframe, current_column: usize, column_rule: &ColumnRule) -> Vec<RuleResult> {
+    let name = column_rule.name.as_str();
+    let mut rule_result = Vec::new();
+
+    if column_rule.column_type != "text" {
+        rule_result.push(RuleResult::new(name, "type", column_rule.column_type.clone()));
+    }
+    if !column_rule.nullable {
+        rule_result.push(RuleResult::new(name, "nullable", "not null".to_string()));
+    }
+    if let Some(allowed) = &column_rule.allowed {
+        rule_result.push(RuleResult::new(name, "allowed", allowed.join(", ")));
+    }
+    if let Some(pattern) = &column_rule.pattern {
+        rule_result.push(RuleResult::new(name, "pattern", pattern.to_string()));
+    }
+    if let Some(min) = column_rule.min {
+        rule_result.push(RuleResult::new(name, "min", format!(">= {}", min)));
+    }
+    if let Some(max) = column_rule.max {
+        rule_result.push(RuleResult::new(name, "max", format!("<= {}", max)));
+    }
+
+    let cell_address = df.cell_address();
+    let cell_per_row = cell_per_row(&df.csv_meta);
+
+    for current_row in 0..df.total_row() {
+        let (start_byte, end_byte) = cell_range(&df.byte_array, cell_address, current_row * cell_per_row + current_column);
+        let cell: &[u8] = &unquote_cell(&df.byte_array[start_byte..end_byte]);
+
+        for x in rule_result.iter_mut() {
+            if is_violation(x.rule, column_rule, cell) {
+                x.add_violation(format!("row {}: {}", current_row + 1, String::from_utf8_lossy(cell)));
+            }
+        }
+    }
+
+    rule_result
+}
+
+// Columns are found by name, then each rule of a column is checked on every row
+fn validate_dataframe(df: &Dataframe, schema: &Schema) -> Validation {
+    let column_name = &df.csv_meta.column_name;
+    let mut rule_result = Vec::new();
+
+    if !df.csv_meta.error_message.is_empty() {
+        let mut x = RuleResult::new("", "read", "no error".to_string());
+        x.add_violation(df.csv_meta.error_message.trim_end().to_string());
+
+        return Validation {
+            row_count: 0,
+            rule: vec![x],
+        };
+    }
+
+    for column_rule in &schema.column {
+        let mut x = RuleResult::new(&column_rule.name, "column", "present".to_string());
+
+        if !column_name.contains(&column_rule.name) {
+            x.add_violation("not found".to_string());
+        }
+
+        rule_result.push(x);
+    }
+
+    if !schema.extra_column {
+        for (n, name) in column_name.iter().enumerate() {
+            if !schema.column.iter().any(|x| &x.name == name) {
+                let mut x = RuleResult::new(name, "extra_column", "not in schema".to_string());
+                x.add_violation(format!("found at position {}", n + 1));
+                rule_result.push(x);
+            }
+        }
+    }
+
+    // Columns of the schema found in the dataframe should be in the order of the schema
+    if schema.ordered {
+        let expected_order: Vec<&String> = schema.column.iter().map(|x| &x.name).filter(|x| column_name.contains(x)).collect();
+        let actual_order: Vec<&String> = column_name.iter().filter(|x| expected_order.contains(x)).collect();
+
+        for (n, (name, actual)) in expected_order.iter().zip(&actual_order).enumerate() {
+            let mut x = RuleResult::new(name, "order", format!("position {}", n + 1));
+
+            if actual != name {
+                let position = actual_order.iter().position(|x| x == name).unwrap_or(n);
+                x.add_violation(format!("found at position {}", position + 1));
+            }
+
+            rule_result.push(x);
+        }
+    }
+
+    for column_rule in &schema.column {
+        if let Some(current_column) = column_name.iter().position(|x| x == &column_rule.name) {
+            rule_result.extend(validate_column(df, current_column, column_rule));
+        }
+    }
+
+    Validation {
+        row_count: df.total_row() as i64,
+        rule: rule_result,
+    }
+}
+
+// Pass or fail of a dataframe against a schema, with the violation count and example rows of each rule
+#[pyclass]
+#[derive(Clone)]
+pub struct Validation {
+    row_count: i64,
+    rule: Vec<RuleResult>,
+}
+
+const RULE_COLUMN: [&str; 5] = ["column", "rule", "expected", "violation", "example"];
+
+impl Validation {
+    fn failed_rule_count(&self) -> usize {
+        self.rule.iter().filter(|x| x.violation > 0).count()
+    }
+
+    fn summary(&self) -> String {
+        match self.failed_rule_count() {
+            0 => format!("Passed, {} rules on {} rows", self.rule.len(), self.row_count),
+            n => format!("Failed, {} of {} rules violated on {} rows", n, self.rule.len(), self.row_count),
+        }
+    }
+
+    fn rule_row(&self) -> Vec<Vec<String>> {
+        self.rule
+            .iter()
+            .map(|x| vec![x.column.clone(), x.rule.to_string(), x.expected.clone(), x.violation.to_string(), x.example.join("; ")])
+            .collect()
+    }
+}
+
+#[pymethods]
+impl Validation {
+    #[getter]
+    fn get_passed(&self) -> bool {
+        self.failed_rule_count() == 0
+    }
+
+    #[getter]
+    fn get_row_count(&self) -> i64 {
+        self.row_count
+    }
+
+    #[getter]
+    fn get_violation_count(&self) -> i64 {
+        self.rule.iter().map(|x| x.violation).sum()
+    }
+
+    fn view(&self) {
+        println!();
+        println!("  {}", self.summary());
+        println!();
+        println!("{}", format_table(&RULE_COLUMN, &self.rule_row()));
+    }
+
+    // The rules as a dataframe, one row per rule
+    fn to_dataframe(&self) -> Dataframe {
+        text_to_dataframe(&RULE_COLUMN, &self.rule_row())
+    }
+
+    fn to_json(&self) -> String {
+        let rule: Vec<Value> = self
+            .rule
+            .iter()
+            .map(|x| json!({"column": x.column, "rule": x.rule, "expected": x.expected, "violation": x.violation, "example": x.example}))
+            .collect();
+
+        let validation = json!({"passed": self.get_passed(), "row_count": self.row_count, "rule": rule});
+        format!("{}\n", serde_json::to_string_pretty(&validation).unwrap_or_default())
+    }
+
+    fn write_json(&self, file_name: &str) -> PyResult<()> {
+        let mut f = BufWriter::new(File::create(file_name)?);
+        f.write_all(self.to_json().as_bytes())?;
+        f.flush()?;
+        Ok(())
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for Validation {
+    fn __str__(&self) -> String {
+        format!("  {}\n\n{}", self.summary(), format_table(&RULE_COLUMN, &self.rule_row()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Validation(passed={}, rule={}, violation={})",
+            if self.get_passed() { "True" } else { "False" },
+            self.rule.len(),
+            self.get_violation_count()
+        )
+    }
+
+    fn __bool__(&self) -> bool {
+        self.get_passed()
+    }
+}
+
+// df_or_path is a dataframe, or any source of read_csv which is then read in full. schema is a list of
+// columns such as {"name": "amount", "type": "float", "nullable": False, "min": 0}, a dict of columns
+// with ordered and extra_column, JSON text or a JSON file. A schema which is not valid raises ValueError.
+#[pyfunction]
+pub fn validate(py: Python, df_or_path: &PyAny, schema: &PyAny) -> PyResult<Validation> {
+    let schema = parse_schema(&schema_value(py, schema)?).map_err(PyValueError::new_err)?;
+
+    if let Ok(df) = df_or_path.extract::<PyRef<Dataframe>>() {
+        return Ok(validate_dataframe(&df, &schema));
+    }
+
+    let df = read_csv(py, df_or_path.extract::<FilePath>()?)?;
+    Ok(validate_dataframe(&df, &schema))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::test_dataframe;
+
+    fn schema(text: &str) -> Result<Schema, String> {
+        parse_schema(&serde_json::from_str(text).unwrap())
+    }
+
+    #[test]
+    fn repeated_schema_column_is_rejected() {
+        let error = schema(r#"[{"name": "a"}, {"name": "a"}]"#).err().unwrap();
+        assert_eq!(error, "Column a is repeated in the schema");
+    }
+
+    #[test]
+    fn repeated_dataframe_column_keeps_order_check() {
+        let df = test_dataframe(b"1,2,3\n", &["b", "a", "b"]);
+        let validation = validate_dataframe(&df, &schema(r#"[{"name": "a"}, {"name": "b"}]"#).unwrap());
+        let order: Vec<i64> = validation.rule.iter().filter(|x| x.rule == "order").map(|x| x.violation).collect();
+        assert_eq!(order, vec![1, 1]);
+    }
+
+    #[test]
+    fn type_and_order_violation() {
+        let df = test_dataframe(b"x,1\n2,y\n", &["b", "a"]);
+        let schema = schema(r#"[{"name": "a", "type": "integer"}, {"name": "b"}]"#).unwrap();
+        let validation = validate_dataframe(&df, &schema);
+        let violation = |rule: &str| validation.rule.iter().filter(|x| x.rule == rule).map(|x| x.violation).sum::<i64>();
+        assert_eq!(violation("type"), 1);
+        assert_eq!(violation("order"), 2);
+        assert_eq!(validation.row_count, 2);
+    }
+
+    #[test]
+    fn pattern_matches_the_whole_cell() {
+        let df = test_dataframe(b"12345\n123456\nA12345\n\n", &["zip"]);
+        let schema = schema(r#"[{"name": "zip", "pattern": "[0-9]{5}|[0-9]{9}"}]"#).unwrap();
+        let validation = validate_dataframe(&df, &schema);
+        let violation: i64 = validation.rule.iter().filter(|x| x.rule == "pattern").map(|x| x.violation).sum();
+        assert_eq!(violation, 2);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::dataframe::Dataframe;
+use crate::{write_csv_cell, CsvMeta};
+use calamine::{open_workbook, Data, Reader, Xlsx};
+use pyo3::prelude::*;
+
+// A whole number is written without a decimal point, as Excel stores every number as a float
+fn number_text(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        value.to_string()
+    }
+}
+
+// A date is yyyy-mm-dd, with hh:mm:ss if it has a time, a duration is h:mm:ss
+fn cell_to_text(cell: &Data) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        Data::String(x) | Data::DateTimeIso(x) | Data::DurationIso(x) => x.clone(),
+        Data::Int(x) => x.to_string(),
+        Data::Float(x) => number_text(*x),
+        Data::Bool(x) => x.to_string(),
+        Data::Error(x) => x.to_string(),
+        Data::DateTime(x) if x.is_duration() => {
+            let second = (x.as_f64() * 86400.0).round() as i64;
+            format!("{}:{:02}:{:02}", second / 3600, second % 3600 / 60, second % 60)
+        }
+        Data::DateTime(x) => match x.as_datetime() {
+            Some(datetime) if datetime.format("%H:%M:%S").to_string() == "00:00:00" => datetime.format("%Y-%m-%d").to_string(),
+            Some(datetime) => datetime.format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => number_text(x.as_f64()),
+        },
+    }
+}
+
+// The first row of the used range of a sheet is the column names, blank rows are skipped
+fn read_xlsx_file(filepath: &str, sheet: Option<&PyAny>) -> Result<(Vec<u8>, CsvMeta), String> {
+    let to_error = |x: calamine::XlsxError| format!("** File {} cannot be read as xlsx: {} ** \n", filepath, x);
+    let mut workbook: Xlsx<_> = open_workbook(filepath).map_err(to_error)?;
+    let sheet_names = workbook.sheet_names();
+
+    let sheet_name = match sheet {
+        None => sheet_names.first().cloned(),
+        Some(x) => match x.extract::<usize>() {
+            Ok(n) => sheet_names.get(n).cloned(),
+            Err(_) => x.extract::<String>().ok().filter(|x| sheet_names.contains(x)),
+        },
+    };
+
+    let sheet_name = match sheet_name {
+        Some(x) => x,
+        None => {
+            return Err(format!(
+                "** Sheet {} is not found, sheets are {} ** \n",
+                sheet.map(|x| x.to_string()).unwrap_or_default(),
+                sheet_names.join(", ")
+            ))
+        }
+    };
+
+    let range = workbook.worksheet_range(&sheet_name).map_err(to_error)?;
+    let mut row = range.rows().filter(|x| x.iter().any(|x| *x != Data::Empty));
+
+    let column_name: Vec<String> = match row.next() {
+        Some(x) => x
+            .iter()
+            .enumerate()
+            .map(|(n, x)| match cell_to_text(x).trim() {
+                "" => format!("column_{}", n + 1),
+                x => x.to_string(),
+            })
+            .collect(),
+        None => return Err(format!("** Sheet {} has no row ** \n", sheet_name)),
+    };
+
+    let mut byte_array = Vec::new();
+    let mut total_row = 0;
+
+    for current_row in row {
+        for (n, cell) in current_row.iter().enumerate() {
+            if n > 0 {
+                byte_array.push(b',');
+            }
+            write_csv_cell(cell_to_text(cell).as_bytes(), b',', &mut byte_array);
+        }
+
+        byte_array.extend_from_slice(b"\r\n");
+        total_row += 1;
+    }
+
+    let csv_meta = CsvMeta {
+        total_column: column_name.len() as i32,
+        validate_row: total_row,
+        estimate_row: total_row,
+        is_line_br_13_exist: true,
+        is_line_br_10_exist: true,
+        column_name,
+        file_size: byte_array.len() as i64,
+        delimiter: 44,
+        ..Default::default()
+    };
+
+    Ok((byte_array, csv_meta))
+}
+
+// sheet is a sheet name or a 0 based sheet number, the first sheet if not given
+#[pyfunction(sheet = "None")]
+pub fn read_xlsx(py: Python, filepath: &str, sheet: Option<PyObject>) -> PyResult<Dataframe> {
+    match read_xlsx_file(filepath, sheet.as_ref().map(|x| x.as_ref(py))) {
+        Ok((byte_array, csv_meta)) => Ok(Dataframe::new(byte_array, csv_meta)),
+        Err(error_message) => Ok(Dataframe::default().with_error(&error_message)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calamine::{ExcelDateTime, ExcelDateTimeType};
+
+    fn date_time(value: f64, datetime_type: ExcelDateTimeType) -> Data {
+        Data::DateTime(ExcelDateTime::new(value, datetime_type, false))
+    }
+
+    #[test]
+    fn whole_float_is_written_without_decimal_point() {
+        assert_eq!(number_text(3.0), "3");
+        assert_eq!(number_text(-0.0), "0");
+        assert_eq!(number_text(2.5), "2.5");
+        assert_eq!(number_text(999_999_999_999_999.0), "999999999999999");
+
+        // From 1e15 a float is written by its shortest form, which still has no decimal point
+        assert_eq!(number_text(1e15), "1000000000000000");
+        assert_eq!(number_text(1e20), "100000000000000000000");
+        assert_eq!(number_text(1e15 + 0.5), "1000000000000000.5");
+    }
+
+    #[test]
+    fn cell_text() {
+        assert_eq!(cell_to_text(&Data::Empty), "");
+        assert_eq!(cell_to_text(&Data::Int(-4)), "-4");
+        assert_eq!(cell_to_text(&Data::Float(12.0)), "12");
+        assert_eq!(cell_to_text(&Data::Bool(true)), "true");
+        assert_eq!(cell_to_text(&Data::String("a,b".to_string())), "a,b");
+    }
+
+    #[test]
+    fn date_and_duration_text() {
+        assert_eq!(cell_to_text(&date_time(45292.0, ExcelDateTimeType::DateTime)), "2024-01-01");
+        assert_eq!(cell_to_text(&date_time(45292.5, ExcelDateTimeType::DateTime)), "2024-01-01 12:00:00");
+        assert_eq!(cell_to_text(&date_time(1.5, ExcelDateTimeType::TimeDelta)), "36:00:00");
+        assert_eq!(cell_to_text(&date_time(3661.0 / 86400.0, ExcelDateTimeType::TimeDelta)), "1:01:01");
+    }
+}